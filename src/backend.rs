@@ -0,0 +1,427 @@
+//! Backends that query and change the state of WireGuard interfaces.
+//!
+//! The TUI and CLI only talk to a [`VpnBackend`], so they can run against
+//! the in-memory [`MockBackend`] on machines without WireGuard or root.
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+
+use wgctrl::types::Key;
+
+use crate::config::Config;
+use crate::profiles::{self, profile_path};
+use crate::status::{self, InterfaceStatus, PeerStatus};
+use crate::sync;
+use crate::validate;
+
+/// Environment variable selecting the backend: `wg-quick`, `netlink` or `mock`.
+pub const BACKEND_ENV: &str = "RUSTGUARD_BACKEND";
+
+/// Operations the UI needs from the system.
+///
+/// Methods return a human-readable status message, or an error message.
+pub trait VpnBackend: Send + Sync {
+    /// Short name shown in the UI.
+    fn name(&self) -> &'static str;
+
+    /// Names of the active WireGuard interfaces.
+    fn list_interfaces(&self) -> Result<Vec<String>, String>;
+
+    /// Runtime state of one interface, or None if it is not active.
+    fn interface_details(&self, interface: &str) -> Result<Option<InterfaceStatus>, String>;
+
+    /// Runtime state of every active interface, in one query.
+    fn all_interface_details(&self) -> Result<Vec<InterfaceStatus>, String>;
+
+    /// Bring a profile up.
+    fn bring_up(&self, profile: &str) -> Result<String, String>;
+
+    /// Bring a profile down.
+    fn bring_down(&self, profile: &str) -> Result<String, String>;
+
+    /// Push a config to a running interface without tearing it down.
+    fn apply_config(&self, interface: &str, config: &Config) -> Result<String, String>;
+}
+
+/// Pick a backend from `RUSTGUARD_BACKEND`, defaulting to netlink on Linux.
+pub fn from_env() -> Box<dyn VpnBackend> {
+    match std::env::var(BACKEND_ENV).as_deref() {
+        Ok("wg-quick") => Box::new(WgQuickBackend),
+        Ok("mock") => Box::new(MockBackend::from_profiles()),
+        #[cfg(target_os = "linux")]
+        _ => Box::new(NetlinkBackend),
+        #[cfg(not(target_os = "linux"))]
+        _ => Box::new(WgQuickBackend),
+    }
+}
+
+/// Toggle the VPN connection through the backend ("up" or "down").
+/// For the "up" action, validate the configuration file before calling the backend.
+/// Returns the status message, as Err if the connection could not be changed.
+pub fn toggle_vpn(backend: &dyn VpnBackend, profile: &str, action: &str) -> Result<String, String> {
+    let mut warnings = String::new();
+    let mut applied = None;
+    if action == "up" {
+        let filename = profile_path(profile);
+        let config = match Config::load(&filename) {
+            Ok(config) if config.is_empty() => {
+                return Err(format!("❌ Failed to start VPN: configuration file {} is empty.", filename));
+            }
+            Ok(config) => config,
+            Err(_) => return Err(format!("❌ Failed to read configuration file {}.", filename)),
+        };
+        let diagnostics = validate::validate(profile, &config);
+        if validate::has_errors(&diagnostics) {
+            let report: Vec<String> = diagnostics.iter().map(|d| d.to_string()).collect();
+            return Err(format!(
+                "❌ Failed to start VPN: {} has problems:\n{}",
+                filename,
+                report.join("\n")
+            ));
+        }
+        for diagnostic in diagnostics {
+            warnings.push_str(&format!("⚠️ {}\n", diagnostic));
+        }
+        applied = Some(config);
+    }
+
+    let result = if action == "up" {
+        backend.bring_up(profile)
+    } else {
+        backend.bring_down(profile)
+    };
+
+    if result.is_ok() {
+        // Remember what the interface runs with, to apply later edits live.
+        let recorded = match &applied {
+            Some(config) => sync::record_applied(profile, config),
+            None => sync::forget_applied(profile),
+        };
+        if let Err(e) = recorded {
+            warnings.push_str(&format!("⚠️ {}\n", e));
+        }
+    }
+    match result {
+        Ok(stdout) => Ok(format!("✅ {} VPN {} successfully\n{}{}", profile, action, warnings, stdout)),
+        Err(stderr) => Err(format!("❌ Failed to {} VPN {}:\n{}", action, profile, stderr)),
+    }
+}
+
+/// Run a command, returning its stdout on success and stderr on failure.
+fn run(command: &mut Command, input: Option<&str>) -> Result<String, String> {
+    let program = format!("{:?}", command.get_program());
+    let mut child = command
+        .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to execute {}: {}", program, e))?;
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        stdin
+            .write_all(input.as_bytes())
+            .map_err(|e| format!("Failed to write to {}: {}", program, e))?;
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("Failed to execute {}: {}", program, e))?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).to_string())
+    }
+}
+
+/// Drives `wg-quick` and `wg` through sudo.
+pub struct WgQuickBackend;
+
+impl WgQuickBackend {
+    fn wg_quick(action: &str, profile: &str) -> Result<String, String> {
+        run(Command::new("sudo").args(["wg-quick", action, profile]), None)
+    }
+}
+
+impl VpnBackend for WgQuickBackend {
+    fn name(&self) -> &'static str {
+        "wg-quick"
+    }
+
+    fn list_interfaces(&self) -> Result<Vec<String>, String> {
+        Ok(status::query_all_wg()?.into_iter().map(|i| i.name).collect())
+    }
+
+    fn interface_details(&self, interface: &str) -> Result<Option<InterfaceStatus>, String> {
+        Ok(status::query_all_wg()?.into_iter().find(|i| i.name == interface))
+    }
+
+    fn all_interface_details(&self) -> Result<Vec<InterfaceStatus>, String> {
+        status::query_all_wg()
+    }
+
+    fn bring_up(&self, profile: &str) -> Result<String, String> {
+        Self::wg_quick("up", profile)
+    }
+
+    fn bring_down(&self, profile: &str) -> Result<String, String> {
+        Self::wg_quick("down", profile)
+    }
+
+    fn apply_config(&self, interface: &str, config: &Config) -> Result<String, String> {
+        run(
+            Command::new("sudo").args(["wg", "syncconf", interface, "/dev/stdin"]),
+            Some(&config.strip().to_string()),
+        )
+    }
+}
+
+/// Reads interface state over netlink; changes still go through wg-quick,
+/// which also manages addresses, routes and DNS.
+#[cfg(target_os = "linux")]
+pub struct NetlinkBackend;
+
+#[cfg(target_os = "linux")]
+impl VpnBackend for NetlinkBackend {
+    fn name(&self) -> &'static str {
+        "netlink"
+    }
+
+    fn list_interfaces(&self) -> Result<Vec<String>, String> {
+        Ok(status::query_all()?.into_iter().map(|i| i.name).collect())
+    }
+
+    fn interface_details(&self, interface: &str) -> Result<Option<InterfaceStatus>, String> {
+        status::query(interface)
+    }
+
+    fn all_interface_details(&self) -> Result<Vec<InterfaceStatus>, String> {
+        status::query_all()
+    }
+
+    fn bring_up(&self, profile: &str) -> Result<String, String> {
+        WgQuickBackend.bring_up(profile)
+    }
+
+    fn bring_down(&self, profile: &str) -> Result<String, String> {
+        WgQuickBackend.bring_down(profile)
+    }
+
+    fn apply_config(&self, interface: &str, config: &Config) -> Result<String, String> {
+        WgQuickBackend.apply_config(interface, config)
+    }
+}
+
+#[derive(Default)]
+struct MockState {
+    profiles: HashMap<String, Config>,
+    active: BTreeMap<String, InterfaceStatus>,
+    calls: Vec<String>,
+}
+
+/// An in-memory backend for tests and for trying the UI without WireGuard.
+#[derive(Default)]
+pub struct MockBackend {
+    state: Mutex<MockState>,
+}
+
+impl MockBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A mock preloaded with every profile in the configuration directory.
+    pub fn from_profiles() -> Self {
+        let mock = Self::new();
+        for profile in profiles::list_vpn_profiles() {
+            if let Ok(config) = Config::load(profile_path(&profile)) {
+                mock.add_profile(&profile, config);
+            }
+        }
+        mock
+    }
+
+    /// Make a profile known to the mock.
+    pub fn add_profile(&self, profile: &str, config: Config) {
+        self.lock().profiles.insert(profile.to_string(), config);
+    }
+
+    /// Forget a profile, so bringing it up fails as with a missing file.
+    pub fn remove_profile(&self, profile: &str) {
+        self.lock().profiles.remove(profile);
+    }
+
+    /// Every operation performed so far, e.g. `"up wg0"`.
+    pub fn calls(&self) -> Vec<String> {
+        self.lock().calls.clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MockState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// The state an interface would have after loading `config`.
+    fn status_from_config(name: &str, config: &Config) -> InterfaceStatus {
+        let interface = config.interface().map(|s| s.as_interface()).unwrap_or_default();
+        let public_key = interface
+            .private_key
+            .as_deref()
+            .and_then(|k| Key::try_from(k).ok())
+            .map(|k| String::from(k.public_key()));
+        InterfaceStatus {
+            name: name.to_string(),
+            public_key,
+            private_key: interface.private_key,
+            listen_port: interface.listen_port.unwrap_or(51820),
+            fwmark: interface.fw_mark.unwrap_or(0),
+            peers: config
+                .peers()
+                .map(|section| {
+                    let peer = section.as_peer();
+                    PeerStatus {
+                        public_key: peer.public_key.unwrap_or_default(),
+                        preshared_key: peer.preshared_key,
+                        endpoint: peer.endpoint.and_then(|e| e.parse().ok()),
+                        allowed_ips: peer.allowed_ips,
+                        persistent_keepalive: peer.persistent_keepalive.filter(|k| *k > 0),
+                        ..Default::default()
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+impl VpnBackend for MockBackend {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    fn list_interfaces(&self) -> Result<Vec<String>, String> {
+        Ok(self.lock().active.keys().cloned().collect())
+    }
+
+    fn interface_details(&self, interface: &str) -> Result<Option<InterfaceStatus>, String> {
+        Ok(self.lock().active.get(interface).cloned())
+    }
+
+    fn all_interface_details(&self) -> Result<Vec<InterfaceStatus>, String> {
+        Ok(self.lock().active.values().cloned().collect())
+    }
+
+    fn bring_up(&self, profile: &str) -> Result<String, String> {
+        let mut state = self.lock();
+        state.calls.push(format!("up {}", profile));
+        if state.active.contains_key(profile) {
+            return Err(format!("wg-quick: `{}' already exists", profile));
+        }
+        let config = state
+            .profiles
+            .get(profile)
+            .ok_or_else(|| format!("wg-quick: `{}' does not exist", profile))?;
+        let status = Self::status_from_config(profile, config);
+        state.active.insert(profile.to_string(), status);
+        Ok(String::new())
+    }
+
+    fn bring_down(&self, profile: &str) -> Result<String, String> {
+        let mut state = self.lock();
+        state.calls.push(format!("down {}", profile));
+        match state.active.remove(profile) {
+            Some(_) => Ok(String::new()),
+            None => Err(format!("wg-quick: `{}' is not a WireGuard interface", profile)),
+        }
+    }
+
+    fn apply_config(&self, interface: &str, config: &Config) -> Result<String, String> {
+        let mut state = self.lock();
+        state.calls.push(format!("apply {}", interface));
+        if !state.active.contains_key(interface) {
+            return Err(format!("Unable to access interface: {}", interface));
+        }
+        let status = Self::status_from_config(interface, config);
+        state.active.insert(interface.to_string(), status);
+        Ok(String::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SectionKind;
+    use crate::testutil::{server_config, TempProfile, TestDirs};
+
+    fn mock_with(profile: &TempProfile) -> MockBackend {
+        let mock = MockBackend::new();
+        mock.add_profile(&profile.name, Config::load(profile.path()).expect("fixture is readable"));
+        mock
+    }
+
+    #[test]
+    fn toggle_brings_a_valid_profile_up_and_down() {
+        let dirs = TestDirs::new();
+        let profile = dirs.profile("rgt-toggle", &server_config());
+        let mock = mock_with(&profile);
+
+        let msg = toggle_vpn(&mock, &profile.name, "up").expect("up succeeds");
+        assert!(msg.starts_with("✅ rgt-toggle VPN up successfully"), "{}", msg);
+        assert_eq!(mock.list_interfaces(), Ok(vec![profile.name.clone()]));
+        assert!(sync::applied_config(&profile.name).is_some());
+
+        toggle_vpn(&mock, &profile.name, "down").expect("down succeeds");
+        assert_eq!(mock.list_interfaces(), Ok(vec![]));
+        assert!(sync::applied_config(&profile.name).is_none());
+        assert_eq!(mock.calls(), ["up rgt-toggle", "down rgt-toggle"]);
+    }
+
+    #[test]
+    fn toggle_refuses_invalid_profiles_without_calling_the_backend() {
+        let dirs = TestDirs::new();
+        let profile = dirs.profile("rgt-invalid", "[Interface]\nListenPort = 70000\n");
+        let mock = mock_with(&profile);
+        let msg = toggle_vpn(&mock, &profile.name, "up").unwrap_err();
+        assert!(msg.contains("has problems"), "{}", msg);
+
+        let empty = dirs.profile("rgt-empty", "");
+        assert!(toggle_vpn(&mock, &empty.name, "up").unwrap_err().contains("is empty"));
+        let missing = dirs.missing("rgt-missing");
+        assert!(toggle_vpn(&mock, &missing.name, "up").unwrap_err().contains("Failed to read"));
+        assert!(mock.calls().is_empty());
+    }
+
+    #[test]
+    fn toggle_reports_backend_failures() {
+        let dirs = TestDirs::new();
+        let profile = dirs.profile("rgt-fail", &server_config());
+        let mock = mock_with(&profile);
+        let msg = toggle_vpn(&mock, &profile.name, "down").unwrap_err();
+        assert!(msg.starts_with("❌ Failed to down VPN rgt-fail"), "{}", msg);
+
+        toggle_vpn(&mock, &profile.name, "up").expect("up succeeds");
+        assert!(toggle_vpn(&mock, &profile.name, "up").unwrap_err().contains("already exists"));
+        assert_eq!(mock.calls(), ["down rgt-fail", "up rgt-fail", "up rgt-fail"]);
+    }
+
+    #[test]
+    fn mock_status_follows_the_applied_config() {
+        let mock = MockBackend::new();
+        let config = Config::parse(&server_config());
+        mock.add_profile("wg9", config.clone());
+        assert!(mock.apply_config("wg9", &config).is_err());
+
+        mock.bring_up("wg9").expect("up succeeds");
+        let status = mock.interface_details("wg9").unwrap().expect("wg9 is up");
+        assert_eq!(status.listen_port, 51820);
+        assert_eq!(status.peers.len(), 2);
+        assert_eq!(status.peers[1].persistent_keepalive, Some(25));
+
+        let mut edited = config.clone();
+        edited.interface_mut().unwrap().set("ListenPort", "51821");
+        let stale = edited.sections().iter().rposition(|s| s.kind == SectionKind::Peer).unwrap();
+        edited.remove_section(stale);
+        mock.apply_config("wg9", &edited).expect("apply succeeds");
+        let status = mock.interface_details("wg9").unwrap().expect("wg9 is up");
+        assert_eq!(status.listen_port, 51821);
+        assert_eq!(status.peers.len(), 1);
+        assert_eq!(mock.calls(), ["apply wg9", "up wg9", "apply wg9"]);
+    }
+}
@@ -0,0 +1,177 @@
+//! The backups screen: previous versions of a profile, with a preview and a
+//! way to restore them.
+
+use std::fs;
+use std::time::SystemTime;
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+    Frame,
+};
+
+use rustguard::profiles::{backup_path, list_backups, restore_backup, Backup};
+use rustguard::status::format_age;
+
+use crate::highlight;
+use crate::prompt::{self, PromptState};
+
+/// State of the backups screen.
+pub struct BackupsState {
+    pub profile: String,
+    backups: Vec<Backup>,
+    selected: usize,
+    /// Contents of the selected backup.
+    preview: Result<Vec<String>, String>,
+    confirm: Option<PromptState<()>>,
+    message: Option<(String, Color)>,
+}
+
+impl BackupsState {
+    pub fn new(profile: String) -> Self {
+        let backups = list_backups(&profile);
+        let mut state = Self {
+            profile,
+            backups,
+            selected: 0,
+            preview: Ok(Vec::new()),
+            confirm: None,
+            message: None,
+        };
+        state.load_preview();
+        state
+    }
+
+    fn load_preview(&mut self) {
+        self.preview = match self.backups.get(self.selected) {
+            Some(backup) => fs::read_to_string(&backup.path)
+                .map(|text| text.lines().map(str::to_string).collect())
+                .map_err(|e| format!("Failed to read {}: {}", backup.path, e)),
+            None => Ok(Vec::new()),
+        };
+    }
+
+    fn select(&mut self, index: usize) {
+        self.selected = index.min(self.backups.len().saturating_sub(1));
+        self.load_preview();
+    }
+
+    /// Restore the selected backup. Returns a status message.
+    fn restore(&mut self) -> Option<String> {
+        let backup = self.backups.get(self.selected)?.clone();
+        let (msg, color) = match restore_backup(&self.profile, &backup) {
+            Ok(outcome) => (
+                outcome.report(format!("Restored {} from the backup of {} ago", self.profile, age(&backup))),
+                Color::Green,
+            ),
+            Err(e) => (e, Color::Red),
+        };
+        // The version that was replaced is now the newest backup.
+        self.backups = list_backups(&self.profile);
+        self.select(0);
+        self.message = Some((msg.clone(), color));
+        Some(msg)
+    }
+
+    /// Handle a key press.
+    ///
+    /// Returns whether the screen should close, and a message for the status
+    /// log when a backup was restored.
+    pub fn handle_event(&mut self, key: KeyEvent) -> (bool, Option<String>) {
+        if let Some(confirm) = &mut self.confirm {
+            return match confirm.handle_event(key) {
+                None => (false, None),
+                Some(answer) => {
+                    self.confirm = None;
+                    (false, answer.and_then(|_| self.restore()))
+                }
+            };
+        }
+
+        self.message = None;
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => return (true, None),
+            KeyCode::Up | KeyCode::Char('k') => self.select(self.selected.saturating_sub(1)),
+            KeyCode::Down | KeyCode::Char('j') => self.select(self.selected + 1),
+            KeyCode::Home | KeyCode::Char('g') => self.select(0),
+            KeyCode::End | KeyCode::Char('G') => self.select(usize::MAX),
+            KeyCode::Enter if !self.backups.is_empty() => {
+                let question = format!(
+                    "Replace {} with the version saved {} ago? The current version is backed up first.",
+                    self.profile,
+                    age(&self.backups[self.selected])
+                );
+                self.confirm = Some(PromptState::confirm((), "Restore Backup", &question));
+            }
+            _ => {}
+        }
+        (false, None)
+    }
+}
+
+fn age(backup: &Backup) -> String {
+    format_age(SystemTime::now().duration_since(backup.saved_at).unwrap_or_default())
+}
+
+/// Draw the backups screen.
+pub fn draw(f: &mut Frame, area: Rect, state: &BackupsState) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(3)].as_ref())
+        .split(area);
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Length(28), Constraint::Min(20)].as_ref())
+        .split(chunks[0]);
+
+    let title = format!(" Backups of {} ({}) ", state.profile, state.backups.len());
+    let list_block = Block::default().title(title).borders(Borders::ALL);
+    if state.backups.is_empty() {
+        let empty = Paragraph::new(format!(
+            "No backups yet. A backup is kept in {} every time the profile is saved.",
+            backup_path()
+        ))
+        .wrap(Wrap { trim: true })
+        .style(Style::default().fg(Color::DarkGray))
+        .block(list_block);
+        f.render_widget(empty, columns[0]);
+    } else {
+        let items: Vec<ListItem> = state
+            .backups
+            .iter()
+            .map(|backup| ListItem::new(format!("{} ago", age(backup))))
+            .collect();
+        let list = List::new(items)
+            .block(list_block)
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD | Modifier::REVERSED));
+        let mut list_state = ListState::default().with_selected(Some(state.selected));
+        f.render_stateful_widget(list, columns[0], &mut list_state);
+    }
+
+    let preview_title = match state.backups.get(state.selected) {
+        Some(backup) => format!(" {} ", backup.path),
+        None => " Preview ".to_string(),
+    };
+    let preview_block = Block::default().title(preview_title).borders(Borders::ALL);
+    let preview = match &state.preview {
+        Ok(lines) => {
+            let text: Vec<Line> = highlight::highlight(lines).into_iter().map(Line::from).collect();
+            Paragraph::new(text).block(preview_block)
+        }
+        Err(e) => Paragraph::new(e.clone()).style(Style::default().fg(Color::Red)).block(preview_block),
+    };
+    f.render_widget(preview, columns[1]);
+
+    let footer = match &state.message {
+        Some((message, color)) => Paragraph::new(message.clone()).style(Style::default().fg(*color)),
+        None => Paragraph::new("↑/k, ↓/j: Select | Enter: Restore | Esc: Back"),
+    };
+    f.render_widget(footer.block(Block::default().borders(Borders::ALL)), chunks[1]);
+
+    if let Some(confirm) = &state.confirm {
+        prompt::draw(f, area, confirm);
+    }
+}
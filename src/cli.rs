@@ -0,0 +1,320 @@
+//! Non-interactive subcommands, for use from shell scripts and cron jobs.
+//!
+//! Exit codes: 0 on success, 1 when the requested operation failed and 2 for
+//! usage errors.
+
+use std::io::Read;
+
+use rustguard::backend::{toggle_vpn, VpnBackend};
+use rustguard::keys;
+use rustguard::logging::{log_file_path, log_status};
+use rustguard::profiles::list_vpn_profiles;
+use rustguard::status::{get_active_vpns, get_vpn_details};
+use rustguard::sync;
+
+pub const EXIT_OK: i32 = 0;
+pub const EXIT_FAILURE: i32 = 1;
+pub const EXIT_USAGE: i32 = 2;
+
+pub const USAGE: &str = "Usage: rustguard [COMMAND]
+
+Without a command, the interactive manager is started.
+
+Commands:
+  list                 List available profiles
+  up <profile>         Bring a profile up
+  down <profile>       Bring a profile down
+  toggle <profile>     Bring a profile up if it is down, or down if it is up
+  apply <profile>      Apply edits of an active profile without reconnecting
+  status [--json]      Show which profiles are active
+  show <profile>       Show runtime details of an active profile
+  edit <profile>       Open a profile in the config editor
+  genkey               Print a new private key
+  pubkey               Read a private key on stdin and print its public key
+  genpsk               Print a new preshared key
+  help                 Show this help";
+
+/// A parsed subcommand.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Command {
+    List,
+    Up(String),
+    Down(String),
+    Toggle(String),
+    Apply(String),
+    Status { json: bool },
+    Show(String),
+    Edit(String),
+    GenKey,
+    PubKey,
+    GenPsk,
+    Help,
+}
+
+/// Parse the command line arguments (without the program name).
+///
+/// Returns Ok(None) when no subcommand was given and the TUI should start.
+pub fn parse(args: &[String]) -> Result<Option<Command>, String> {
+    let Some((name, rest)) = args.split_first() else {
+        return Ok(None);
+    };
+    let profile_arg = |rest: &[String]| match rest {
+        [profile] => Ok(profile.clone()),
+        [] => Err(format!("'{}' requires a profile name", name)),
+        _ => Err(format!("'{}' takes a single profile name", name)),
+    };
+    let no_args = |command: Command| {
+        if rest.is_empty() {
+            Ok(command)
+        } else {
+            Err(format!("'{}' takes no arguments", name))
+        }
+    };
+    let command = match name.as_str() {
+        "list" | "ls" => no_args(Command::List)?,
+        "up" => Command::Up(profile_arg(rest)?),
+        "down" => Command::Down(profile_arg(rest)?),
+        "toggle" => Command::Toggle(profile_arg(rest)?),
+        "apply" => Command::Apply(profile_arg(rest)?),
+        "status" => match rest {
+            [] => Command::Status { json: false },
+            [flag] if flag == "--json" => Command::Status { json: true },
+            _ => return Err(format!("unexpected arguments to 'status': {}", rest.join(" "))),
+        },
+        "show" => Command::Show(profile_arg(rest)?),
+        "edit" => Command::Edit(profile_arg(rest)?),
+        "genkey" => no_args(Command::GenKey)?,
+        "pubkey" => no_args(Command::PubKey)?,
+        "genpsk" => no_args(Command::GenPsk)?,
+        "help" | "-h" | "--help" => Command::Help,
+        other => return Err(format!("unknown command '{}'", other)),
+    };
+    Ok(Some(command))
+}
+
+/// Check that a profile exists, printing an error if it does not.
+pub fn require_profile(profile: &str) -> bool {
+    if list_vpn_profiles().iter().any(|p| p == profile) {
+        true
+    } else {
+        eprintln!("rustguard: no such profile '{}'", profile);
+        false
+    }
+}
+
+/// Run a non-interactive command and return the process exit code.
+///
+/// `Command::Edit` needs the terminal UI and is handled by the caller.
+pub fn run(backend: &dyn VpnBackend, command: Command) -> i32 {
+    match command {
+        Command::List => {
+            for profile in list_vpn_profiles() {
+                println!("{}", profile);
+            }
+            EXIT_OK
+        }
+        Command::Up(profile) => connect(backend, &profile, "up"),
+        Command::Down(profile) => connect(backend, &profile, "down"),
+        Command::Toggle(profile) => match active_vpns(backend) {
+            Some(active) if active.contains(&profile) => connect(backend, &profile, "down"),
+            Some(_) => connect(backend, &profile, "up"),
+            None => EXIT_FAILURE,
+        },
+        Command::Apply(profile) => {
+            if !require_profile(&profile) {
+                return EXIT_FAILURE;
+            }
+            report(sync::apply_live(backend, &profile))
+        }
+        Command::Status { json } => match active_vpns(backend) {
+            Some(active) => {
+                status(&active, json);
+                EXIT_OK
+            }
+            None => EXIT_FAILURE,
+        },
+        Command::Show(profile) => {
+            if !require_profile(&profile) {
+                return EXIT_FAILURE;
+            }
+            let Some(active) = active_vpns(backend) else {
+                return EXIT_FAILURE;
+            };
+            if !active.contains(&profile) {
+                eprintln!("rustguard: {} is not active", profile);
+                return EXIT_FAILURE;
+            }
+            print!("{}", get_vpn_details(backend, &profile));
+            EXIT_OK
+        }
+        Command::Edit(_) => EXIT_USAGE,
+        Command::GenKey => print_key(keys::generate_private_key()),
+        Command::PubKey => {
+            let mut private_key = String::new();
+            if let Err(e) = std::io::stdin().read_to_string(&mut private_key) {
+                eprintln!("rustguard: failed to read private key: {}", e);
+                return EXIT_FAILURE;
+            }
+            print_key(keys::public_key(&private_key))
+        }
+        Command::GenPsk => print_key(keys::generate_preshared_key()),
+        Command::Help => {
+            println!("{}", USAGE);
+            EXIT_OK
+        }
+    }
+}
+
+/// Active interfaces, printing an error if they cannot be determined.
+fn active_vpns(backend: &dyn VpnBackend) -> Option<Vec<String>> {
+    get_active_vpns(backend)
+        .map_err(|e| eprintln!("rustguard: {}", e))
+        .ok()
+}
+
+fn print_key(key: Result<String, String>) -> i32 {
+    match key {
+        Ok(key) => {
+            println!("{}", key);
+            EXIT_OK
+        }
+        Err(e) => {
+            eprintln!("rustguard: {}", e);
+            EXIT_FAILURE
+        }
+    }
+}
+
+fn connect(backend: &dyn VpnBackend, profile: &str, action: &str) -> i32 {
+    if !require_profile(profile) {
+        return EXIT_FAILURE;
+    }
+    report(toggle_vpn(backend, profile, action))
+}
+
+/// Log and print the outcome of a connection change.
+fn report(result: Result<String, String>) -> i32 {
+    let (msg, code) = match result {
+        Ok(msg) => (msg, EXIT_OK),
+        Err(msg) => (msg, EXIT_FAILURE),
+    };
+    if let Err(e) = log_status(&msg) {
+        eprintln!("rustguard: failed to write to {}: {}", log_file_path(), e);
+    }
+    if code == EXIT_OK {
+        println!("{}", msg.trim_end());
+    } else {
+        eprintln!("{}", msg.trim_end());
+    }
+    code
+}
+
+fn status(active: &[String], json: bool) {
+    let profiles = list_vpn_profiles();
+
+    if json {
+        let entries: Vec<String> = profiles
+            .iter()
+            .map(|p| format!("{{\"name\":{},\"active\":{}}}", json_string(p), active.contains(p)))
+            .collect();
+        let active: Vec<String> = active.iter().map(|a| json_string(a)).collect();
+        println!("{{\"profiles\":[{}],\"active\":[{}]}}", entries.join(","), active.join(","));
+    } else {
+        for profile in &profiles {
+            let state = if active.contains(profile) { "up" } else { "down" };
+            println!("{:<16} {}", profile, state);
+        }
+        for interface in active.iter().filter(|a| !profiles.contains(a)) {
+            println!("{:<16} up (no profile)", interface);
+        }
+    }
+}
+
+/// Quote a string as a JSON string literal.
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustguard::backend::MockBackend;
+    use rustguard::config::Config;
+    use rustguard::testutil::{server_config, TestDirs};
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_commands_and_rejects_bad_usage() {
+        assert_eq!(parse(&[]), Ok(None));
+        assert_eq!(parse(&args(&["up", "wg0"])), Ok(Some(Command::Up("wg0".into()))));
+        assert_eq!(parse(&args(&["status", "--json"])), Ok(Some(Command::Status { json: true })));
+        assert_eq!(parse(&args(&["--help"])), Ok(Some(Command::Help)));
+        assert!(parse(&args(&["up"])).is_err());
+        assert!(parse(&args(&["up", "wg0", "wg1"])).is_err());
+        assert!(parse(&args(&["genkey", "x"])).is_err());
+        assert!(parse(&args(&["status", "--yaml"])).is_err());
+        assert!(parse(&args(&["frobnicate"])).is_err());
+    }
+
+    #[test]
+    fn connection_commands_exit_with_the_outcome() {
+        let dirs = TestDirs::new();
+        let profile = dirs.profile("wg0", &server_config());
+        let mock = MockBackend::new();
+        mock.add_profile(&profile.name, Config::parse(&server_config()));
+        let name = || profile.name.clone();
+
+        assert_eq!(run(&mock, Command::Down(name())), EXIT_FAILURE);
+        assert_eq!(run(&mock, Command::Up(name())), EXIT_OK);
+        assert_eq!(run(&mock, Command::Show(name())), EXIT_OK);
+        assert_eq!(run(&mock, Command::Apply(name())), EXIT_OK);
+        assert_eq!(run(&mock, Command::Toggle(name())), EXIT_OK);
+        assert_eq!(run(&mock, Command::Show(name())), EXIT_FAILURE);
+        assert_eq!(run(&mock, Command::Apply(name())), EXIT_FAILURE);
+        assert_eq!(run(&mock, Command::Toggle(name())), EXIT_OK);
+        assert_eq!(mock.calls(), ["down wg0", "up wg0", "down wg0", "up wg0"]);
+    }
+
+    #[test]
+    fn unknown_profiles_fail_without_calling_the_backend() {
+        let _dirs = TestDirs::new();
+        let mock = MockBackend::new();
+        for command in [Command::Up, Command::Down, Command::Toggle, Command::Apply, Command::Show] {
+            assert_eq!(run(&mock, command("wg0".into())), EXIT_FAILURE);
+        }
+        assert!(mock.calls().is_empty());
+    }
+
+    #[test]
+    fn other_commands() {
+        let _dirs = TestDirs::new();
+        let mock = MockBackend::new();
+        assert_eq!(run(&mock, Command::Status { json: true }), EXIT_OK);
+        assert_eq!(run(&mock, Command::List), EXIT_OK);
+        assert_eq!(run(&mock, Command::GenKey), EXIT_OK);
+        assert_eq!(run(&mock, Command::Help), EXIT_OK);
+        assert_eq!(run(&mock, Command::Edit("wg0".into())), EXIT_USAGE);
+        assert!(mock.calls().is_empty());
+    }
+
+    #[test]
+    fn json_strings_are_escaped() {
+        assert_eq!(json_string("wg0"), "\"wg0\"");
+        assert_eq!(json_string("a\"b\\c\n\u{1}"), "\"a\\\"b\\\\c\\n\\u0001\"");
+    }
+}
@@ -0,0 +1,137 @@
+//! Generating configs for new clients of a server profile.
+
+use std::net::IpAddr;
+
+use crate::config::{Config, SectionKind};
+use crate::keys::{generate_key_pair, generate_preshared_key, public_key};
+use crate::validate::check_endpoint;
+
+/// Keepalive given to clients, which are usually behind NAT.
+const CLIENT_KEEPALIVE: &str = "25";
+
+/// How many addresses of a subnet are tried before giving up.
+const MAX_CANDIDATES: u128 = 1 << 16;
+
+/// Add a new client named `name` to the `server` profile and return the
+/// client's config.
+///
+/// The client gets a fresh key pair, a preshared key shared with the server
+/// and the first free address in each subnet of the server's `Address`. It
+/// connects to the server at `endpoint` and routes all traffic through it.
+pub fn add_client(server: &mut Config, name: &str, endpoint: &str) -> Result<Config, String> {
+    check_endpoint(endpoint).map_err(|e| format!("Endpoint: {}", e))?;
+    let interface = server.interface().ok_or("The profile has no [Interface] section")?;
+    let server_private_key = interface.get("PrivateKey").ok_or("The profile has no PrivateKey")?;
+    let server_public_key = public_key(server_private_key)?;
+    let dns = interface.get("DNS").map(str::to_string);
+
+    let used: Vec<IpAddr> = std::iter::once(interface.get_list("Address"))
+        .chain(server.peers().map(|peer| peer.get_list("AllowedIPs")))
+        .flatten()
+        .filter_map(|a| parse_cidr(&a).map(|(ip, _)| ip))
+        .collect();
+    let addresses: Vec<IpAddr> = interface
+        .get_list("Address")
+        .iter()
+        .filter_map(|a| parse_cidr(a))
+        .filter_map(|(ip, prefix)| next_free(ip, prefix, &used))
+        .collect();
+    if addresses.is_empty() {
+        return Err("The profile has no Address with room for another client".into());
+    }
+    let host_routes: Vec<String> = addresses
+        .iter()
+        .map(|ip| format!("{}/{}", ip, if ip.is_ipv4() { 32 } else { 128 }))
+        .collect();
+    let routes = if addresses.iter().any(IpAddr::is_ipv6) { "0.0.0.0/0, ::/0" } else { "0.0.0.0/0" };
+
+    let keys = generate_key_pair()?;
+    let preshared_key = generate_preshared_key()?;
+
+    let mut client = Config::new();
+    let section = client.add_section(SectionKind::Interface);
+    section.set("PrivateKey", &keys.private_key);
+    section.set("Address", &host_routes.join(", "));
+    if let Some(dns) = &dns {
+        section.set("DNS", dns);
+    }
+    let section = client.add_section(SectionKind::Peer);
+    section.set("PublicKey", &server_public_key);
+    section.set("PresharedKey", &preshared_key);
+    section.set("Endpoint", endpoint);
+    section.set("AllowedIPs", routes);
+    section.set("PersistentKeepalive", CLIENT_KEEPALIVE);
+
+    let peer = server.add_section(SectionKind::Peer);
+    peer.set("PublicKey", &keys.public_key);
+    peer.set("PresharedKey", &preshared_key);
+    peer.set("AllowedIPs", &host_routes.join(", "));
+    peer.set_friendly_name(name);
+    Ok(client)
+}
+
+fn parse_cidr(value: &str) -> Option<(IpAddr, u8)> {
+    match value.split_once('/') {
+        Some((ip, prefix)) => Some((ip.parse().ok()?, prefix.parse().ok()?)),
+        None => {
+            let ip: IpAddr = value.parse().ok()?;
+            Some((ip, if ip.is_ipv4() { 32 } else { 128 }))
+        }
+    }
+}
+
+/// The lowest host address of the subnet of `ip` that is not in `used`.
+fn next_free(ip: IpAddr, prefix: u8, used: &[IpAddr]) -> Option<IpAddr> {
+    let (value, bits) = match ip {
+        IpAddr::V4(ip) => (u128::from(u32::from(ip)), 32),
+        IpAddr::V6(ip) => (u128::from(ip), 128),
+    };
+    let host_bits = bits - u32::from(prefix.min(bits as u8));
+    let host_mask = 1u128.checked_shl(host_bits).map_or(u128::MAX, |size| size - 1);
+    let network = value & !host_mask;
+    // Skip the network address, and the broadcast address of IPv4 subnets.
+    let last = if bits == 32 { host_mask.saturating_sub(1) } else { host_mask };
+    (1..=last.min(MAX_CANDIDATES))
+        .map(|offset| match ip {
+            IpAddr::V4(_) => IpAddr::from(((network + offset) as u32).to_be_bytes()),
+            IpAddr::V6(_) => IpAddr::from((network + offset).to_be_bytes()),
+        })
+        .find(|candidate| !used.contains(candidate))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(value: &str) -> IpAddr {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_addresses_with_and_without_prefix() {
+        assert_eq!(parse_cidr("10.0.0.1/24"), Some((ip("10.0.0.1"), 24)));
+        assert_eq!(parse_cidr("10.0.0.1"), Some((ip("10.0.0.1"), 32)));
+        assert_eq!(parse_cidr("fd00::1"), Some((ip("fd00::1"), 128)));
+        assert_eq!(parse_cidr("10.0.0.1/x"), None);
+        assert_eq!(parse_cidr("vpn.example.com/24"), None);
+    }
+
+    #[test]
+    fn finds_the_lowest_free_host_address() {
+        assert_eq!(next_free(ip("10.0.0.1"), 24, &[ip("10.0.0.1")]), Some(ip("10.0.0.2")));
+        let used = [ip("10.0.0.1"), ip("10.0.0.2"), ip("10.0.0.4")];
+        assert_eq!(next_free(ip("10.0.0.1"), 24, &used), Some(ip("10.0.0.3")));
+        assert_eq!(next_free(ip("fd00::1"), 64, &[ip("fd00::1")]), Some(ip("fd00::2")));
+    }
+
+    #[test]
+    fn full_subnets_have_no_free_address() {
+        assert_eq!(next_free(ip("10.0.0.1"), 32, &[ip("10.0.0.1")]), None);
+        assert_eq!(next_free(ip("fd00::1"), 128, &[ip("fd00::1")]), None);
+        // The broadcast address 10.0.0.3 is never given out.
+        assert_eq!(next_free(ip("10.0.0.1"), 30, &[ip("10.0.0.1"), ip("10.0.0.2")]), None);
+        assert_eq!(next_free(ip("10.0.0.1"), 30, &[ip("10.0.0.1")]), Some(ip("10.0.0.2")));
+        // The last address of an IPv6 subnet is an ordinary host.
+        assert_eq!(next_free(ip("fd00::1"), 126, &[ip("fd00::1"), ip("fd00::2")]), Some(ip("fd00::3")));
+    }
+}
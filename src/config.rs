@@ -0,0 +1,709 @@
+//! Typed model of wg-quick configuration files.
+//!
+//! A [`Config`] keeps every physical line of the file it was parsed from, so
+//! serializing an untouched config yields the original bytes, including
+//! comments, blank lines, key ordering and keys this module does not know.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::ops::Range;
+use std::path::Path;
+
+/// Keys accepted by wg-quick in the `[Interface]` section.
+pub const INTERFACE_KEYS: &[&str] = &[
+    "PrivateKey",
+    "ListenPort",
+    "FwMark",
+    "Address",
+    "DNS",
+    "MTU",
+    "Table",
+    "PreUp",
+    "PostUp",
+    "PreDown",
+    "PostDown",
+    "SaveConfig",
+];
+
+/// Keys accepted by wg-quick in a `[Peer]` section.
+pub const PEER_KEYS: &[&str] = &[
+    "PublicKey",
+    "PresharedKey",
+    "AllowedIPs",
+    "Endpoint",
+    "PersistentKeepalive",
+];
+
+/// `[Interface]` keys that only wg-quick understands; `wg setconf` rejects them.
+pub const WG_QUICK_ONLY_KEYS: &[&str] = &[
+    "Address",
+    "DNS",
+    "MTU",
+    "Table",
+    "PreUp",
+    "PostUp",
+    "PreDown",
+    "PostDown",
+    "SaveConfig",
+];
+
+/// Lexical class of a token within a single config line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TokenKind {
+    /// A `[Section]` header, brackets included.
+    Section,
+    /// The key of a `Key = Value` line.
+    Key,
+    /// The `=` separating key and value.
+    Equals,
+    /// The value of a `Key = Value` line.
+    Value,
+    /// A `#` comment running to the end of the line.
+    Comment,
+    /// Text that is neither a section header nor a key/value pair.
+    Invalid,
+}
+
+/// A token and the byte range it covers in its line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub range: Range<usize>,
+}
+
+/// Split a single config line into tokens.
+///
+/// Whitespace between tokens is not emitted and a trailing `\r` is ignored, so
+/// the ranges always point at meaningful text.
+pub fn tokenize_line(line: &str) -> Vec<Token> {
+    let line = line.strip_suffix('\r').unwrap_or(line);
+    let mut tokens = Vec::new();
+
+    let (content_end, comment) = match line.find('#') {
+        Some(pos) => (pos, Some(pos..line.len())),
+        None => (line.len(), None),
+    };
+    let content = &line[..content_end];
+    let start = content.len() - content.trim_start().len();
+    let end = content.trim_end().len();
+
+    if start < end {
+        let text = &content[start..end];
+        if text.starts_with('[') && text.ends_with(']') {
+            tokens.push(Token { kind: TokenKind::Section, range: start..end });
+        } else if let Some(eq) = text.find('=') {
+            let key = &text[..eq];
+            let key_end = start + key.trim_end().len();
+            if key.trim().is_empty() {
+                tokens.push(Token { kind: TokenKind::Invalid, range: start..end });
+            } else {
+                tokens.push(Token { kind: TokenKind::Key, range: start..key_end });
+                tokens.push(Token { kind: TokenKind::Equals, range: start + eq..start + eq + 1 });
+                let value = &text[eq + 1..];
+                let value_start = start + eq + 1 + (value.len() - value.trim_start().len());
+                if value_start < end {
+                    tokens.push(Token { kind: TokenKind::Value, range: value_start..end });
+                }
+            }
+        } else {
+            tokens.push(Token { kind: TokenKind::Invalid, range: start..end });
+        }
+    }
+    if let Some(range) = comment {
+        tokens.push(Token { kind: TokenKind::Comment, range });
+    }
+    tokens
+}
+
+/// What a single line of a config file contains.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LineKind {
+    Blank,
+    Comment,
+    /// A section header; holds the name between the brackets.
+    Section(String),
+    Field { key: String, value: String },
+    Invalid,
+}
+
+impl LineKind {
+    fn classify(raw: &str) -> Self {
+        let tokens = tokenize_line(raw);
+        let text = |token: &Token| raw[token.range.clone()].to_string();
+        match tokens.first().map(|t| t.kind) {
+            None => LineKind::Blank,
+            Some(TokenKind::Comment) => LineKind::Comment,
+            Some(TokenKind::Section) => {
+                let header = text(&tokens[0]);
+                LineKind::Section(header[1..header.len() - 1].trim().to_string())
+            }
+            Some(TokenKind::Key) => LineKind::Field {
+                key: text(&tokens[0]),
+                value: tokens
+                    .iter()
+                    .find(|t| t.kind == TokenKind::Value)
+                    .map(text)
+                    .unwrap_or_default(),
+            },
+            Some(_) => LineKind::Invalid,
+        }
+    }
+}
+
+/// A single physical line of a config file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Line {
+    /// 1-based line number in the parsed text, or 0 for lines added afterwards.
+    pub number: usize,
+    pub kind: LineKind,
+    raw: String,
+}
+
+impl Line {
+    fn parse(number: usize, raw: &str) -> Self {
+        Self { number, kind: LineKind::classify(raw), raw: raw.to_string() }
+    }
+
+    fn field(key: &str, value: &str) -> Self {
+        Self {
+            number: 0,
+            kind: LineKind::Field { key: key.to_string(), value: value.to_string() },
+            raw: format!("{} = {}", key, value),
+        }
+    }
+
+    /// The line exactly as it appears in the file.
+    pub fn raw(&self) -> &str {
+        &self.raw
+    }
+
+    /// Returns `(key, value)` if this line is a `Key = Value` field.
+    pub fn as_field(&self) -> Option<(&str, &str)> {
+        match &self.kind {
+            LineKind::Field { key, value } => Some((key, value)),
+            _ => None,
+        }
+    }
+
+    fn is_key(&self, wanted: &str) -> bool {
+        self.as_field().is_some_and(|(key, _)| key.eq_ignore_ascii_case(wanted))
+    }
+
+    /// The value of a `# Name = ...` comment.
+    fn name_comment(&self) -> Option<&str> {
+        if self.kind != LineKind::Comment {
+            return None;
+        }
+        let (key, value) = self.raw.trim_start().strip_prefix('#')?.split_once('=')?;
+        key.trim().eq_ignore_ascii_case("Name").then(|| value.trim())
+    }
+}
+
+/// The kind of a `[Section]`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SectionKind {
+    Interface,
+    Peer,
+    Unknown,
+}
+
+impl SectionKind {
+    /// The kind of section a `[name]` header introduces.
+    pub fn from_name(name: &str) -> Self {
+        if name.eq_ignore_ascii_case("Interface") {
+            SectionKind::Interface
+        } else if name.eq_ignore_ascii_case("Peer") {
+            SectionKind::Peer
+        } else {
+            SectionKind::Unknown
+        }
+    }
+
+    /// The keys wg-quick accepts in this kind of section.
+    pub fn known_keys(self) -> &'static [&'static str] {
+        match self {
+            SectionKind::Interface => INTERFACE_KEYS,
+            SectionKind::Peer => PEER_KEYS,
+            SectionKind::Unknown => &[],
+        }
+    }
+}
+
+/// A `[Section]` header together with the lines following it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Section {
+    pub kind: SectionKind,
+    header: Line,
+    lines: Vec<Line>,
+}
+
+impl Section {
+    fn new(kind: SectionKind) -> Self {
+        let name = match kind {
+            SectionKind::Interface => "Interface",
+            SectionKind::Peer => "Peer",
+            SectionKind::Unknown => "Unknown",
+        };
+        Self { kind, header: Line::parse(0, &format!("[{}]", name)), lines: Vec::new() }
+    }
+
+    /// The section name as written between the brackets.
+    pub fn name(&self) -> &str {
+        match &self.header.kind {
+            LineKind::Section(name) => name,
+            _ => "",
+        }
+    }
+
+    /// The header line.
+    pub fn header(&self) -> &Line {
+        &self.header
+    }
+
+    /// The lines below the header, up to the next section.
+    pub fn lines(&self) -> &[Line] {
+        &self.lines
+    }
+
+    /// Iterate over the `(key, value, line)` fields of this section.
+    pub fn fields(&self) -> impl Iterator<Item = (&str, &str, &Line)> {
+        self.lines
+            .iter()
+            .filter_map(|line| line.as_field().map(|(key, value)| (key, value, line)))
+    }
+
+    /// The value of a key, matched case-insensitively.
+    ///
+    /// When a key is repeated the last value wins, as it does for `wg setconf`.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.lines
+            .iter()
+            .rev()
+            .find(|line| line.is_key(key))
+            .and_then(|line| line.as_field().map(|(_, value)| value))
+    }
+
+    /// Every comma-separated item of a key, across all its occurrences.
+    pub fn get_list(&self, key: &str) -> Vec<String> {
+        self.lines
+            .iter()
+            .filter(|line| line.is_key(key))
+            .filter_map(|line| line.as_field().map(|(_, value)| value))
+            .flat_map(|value| value.split(','))
+            .map(|item| item.trim().to_string())
+            .filter(|item| !item.is_empty())
+            .collect()
+    }
+
+    /// Every value of a key that may be repeated, such as `PostUp`.
+    pub fn get_all(&self, key: &str) -> Vec<String> {
+        self.lines
+            .iter()
+            .filter(|line| line.is_key(key))
+            .filter_map(|line| line.as_field().map(|(_, value)| value.to_string()))
+            .collect()
+    }
+
+    /// Set a key to a single value.
+    ///
+    /// The first existing occurrence is rewritten in place and any further
+    /// occurrences are dropped; otherwise the field is added after the last
+    /// field of the section.
+    pub fn set(&mut self, key: &str, value: &str) {
+        match self.lines.iter().position(|line| line.is_key(key)) {
+            Some(index) => {
+                let number = self.lines[index].number;
+                let written_key = self.lines[index]
+                    .as_field()
+                    .map(|(k, _)| k.to_string())
+                    .unwrap_or_else(|| key.to_string());
+                self.lines[index] = Line { number, ..Line::field(&written_key, value) };
+                let mut seen = 0;
+                self.lines.retain(|line| {
+                    if line.is_key(key) {
+                        seen += 1;
+                        seen == 1
+                    } else {
+                        true
+                    }
+                });
+            }
+            None => self.append(key, value),
+        }
+    }
+
+    /// Add a field after the last field of the section, keeping any existing
+    /// occurrences of the key.
+    pub fn append(&mut self, key: &str, value: &str) {
+        let index = self
+            .lines
+            .iter()
+            .rposition(|line| line.as_field().is_some())
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        self.lines.insert(index, Line::field(key, value));
+    }
+
+    /// Remove every occurrence of a key. Returns true if anything was removed.
+    pub fn remove(&mut self, key: &str) -> bool {
+        let before = self.lines.len();
+        self.lines.retain(|line| !line.is_key(key));
+        before != self.lines.len()
+    }
+
+    /// A friendly name given in a `# Name = ...` comment inside the section.
+    ///
+    /// wg-quick ignores comments, so this is the usual way to label peers.
+    pub fn friendly_name(&self) -> Option<&str> {
+        self.lines
+            .iter()
+            .find_map(Line::name_comment)
+            .filter(|value| !value.is_empty())
+    }
+
+    /// Set the `# Name = ...` comment, replacing an existing one or adding it
+    /// right below the header.
+    pub fn set_friendly_name(&mut self, name: &str) {
+        let comment = Line::parse(0, &format!("# Name = {}", name));
+        match self.lines.iter().position(|line| line.name_comment().is_some()) {
+            Some(index) => self.lines[index] = Line { number: self.lines[index].number, ..comment },
+            None => self.lines.insert(0, comment),
+        }
+    }
+
+    /// Typed view of an `[Interface]` section.
+    pub fn as_interface(&self) -> Interface {
+        Interface {
+            private_key: self.get("PrivateKey").map(str::to_string),
+            listen_port: self.get("ListenPort").and_then(|v| v.parse().ok()),
+            fw_mark: self.get("FwMark").and_then(parse_fwmark),
+            address: self.get_list("Address"),
+            dns: self.get_list("DNS"),
+            mtu: self.get("MTU").and_then(|v| v.parse().ok()),
+            table: self.get("Table").map(str::to_string),
+            pre_up: self.get_all("PreUp"),
+            post_up: self.get_all("PostUp"),
+            pre_down: self.get_all("PreDown"),
+            post_down: self.get_all("PostDown"),
+            save_config: self.get("SaveConfig").is_some_and(|v| v.eq_ignore_ascii_case("true")),
+        }
+    }
+
+    /// Typed view of a `[Peer]` section.
+    pub fn as_peer(&self) -> Peer {
+        Peer {
+            public_key: self.get("PublicKey").map(str::to_string),
+            preshared_key: self.get("PresharedKey").map(str::to_string),
+            allowed_ips: self.get_list("AllowedIPs"),
+            endpoint: self.get("Endpoint").map(str::to_string),
+            persistent_keepalive: self.get("PersistentKeepalive").and_then(parse_keepalive),
+        }
+    }
+
+    fn ends_with_blank(&self) -> bool {
+        self.lines.last().is_some_and(|line| line.kind == LineKind::Blank)
+    }
+}
+
+/// Parse a `FwMark` value: decimal, `0x` hexadecimal or `off`.
+pub fn parse_fwmark(value: &str) -> Option<u32> {
+    let value = value.trim();
+    if value.eq_ignore_ascii_case("off") {
+        Some(0)
+    } else if let Some(hex) = value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")) {
+        u32::from_str_radix(hex, 16).ok()
+    } else {
+        value.parse().ok()
+    }
+}
+
+/// Parse a `PersistentKeepalive` value: seconds or `off`.
+pub fn parse_keepalive(value: &str) -> Option<u16> {
+    let value = value.trim();
+    if value.eq_ignore_ascii_case("off") {
+        Some(0)
+    } else {
+        value.parse().ok()
+    }
+}
+
+/// Typed contents of an `[Interface]` section.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Interface {
+    pub private_key: Option<String>,
+    pub listen_port: Option<u16>,
+    pub fw_mark: Option<u32>,
+    pub address: Vec<String>,
+    pub dns: Vec<String>,
+    pub mtu: Option<u16>,
+    pub table: Option<String>,
+    pub pre_up: Vec<String>,
+    pub post_up: Vec<String>,
+    pub pre_down: Vec<String>,
+    pub post_down: Vec<String>,
+    pub save_config: bool,
+}
+
+/// Typed contents of a `[Peer]` section.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Peer {
+    pub public_key: Option<String>,
+    pub preshared_key: Option<String>,
+    pub allowed_ips: Vec<String>,
+    pub endpoint: Option<String>,
+    pub persistent_keepalive: Option<u16>,
+}
+
+/// A parsed wg-quick configuration file.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Config {
+    /// Lines before the first section header.
+    preamble: Vec<Line>,
+    sections: Vec<Section>,
+    trailing_newline: bool,
+}
+
+impl Config {
+    /// An empty config.
+    pub fn new() -> Self {
+        Self { trailing_newline: true, ..Self::default() }
+    }
+
+    /// Parse config text. Parsing never fails: lines that cannot be understood
+    /// are kept as [`LineKind::Invalid`] so they can be reported and written back.
+    pub fn parse(text: &str) -> Self {
+        let mut config = Config::default();
+        if text.is_empty() {
+            return config;
+        }
+        let body = match text.strip_suffix('\n') {
+            Some(body) => {
+                config.trailing_newline = true;
+                body
+            }
+            None => text,
+        };
+        for (index, raw) in body.split('\n').enumerate() {
+            let line = Line::parse(index + 1, raw);
+            if let LineKind::Section(name) = &line.kind {
+                let kind = SectionKind::from_name(name);
+                config.sections.push(Section { kind, header: line, lines: Vec::new() });
+            } else if let Some(section) = config.sections.last_mut() {
+                section.lines.push(line);
+            } else {
+                config.preamble.push(line);
+            }
+        }
+        config
+    }
+
+    /// Read and parse a config file.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::parse(&fs::read_to_string(path)?))
+    }
+
+    /// True when the config has no sections and no fields.
+    pub fn is_empty(&self) -> bool {
+        self.sections.is_empty() && self.preamble.iter().all(|line| line.as_field().is_none())
+    }
+
+    /// Lines before the first section header.
+    pub fn preamble(&self) -> &[Line] {
+        &self.preamble
+    }
+
+    /// All sections in file order.
+    pub fn sections(&self) -> &[Section] {
+        &self.sections
+    }
+
+    /// All sections in file order, mutably.
+    pub fn sections_mut(&mut self) -> &mut [Section] {
+        &mut self.sections
+    }
+
+    /// The first `[Interface]` section.
+    pub fn interface(&self) -> Option<&Section> {
+        self.sections.iter().find(|s| s.kind == SectionKind::Interface)
+    }
+
+    /// The first `[Interface]` section, mutably.
+    pub fn interface_mut(&mut self) -> Option<&mut Section> {
+        self.sections.iter_mut().find(|s| s.kind == SectionKind::Interface)
+    }
+
+    /// All `[Peer]` sections.
+    pub fn peers(&self) -> impl Iterator<Item = &Section> {
+        self.sections.iter().filter(|s| s.kind == SectionKind::Peer)
+    }
+
+    /// All `[Peer]` sections, mutably.
+    pub fn peers_mut(&mut self) -> impl Iterator<Item = &mut Section> {
+        self.sections.iter_mut().filter(|s| s.kind == SectionKind::Peer)
+    }
+
+    /// Append a new, empty section, separated from the previous one by a blank line.
+    pub fn add_section(&mut self, kind: SectionKind) -> &mut Section {
+        match self.sections.last_mut() {
+            Some(last) if !last.ends_with_blank() => last.lines.push(Line::parse(0, "")),
+            None if self.preamble.last().is_some_and(|l| l.kind != LineKind::Blank) => {
+                self.preamble.push(Line::parse(0, ""))
+            }
+            _ => {}
+        }
+        self.trailing_newline = true;
+        self.sections.push(Section::new(kind));
+        self.sections.last_mut().expect("section was just pushed")
+    }
+
+    /// Remove the section at `index` (as returned by [`Config::sections`]).
+    pub fn remove_section(&mut self, index: usize) -> Section {
+        self.sections.remove(index)
+    }
+
+    /// A copy without the wg-quick-only keys, suitable for `wg setconf` and
+    /// `wg syncconf` (the equivalent of `wg-quick strip`).
+    pub fn strip(&self) -> Config {
+        let mut stripped = self.clone();
+        for section in stripped.sections.iter_mut().filter(|s| s.kind == SectionKind::Interface) {
+            for key in WG_QUICK_ONLY_KEYS {
+                section.remove(key);
+            }
+        }
+        stripped
+    }
+
+    /// Every line of the file in order.
+    pub fn lines(&self) -> impl Iterator<Item = &Line> {
+        self.preamble.iter().chain(
+            self.sections
+                .iter()
+                .flat_map(|section| std::iter::once(&section.header).chain(section.lines.iter())),
+        )
+    }
+}
+
+impl fmt::Display for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, line) in self.lines().enumerate() {
+            if index > 0 {
+                f.write_str("\n")?;
+            }
+            f.write_str(&line.raw)?;
+        }
+        if self.trailing_newline && self.lines().next().is_some() {
+            f.write_str("\n")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "# Managed by hand\n\
+        [Interface]\n\
+        PrivateKey = GHuMwljFfqd2a7cs6BaUOmHflK23zME8VNvC5B37S3k=\n\
+        Address = 10.0.0.1/24, fd00::1/64\n\
+        ListenPort = 51820\n\
+        PostUp = iptables -A FORWARD -i %i -j ACCEPT\n\
+        \n\
+        [Peer]\n\
+        # Name = laptop\n\
+        PublicKey = aPxGwq8zERHQ3Q1cOZFdJ+cvJX5Ka4mLN38AyYKYF10=\n\
+        AllowedIPs = 10.0.0.2/32\n\
+        AllowedIPs = 10.0.1.0/24\n";
+
+    fn round_trip(text: &str) {
+        assert_eq!(Config::parse(text).to_string(), text);
+    }
+
+    #[test]
+    fn untouched_configs_round_trip() {
+        round_trip("");
+        round_trip("\n");
+        round_trip(SAMPLE);
+        round_trip(SAMPLE.trim_end_matches('\n'));
+        // Odd spacing, comments after values, unknown keys and sections, invalid lines.
+        round_trip("  [Interface]  # main\nPrivateKey=abc=\n\tListenPort  =   51820 # port\nFoo = bar\n\n\n");
+        round_trip("[Unknown]\nnot a field\n= no key\n[Peer]\nPublicKey = x\nPublicKey = y\n");
+    }
+
+    #[test]
+    fn crlf_input_round_trips_and_parses_without_carriage_returns() {
+        let text = "[Interface]\r\nListenPort = 51820\r\n\r\n[Peer]\r\nPublicKey = x\r\n";
+        round_trip(text);
+        let config = Config::parse(text);
+        assert_eq!(config.interface().unwrap().get("ListenPort"), Some("51820"));
+        assert_eq!(config.peers().next().unwrap().get("PublicKey"), Some("x"));
+    }
+
+    #[test]
+    fn duplicate_keys_are_kept_and_the_last_value_wins() {
+        let config = Config::parse(SAMPLE);
+        let peer = config.peers().next().unwrap();
+        assert_eq!(peer.get("AllowedIPs"), Some("10.0.1.0/24"));
+        assert_eq!(peer.get_list("allowedips"), ["10.0.0.2/32", "10.0.1.0/24"]);
+        assert_eq!(config.interface().unwrap().get_list("Address"), ["10.0.0.1/24", "fd00::1/64"]);
+    }
+
+    #[test]
+    fn set_rewrites_the_first_occurrence_in_place_and_drops_the_rest() {
+        let mut config = Config::parse(SAMPLE);
+        config.peers_mut().next().unwrap().set("allowedips", "10.0.0.9/32");
+        let expected = SAMPLE.replace("AllowedIPs = 10.0.0.2/32\nAllowedIPs = 10.0.1.0/24", "AllowedIPs = 10.0.0.9/32");
+        assert_eq!(config.to_string(), expected);
+
+        let mut config = Config::parse(SAMPLE);
+        config.interface_mut().unwrap().set("MTU", "1420");
+        let expected = SAMPLE.replace("ACCEPT\n", "ACCEPT\nMTU = 1420\n");
+        assert_eq!(config.to_string(), expected);
+    }
+
+    #[test]
+    fn append_adds_after_the_last_field_and_keeps_existing_values() {
+        let mut config = Config::parse(SAMPLE);
+        config.interface_mut().unwrap().append("PostUp", "echo up");
+        let expected = SAMPLE.replace("ACCEPT\n", "ACCEPT\nPostUp = echo up\n");
+        assert_eq!(config.to_string(), expected);
+        assert_eq!(config.interface().unwrap().get_all("PostUp").len(), 2);
+    }
+
+    #[test]
+    fn remove_drops_every_occurrence() {
+        let mut config = Config::parse(SAMPLE);
+        let peer = config.peers_mut().next().unwrap();
+        assert!(peer.remove("AllowedIPs"));
+        assert!(!peer.remove("Endpoint"));
+        assert_eq!(config.to_string(), SAMPLE.replace("AllowedIPs = 10.0.0.2/32\nAllowedIPs = 10.0.1.0/24\n", ""));
+    }
+
+    #[test]
+    fn set_friendly_name_replaces_or_adds_the_name_comment() {
+        let mut config = Config::parse(SAMPLE);
+        config.peers_mut().next().unwrap().set_friendly_name("phone");
+        assert_eq!(config.to_string(), SAMPLE.replace("# Name = laptop", "# Name = phone"));
+        assert_eq!(config.peers().next().unwrap().friendly_name(), Some("phone"));
+
+        let mut config = Config::parse("[Peer]\nPublicKey = x\n");
+        config.peers_mut().next().unwrap().set_friendly_name("server");
+        assert_eq!(config.to_string(), "[Peer]\n# Name = server\nPublicKey = x\n");
+    }
+
+    #[test]
+    fn add_section_separates_sections_with_a_blank_line() {
+        let mut config = Config::parse("[Interface]\nListenPort = 51820");
+        config.add_section(SectionKind::Peer).set("PublicKey", "x");
+        assert_eq!(config.to_string(), "[Interface]\nListenPort = 51820\n\n[Peer]\nPublicKey = x\n");
+    }
+
+    #[test]
+    fn strip_removes_wg_quick_only_keys() {
+        let stripped = Config::parse(SAMPLE).strip().to_string();
+        assert!(!stripped.contains("Address"));
+        assert!(!stripped.contains("PostUp"));
+        assert!(stripped.contains("ListenPort = 51820"));
+    }
+}
@@ -0,0 +1,530 @@
+//! The VPN details screen: interface summary, throughput history and a
+//! sortable peer table, updated from the background poller's snapshots.
+
+use std::cmp::Ordering;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Row, Sparkline, Table, TableState},
+    Frame,
+};
+use rustguard::backend::VpnBackend;
+use rustguard::config::Config;
+use rustguard::profiles::profile_path;
+use rustguard::status::{format_age, format_bytes, handshake_age, InterfaceStatus, PeerStatus};
+
+/// Handshakes older than this mean the session has expired (REJECT_AFTER_TIME).
+const HANDSHAKE_STALE: Duration = Duration::from_secs(180);
+
+/// Handshakes older than this are considered dead.
+const HANDSHAKE_DEAD: Duration = Duration::from_secs(600);
+
+/// Rate samples kept per interface and peer: five minutes at the default poll interval.
+const HISTORY_LEN: usize = 300;
+
+/// Transfer rates derived from successive rx/tx counter readings.
+#[derive(Default)]
+struct RateHistory {
+    last: Option<(Instant, u64, u64)>,
+    /// Bytes per second, oldest first.
+    rx: VecDeque<u64>,
+    tx: VecDeque<u64>,
+}
+
+impl RateHistory {
+    fn record(&mut self, at: Instant, rx: u64, tx: u64) {
+        if let Some((then, last_rx, last_tx)) = self.last {
+            let secs = at.duration_since(then).as_secs_f64();
+            if secs > 0.0 {
+                // Counters restart from zero when an interface is recreated.
+                let rate = |now: u64, before: u64| (now.saturating_sub(before) as f64 / secs) as u64;
+                self.rx.push_back(rate(rx, last_rx));
+                self.tx.push_back(rate(tx, last_tx));
+                while self.rx.len() > HISTORY_LEN {
+                    self.rx.pop_front();
+                    self.tx.pop_front();
+                }
+            }
+        }
+        self.last = Some((at, rx, tx));
+    }
+
+    fn rx_rate(&self) -> u64 {
+        self.rx.back().copied().unwrap_or(0)
+    }
+
+    fn tx_rate(&self) -> u64 {
+        self.tx.back().copied().unwrap_or(0)
+    }
+}
+
+fn format_rate(bytes_per_sec: u64) -> String {
+    format!("{}/s", format_bytes(bytes_per_sec))
+}
+
+/// The most recent samples that fit in `width` columns.
+fn tail(samples: &VecDeque<u64>, width: u16) -> Vec<u64> {
+    let skip = samples.len().saturating_sub(width as usize);
+    samples.iter().skip(skip).copied().collect()
+}
+
+/// Columns of the peer table, in display order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Column {
+    Name,
+    PublicKey,
+    Endpoint,
+    AllowedIps,
+    Handshake,
+    Received,
+    Sent,
+    RxRate,
+    TxRate,
+    Keepalive,
+}
+
+const COLUMNS: [Column; 10] = [
+    Column::Name,
+    Column::PublicKey,
+    Column::Endpoint,
+    Column::AllowedIps,
+    Column::Handshake,
+    Column::Received,
+    Column::Sent,
+    Column::RxRate,
+    Column::TxRate,
+    Column::Keepalive,
+];
+
+impl Column {
+    fn title(self) -> &'static str {
+        match self {
+            Column::Name => "Name",
+            Column::PublicKey => "Public key",
+            Column::Endpoint => "Endpoint",
+            Column::AllowedIps => "Allowed IPs",
+            Column::Handshake => "Handshake",
+            Column::Received => "Rx",
+            Column::Sent => "Tx",
+            Column::RxRate => "Rx/s",
+            Column::TxRate => "Tx/s",
+            Column::Keepalive => "Keepalive",
+        }
+    }
+
+    fn width(self) -> Constraint {
+        match self {
+            Column::Name => Constraint::Min(10),
+            Column::PublicKey => Constraint::Length(10),
+            Column::Endpoint => Constraint::Min(15),
+            Column::AllowedIps => Constraint::Min(18),
+            Column::Handshake => Constraint::Length(10),
+            Column::Received | Column::Sent => Constraint::Length(11),
+            Column::RxRate | Column::TxRate => Constraint::Length(13),
+            Column::Keepalive => Constraint::Length(9),
+        }
+    }
+}
+
+/// State of the details screen for one interface.
+pub struct DetailsState {
+    pub interface: String,
+    status: Result<Option<InterfaceStatus>, String>,
+    /// Friendly names from `# Name = ...` comments, by peer public key.
+    names: HashMap<String, String>,
+    table: TableState,
+    /// Public key of the selected peer, so the selection follows it when rows are re-sorted.
+    selected: Option<String>,
+    sort: Column,
+    descending: bool,
+    interface_rates: RateHistory,
+    peer_rates: HashMap<String, RateHistory>,
+}
+
+impl DetailsState {
+    pub fn new(backend: &dyn VpnBackend, interface: String) -> Self {
+        let names = Config::load(profile_path(&interface))
+            .map(|config| {
+                config
+                    .peers()
+                    .filter_map(|peer| Some((peer.get("PublicKey")?.to_string(), peer.friendly_name()?.to_string())))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let mut state = Self {
+            interface,
+            status: Ok(None),
+            names,
+            table: TableState::default(),
+            selected: None,
+            sort: Column::Name,
+            descending: false,
+            interface_rates: RateHistory::default(),
+            peer_rates: HashMap::new(),
+        };
+        let status = backend.interface_details(&state.interface);
+        state.update(Instant::now(), status);
+        state
+    }
+
+    /// Show newly polled state and record transfer rate samples.
+    pub fn update(&mut self, now: Instant, status: Result<Option<InterfaceStatus>, String>) {
+        self.status = status;
+        let Ok(Some(status)) = &self.status else {
+            return;
+        };
+        let rx = status.peers.iter().map(|p| p.rx_bytes).sum();
+        let tx = status.peers.iter().map(|p| p.tx_bytes).sum();
+        self.interface_rates.record(now, rx, tx);
+        self.peer_rates.retain(|key, _| status.peers.iter().any(|p| &p.public_key == key));
+        for peer in &status.peers {
+            self.peer_rates
+                .entry(peer.public_key.clone())
+                .or_default()
+                .record(now, peer.rx_bytes, peer.tx_bytes);
+        }
+        // Select the first row when peers first appear, or the selected one is gone.
+        if self.selected_index().is_none() {
+            self.select_index(0);
+        }
+    }
+
+    fn rates_of(&self, peer: &PeerStatus) -> (u64, u64) {
+        self.peer_rates
+            .get(&peer.public_key)
+            .map(|r| (r.rx_rate(), r.tx_rate()))
+            .unwrap_or_default()
+    }
+
+    fn name_of(&self, peer: &PeerStatus) -> &str {
+        self.names.get(&peer.public_key).map(String::as_str).unwrap_or("")
+    }
+
+    fn peer_count(&self) -> usize {
+        match &self.status {
+            Ok(Some(status)) => status.peers.len(),
+            _ => 0,
+        }
+    }
+
+    /// Peers in the current sort order.
+    fn sorted_peers(&self) -> Vec<&PeerStatus> {
+        let Ok(Some(status)) = &self.status else {
+            return Vec::new();
+        };
+        let mut peers: Vec<&PeerStatus> = status.peers.iter().collect();
+        peers.sort_by(|a, b| {
+            let ordering = match self.sort {
+                Column::Name => self.name_of(a).cmp(self.name_of(b)),
+                Column::PublicKey => a.public_key.cmp(&b.public_key),
+                Column::Endpoint => a.endpoint.cmp(&b.endpoint),
+                Column::AllowedIps => a.allowed_ips.cmp(&b.allowed_ips),
+                // Most recent first; peers that never shook hands last.
+                Column::Handshake => match (a.last_handshake, b.last_handshake) {
+                    (Some(a), Some(b)) => b.cmp(&a),
+                    (Some(_), None) => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    (None, None) => Ordering::Equal,
+                },
+                Column::Received => a.rx_bytes.cmp(&b.rx_bytes),
+                Column::Sent => a.tx_bytes.cmp(&b.tx_bytes),
+                Column::RxRate => self.rates_of(a).0.cmp(&self.rates_of(b).0),
+                Column::TxRate => self.rates_of(a).1.cmp(&self.rates_of(b).1),
+                Column::Keepalive => a.persistent_keepalive.cmp(&b.persistent_keepalive),
+            };
+            let ordering = ordering.then_with(|| a.public_key.cmp(&b.public_key));
+            if self.descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+        peers
+    }
+
+    /// Row of the selected peer in the current sort order.
+    fn selected_index(&self) -> Option<usize> {
+        let selected = self.selected.as_ref()?;
+        self.sorted_peers().iter().position(|p| &p.public_key == selected)
+    }
+
+    fn select_index(&mut self, index: usize) {
+        self.selected = self.sorted_peers().get(index).map(|p| p.public_key.clone());
+    }
+
+    /// Handle a key press. Returns true when the screen should be closed.
+    pub fn handle_event(&mut self, key: KeyEvent) -> bool {
+        let count = self.peer_count();
+        let selected = self.selected_index().unwrap_or(0);
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('w') => return true,
+            KeyCode::Down | KeyCode::Char('j') if selected + 1 < count => self.select_index(selected + 1),
+            KeyCode::Up | KeyCode::Char('k') => self.select_index(selected.saturating_sub(1)),
+            KeyCode::PageDown if count > 0 => self.select_index((selected + 10).min(count - 1)),
+            KeyCode::PageUp => self.select_index(selected.saturating_sub(10)),
+            KeyCode::Home | KeyCode::Char('g') => self.select_index(0),
+            KeyCode::End | KeyCode::Char('G') if count > 0 => self.select_index(count - 1),
+            KeyCode::Right | KeyCode::Char('l') | KeyCode::Tab => {
+                let index = COLUMNS.iter().position(|c| *c == self.sort).unwrap_or(0);
+                self.sort = COLUMNS[(index + 1) % COLUMNS.len()];
+            }
+            KeyCode::Left | KeyCode::Char('h') | KeyCode::BackTab => {
+                let index = COLUMNS.iter().position(|c| *c == self.sort).unwrap_or(0);
+                self.sort = COLUMNS[(index + COLUMNS.len() - 1) % COLUMNS.len()];
+            }
+            KeyCode::Char('r') => self.descending = !self.descending,
+            _ => {}
+        }
+        false
+    }
+}
+
+fn shorten_key(key: &str) -> String {
+    if key.len() > 8 {
+        format!("{}…", &key[..8])
+    } else {
+        key.to_string()
+    }
+}
+
+fn handshake_cell(peer: &PeerStatus) -> Span<'static> {
+    match handshake_age(peer.last_handshake) {
+        None => Span::styled("never", Style::default().fg(Color::DarkGray)),
+        Some(age) => {
+            let color = if age < HANDSHAKE_STALE {
+                Color::Green
+            } else if age < HANDSHAKE_DEAD {
+                Color::Yellow
+            } else {
+                Color::Red
+            };
+            Span::styled(format!("{} ago", format_age(age)), Style::default().fg(color))
+        }
+    }
+}
+
+fn header_lines(status: &InterfaceStatus) -> Vec<Line<'static>> {
+    let label = Style::default().fg(Color::Cyan);
+    let rx: u64 = status.peers.iter().map(|p| p.rx_bytes).sum();
+    let tx: u64 = status.peers.iter().map(|p| p.tx_bytes).sum();
+    vec![
+        Line::from(vec![
+            Span::styled("public key: ", label),
+            Span::raw(status.public_key.clone().unwrap_or_else(|| "(none)".into())),
+        ]),
+        Line::from(vec![
+            Span::styled("listening port: ", label),
+            Span::raw(status.listen_port.to_string()),
+            Span::styled("   fwmark: ", label),
+            Span::raw(if status.fwmark == 0 { "off".to_string() } else { format!("{:#x}", status.fwmark) }),
+            Span::styled("   peers: ", label),
+            Span::raw(status.peers.len().to_string()),
+        ]),
+        Line::from(vec![
+            Span::styled("transfer: ", label),
+            Span::raw(format!("{} received, {} sent", format_bytes(rx), format_bytes(tx))),
+        ]),
+    ]
+}
+
+/// Draw rx and tx sparklines side by side.
+fn draw_rates(f: &mut Frame, area: Rect, label: &str, rates: &RateHistory) {
+    let halves = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+        .split(area);
+    let charts = [
+        ("received", &rates.rx, rates.rx_rate(), halves[0], Color::Green),
+        ("sent", &rates.tx, rates.tx_rate(), halves[1], Color::Blue),
+    ];
+    for (direction, samples, current, area, color) in charts {
+        let title = format!(" {} {}: {} ", label, direction, format_rate(current));
+        let data = tail(samples, area.width.saturating_sub(2));
+        let sparkline = Sparkline::default()
+            .block(Block::default().title(title).borders(Borders::ALL))
+            .data(&data)
+            .style(Style::default().fg(color));
+        f.render_widget(sparkline, area);
+    }
+}
+
+/// Draw the details screen.
+pub fn draw(f: &mut Frame, area: Rect, state: &mut DetailsState) {
+    let title = format!(
+        " VPN Details: {} (↑/↓: Scroll, ←/→: Sort, R: Reverse, Esc: Back) ",
+        state.interface
+    );
+    let status = match &state.status {
+        Ok(Some(status)) => status,
+        Ok(None) => {
+            let paragraph = Paragraph::new(format!("{} is not active", state.interface))
+                .block(Block::default().title(title).borders(Borders::ALL));
+            f.render_widget(paragraph, area);
+            return;
+        }
+        Err(e) => {
+            let paragraph = Paragraph::new(e.clone())
+                .style(Style::default().fg(Color::Red))
+                .block(Block::default().title(title).borders(Borders::ALL));
+            f.render_widget(paragraph, area);
+            return;
+        }
+    };
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Length(5),
+                Constraint::Length(6),
+                Constraint::Min(3),
+                Constraint::Length(6),
+            ]
+            .as_ref(),
+        )
+        .split(area);
+
+    let header = Paragraph::new(header_lines(status))
+        .block(Block::default().title(title).borders(Borders::ALL));
+    f.render_widget(header, chunks[0]);
+    draw_rates(f, chunks[1], "Interface", &state.interface_rates);
+
+    let arrow = if state.descending { " ▼" } else { " ▲" };
+    let header_row = Row::new(COLUMNS.iter().map(|column| {
+        if *column == state.sort {
+            Span::styled(
+                format!("{}{}", column.title(), arrow),
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            )
+        } else {
+            Span::styled(column.title(), Style::default().add_modifier(Modifier::BOLD))
+        }
+    }));
+
+    let rows: Vec<Row> = state
+        .sorted_peers()
+        .into_iter()
+        .map(|peer| {
+            Row::new(vec![
+                Span::raw(state.name_of(peer).to_string()),
+                Span::raw(shorten_key(&peer.public_key)),
+                Span::raw(peer.endpoint.map(|e| e.to_string()).unwrap_or_else(|| "(none)".into())),
+                Span::raw(peer.allowed_ips.join(", ")),
+                handshake_cell(peer),
+                Span::raw(format_bytes(peer.rx_bytes)),
+                Span::raw(format_bytes(peer.tx_bytes)),
+                Span::raw(format_rate(state.rates_of(peer).0)),
+                Span::raw(format_rate(state.rates_of(peer).1)),
+                Span::raw(
+                    peer.persistent_keepalive
+                        .map(|k| format!("{}s", k))
+                        .unwrap_or_else(|| "off".into()),
+                ),
+            ])
+        })
+        .collect();
+
+    let table = Table::new(rows, COLUMNS.iter().map(|c| c.width()))
+        .header(header_row)
+        .block(Block::default().title(" Peers ").borders(Borders::ALL))
+        .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    state.table.select(state.selected_index());
+    f.render_stateful_widget(table, chunks[2], &mut state.table);
+
+    let selected = state.selected.as_ref().and_then(|key| Some((key, state.peer_rates.get(key)?)));
+    match selected {
+        Some((key, rates)) => {
+            let label = match state.names.get(key) {
+                Some(name) => format!("Peer {}", name),
+                None => format!("Peer {}", shorten_key(key)),
+            };
+            draw_rates(f, chunks[3], &label, rates);
+        }
+        None => f.render_widget(Block::default().title(" No peer selected ").borders(Borders::ALL), chunks[3]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+    use rustguard::backend::MockBackend;
+    use rustguard::testutil::TestDirs;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn peer(key: &str, handshake: Option<u64>) -> PeerStatus {
+        PeerStatus {
+            public_key: key.to_string(),
+            last_handshake: handshake.map(|secs| UNIX_EPOCH + Duration::from_secs(secs)),
+            ..PeerStatus::default()
+        }
+    }
+
+    fn status(peers: Vec<PeerStatus>) -> Result<Option<InterfaceStatus>, String> {
+        Ok(Some(InterfaceStatus { name: "wg0".into(), peers, ..InterfaceStatus::default() }))
+    }
+
+    fn keys(details: &DetailsState) -> Vec<&str> {
+        details.sorted_peers().iter().map(|p| p.public_key.as_str()).collect()
+    }
+
+    fn press(details: &mut DetailsState, code: KeyCode) {
+        details.handle_event(KeyEvent::new(code, KeyModifiers::NONE));
+    }
+
+    #[test]
+    fn rates_survive_counter_resets() {
+        let start = Instant::now();
+        let mut rates = RateHistory::default();
+        rates.record(start, 1000, 500);
+        assert_eq!((rates.rx_rate(), rates.tx_rate()), (0, 0));
+        rates.record(start + Duration::from_secs(2), 3000, 900);
+        assert_eq!((rates.rx_rate(), rates.tx_rate()), (1000, 200));
+        // The interface was recreated and its counters restarted from zero.
+        rates.record(start + Duration::from_secs(3), 100, 50);
+        assert_eq!((rates.rx_rate(), rates.tx_rate()), (0, 0));
+        rates.record(start + Duration::from_secs(4), 400, 50);
+        assert_eq!(rates.rx, [1000, 0, 300]);
+        // Readings at the same instant add no sample.
+        rates.record(start + Duration::from_secs(4), 900, 50);
+        assert_eq!(rates.rx.len(), 3);
+    }
+
+    #[test]
+    fn peers_that_never_shook_hands_sort_last() {
+        let _dirs = TestDirs::new();
+        let mut details = DetailsState::new(&MockBackend::new(), "wg0".into());
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let peers = vec![peer("a", None), peer("b", Some(now - 500)), peer("c", Some(now - 5)), peer("d", None)];
+        details.update(Instant::now(), status(peers));
+        details.sort = Column::Handshake;
+        assert_eq!(keys(&details), ["c", "b", "a", "d"]);
+        details.descending = true;
+        assert_eq!(keys(&details), ["d", "a", "b", "c"]);
+    }
+
+    #[test]
+    fn the_first_peer_is_selected_when_peers_appear() {
+        let _dirs = TestDirs::new();
+        let mut details = DetailsState::new(&MockBackend::new(), "wg0".into());
+        assert_eq!(details.selected, None);
+        press(&mut details, KeyCode::Down);
+        assert_eq!(details.selected, None);
+
+        details.update(Instant::now(), status(vec![peer("a", None), peer("b", None), peer("c", None)]));
+        assert_eq!(details.selected_index(), Some(0));
+        press(&mut details, KeyCode::Down);
+        assert_eq!(details.selected.as_deref(), Some("b"));
+
+        // The selection follows its peer, and moves to the first row when the peer goes away.
+        details.update(Instant::now(), status(vec![peer("0", None), peer("b", None)]));
+        assert_eq!(details.selected_index(), Some(1));
+        details.update(Instant::now(), status(vec![peer("a", None), peer("c", None)]));
+        assert_eq!(details.selected.as_deref(), Some("a"));
+    }
+}
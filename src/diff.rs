@@ -0,0 +1,155 @@
+//! Line diffs between two versions of a config.
+
+/// Unchanged lines shown around each change by default, as `diff -u` does.
+pub const DEFAULT_CONTEXT: usize = 3;
+
+/// One line of a diff.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DiffLine {
+    Same(String),
+    Added(String),
+    Removed(String),
+}
+
+impl DiffLine {
+    fn in_old(&self) -> bool {
+        !matches!(self, DiffLine::Added(_))
+    }
+
+    fn in_new(&self) -> bool {
+        !matches!(self, DiffLine::Removed(_))
+    }
+
+    fn is_change(&self) -> bool {
+        !matches!(self, DiffLine::Same(_))
+    }
+}
+
+/// Diff two texts line by line, using their longest common subsequence.
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    // lcs[i][j] is the length of the longest common subsequence of old[i..] and new[j..].
+    let mut lcs = vec![vec![0u32; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] { lcs[i + 1][j + 1] + 1 } else { lcs[i + 1][j].max(lcs[i][j + 1]) };
+        }
+    }
+
+    let mut lines = Vec::with_capacity(old.len().max(new.len()));
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            lines.push(DiffLine::Same(old[i].to_string()));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            lines.push(DiffLine::Removed(old[i].to_string()));
+            i += 1;
+        } else {
+            lines.push(DiffLine::Added(new[j].to_string()));
+            j += 1;
+        }
+    }
+    lines.extend(old[i..].iter().map(|line| DiffLine::Removed(line.to_string())));
+    lines.extend(new[j..].iter().map(|line| DiffLine::Added(line.to_string())));
+    lines
+}
+
+/// Whether a diff contains any change.
+pub fn has_changes(diff: &[DiffLine]) -> bool {
+    diff.iter().any(DiffLine::is_change)
+}
+
+/// Format a diff like `diff -u` does, without the file headers: hunks
+/// starting with an `@@` line, each showing `context` unchanged lines around
+/// the changes.
+pub fn unified(diff: &[DiffLine], context: usize) -> Vec<String> {
+    let changes: Vec<usize> = (0..diff.len()).filter(|&i| diff[i].is_change()).collect();
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for &i in &changes {
+        let start = i.saturating_sub(context);
+        let end = (i + context + 1).min(diff.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    let mut out = Vec::new();
+    for (start, end) in hunks {
+        let old_before = diff[..start].iter().filter(|l| l.in_old()).count();
+        let new_before = diff[..start].iter().filter(|l| l.in_new()).count();
+        let old_count = diff[start..end].iter().filter(|l| l.in_old()).count();
+        let new_count = diff[start..end].iter().filter(|l| l.in_new()).count();
+        // An empty range is given by the line before it.
+        let first = |before: usize, count: usize| if count == 0 { before } else { before + 1 };
+        out.push(format!(
+            "@@ -{},{} +{},{} @@",
+            first(old_before, old_count),
+            old_count,
+            first(new_before, new_count),
+            new_count
+        ));
+        out.extend(diff[start..end].iter().map(|line| match line {
+            DiffLine::Same(text) => format!(" {}", text),
+            DiffLine::Added(text) => format!("+{}", text),
+            DiffLine::Removed(text) => format!("-{}", text),
+        }));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(old: &str, new: &str, context: usize) -> Vec<String> {
+        unified(&diff_lines(old, new), context).into_iter().filter(|line| line.starts_with("@@")).collect()
+    }
+
+    #[test]
+    fn diffs_lines() {
+        let diff = diff_lines("a\nb\nc\n", "a\nx\nc\nd\n");
+        assert_eq!(
+            diff,
+            [
+                DiffLine::Same("a".into()),
+                DiffLine::Removed("b".into()),
+                DiffLine::Added("x".into()),
+                DiffLine::Same("c".into()),
+                DiffLine::Added("d".into()),
+            ]
+        );
+        assert!(has_changes(&diff));
+        assert!(!has_changes(&diff_lines("a\nb\n", "a\nb\n")));
+        assert_eq!(unified(&diff_lines("a\nb\n", "a\nb\n"), DEFAULT_CONTEXT), Vec::<String>::new());
+    }
+
+    #[test]
+    fn hunk_headers_match_diff_u() {
+        let ten = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n";
+        let cases: &[(&str, &str, usize, &[&str])] = &[
+            ("a\nb\nc\n", "a\nx\nc\n", 3, &["@@ -1,3 +1,3 @@"]),
+            ("a\nb\nc\n", "a\nx\nc\n", 0, &["@@ -2,1 +2,1 @@"]),
+            // An empty side is given by the line before it, 0 for an empty file.
+            ("", "a\nb\n", 3, &["@@ -0,0 +1,2 @@"]),
+            ("a\nb\n", "", 3, &["@@ -1,2 +0,0 @@"]),
+            ("a\nb\nc\n", "a\nc\n", 0, &["@@ -2,1 +1,0 @@"]),
+            ("a\nc\n", "a\nb\nc\n", 0, &["@@ -1,0 +2,1 @@"]),
+            // Changes further apart than twice the context are separate hunks.
+            (ten, "x\n2\n3\n4\n5\n6\n7\n8\n9\ny\n", 1, &["@@ -1,2 +1,2 @@", "@@ -9,2 +9,2 @@"]),
+            (ten, "x\n2\n3\n4\n5\n6\n7\n8\n9\ny\n", 4, &["@@ -1,10 +1,10 @@"]),
+        ];
+        for (old, new, context, expected) in cases {
+            assert_eq!(headers(old, new, *context), *expected, "{:?} -> {:?}", old, new);
+        }
+    }
+
+    #[test]
+    fn hunks_show_context_around_changes() {
+        let lines = unified(&diff_lines("a\nb\nc\nd\n", "a\nb\nx\nd\n"), 1);
+        assert_eq!(lines, ["@@ -2,3 +2,3 @@", " b", "-c", "+x", " d"]);
+    }
+}
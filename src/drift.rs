@@ -0,0 +1,211 @@
+//! The drift screen: how a running interface differs from its profile, e.g.
+//! after `wg set` was run by hand, with ways to bring them back in line.
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Wrap},
+    Frame,
+};
+
+use rustguard::backend::VpnBackend;
+use rustguard::config::Config;
+use rustguard::diff::{diff_lines, unified, DEFAULT_CONTEXT};
+use rustguard::profiles::{profile_path, save_profile};
+use rustguard::sync;
+
+use crate::highlight;
+use crate::prompt::{self, PromptState};
+
+/// What a confirmed prompt on the drift screen does.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum DriftAction {
+    /// Overwrite the profile with the state of the device.
+    SaveRunning,
+    /// Push the profile to the device.
+    Reapply,
+}
+
+/// How the device differs from the profile.
+struct Drift {
+    differences: Vec<String>,
+    /// The profile as it would be saved from the device.
+    running: Config,
+    /// What saving the running state would change in the file.
+    diff: Vec<String>,
+}
+
+/// State of the drift screen.
+pub struct DriftState {
+    pub profile: String,
+    drift: Result<Drift, String>,
+    scroll: u16,
+    confirm: Option<PromptState<DriftAction>>,
+    message: Option<(String, Color)>,
+}
+
+impl DriftState {
+    pub fn new(backend: &dyn VpnBackend, profile: String) -> Self {
+        let mut state = Self { profile, drift: Err(String::new()), scroll: 0, confirm: None, message: None };
+        state.load(backend);
+        state
+    }
+
+    fn load(&mut self, backend: &dyn VpnBackend) {
+        self.scroll = 0;
+        self.drift = Self::compare(backend, &self.profile);
+    }
+
+    fn compare(backend: &dyn VpnBackend, profile: &str) -> Result<Drift, String> {
+        let path = profile_path(profile);
+        let config = Config::load(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        let running = backend
+            .interface_details(profile)?
+            .ok_or_else(|| format!("{} is not up, so there is nothing to compare its file with.", profile))?;
+        let differences = sync::drift(&running, &config);
+        let running = sync::running_config(&running, &config);
+        let diff = unified(&diff_lines(&config.to_string(), &running.to_string()), DEFAULT_CONTEXT);
+        Ok(Drift { differences, running, diff })
+    }
+
+    fn has_drifted(&self) -> bool {
+        self.drift.as_ref().is_ok_and(|drift| !drift.differences.is_empty())
+    }
+
+    /// Carry out a confirmed action. Returns a status message.
+    fn run(&mut self, backend: &dyn VpnBackend, action: DriftAction) -> String {
+        let result = match action {
+            DriftAction::SaveRunning => self.save_running(),
+            DriftAction::Reapply => sync::apply_live(backend, &self.profile),
+        };
+        self.load(backend);
+        let (msg, color) = match result {
+            Ok(msg) => (msg, Color::Green),
+            Err(msg) => (msg, Color::Red),
+        };
+        self.message = Some((msg.clone(), color));
+        msg
+    }
+
+    fn save_running(&self) -> Result<String, String> {
+        let drift = self.drift.as_ref().map_err(String::clone)?;
+        let outcome =
+            save_profile(&self.profile, &drift.running.to_string(), "Saved the running state of the interface")?;
+        // The device now runs with exactly what the file says.
+        let mut msg =
+            outcome.report(format!("Saved the running state of {} to {}", self.profile, profile_path(&self.profile)));
+        if let Err(e) = sync::record_applied(&self.profile, &drift.running) {
+            msg.push_str(&format!("\n⚠️ {}", e));
+        }
+        Ok(msg)
+    }
+
+    /// Handle a key press.
+    ///
+    /// Returns whether the screen should close, and a message for the status
+    /// log when the profile or the device was changed.
+    pub fn handle_event(&mut self, backend: &dyn VpnBackend, key: KeyEvent) -> (bool, Option<String>) {
+        if let Some(confirm) = &mut self.confirm {
+            return match confirm.handle_event(key) {
+                None => (false, None),
+                Some(answer) => {
+                    let action = confirm.action;
+                    self.confirm = None;
+                    (false, answer.map(|_| self.run(backend, action)))
+                }
+            };
+        }
+
+        self.message = None;
+        let path = profile_path(&self.profile);
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => return (true, None),
+            KeyCode::Char('r') => self.load(backend),
+            KeyCode::Down | KeyCode::Char('j') => self.scroll = self.scroll.saturating_add(1),
+            KeyCode::Up | KeyCode::Char('k') => self.scroll = self.scroll.saturating_sub(1),
+            KeyCode::PageDown => self.scroll = self.scroll.saturating_add(10),
+            KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(10),
+            KeyCode::Char('s') if self.has_drifted() => {
+                let question = format!(
+                    "Overwrite {} with the running state of {}? The current file is backed up first.",
+                    path, self.profile
+                );
+                self.confirm = Some(PromptState::confirm(DriftAction::SaveRunning, "Save Running State", &question));
+            }
+            KeyCode::Char('a') if self.has_drifted() => {
+                let question = format!(
+                    "Apply {} to the running {}, undoing the changes made on the device?",
+                    path, self.profile
+                );
+                self.confirm = Some(PromptState::confirm(DriftAction::Reapply, "Reapply File", &question));
+            }
+            _ => {}
+        }
+        (false, None)
+    }
+}
+
+/// Draw the drift screen.
+pub fn draw(f: &mut Frame, area: Rect, state: &DriftState) {
+    let summary_height = match &state.drift {
+        Ok(drift) => drift.differences.len().clamp(1, 8) as u16 + 2,
+        Err(_) => 4,
+    };
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(summary_height), Constraint::Min(3), Constraint::Length(3)].as_ref())
+        .split(area);
+
+    let summary_block = Block::default().title(format!(" Drift of {} ", state.profile)).borders(Borders::ALL);
+    let summary = match &state.drift {
+        Ok(drift) if drift.differences.is_empty() => Paragraph::new(format!(
+            "{} runs exactly as {} says.",
+            state.profile,
+            profile_path(&state.profile)
+        ))
+        .style(Style::default().fg(Color::Green)),
+        Ok(drift) => Paragraph::new(
+            drift
+                .differences
+                .iter()
+                .map(|difference| Line::from(format!("⚠ {}", difference)))
+                .collect::<Vec<_>>(),
+        )
+        .style(Style::default().fg(Color::Yellow)),
+        Err(e) => Paragraph::new(e.clone()).style(Style::default().fg(Color::Red)),
+    };
+    f.render_widget(summary.wrap(Wrap { trim: true }).block(summary_block), chunks[0]);
+
+    let diff_block = Block::default()
+        .title(" Changes saving the running state would make to the file ")
+        .borders(Borders::ALL);
+    let diff = match &state.drift {
+        Ok(drift) if drift.diff.is_empty() => {
+            Paragraph::new("No differences").style(Style::default().fg(Color::DarkGray))
+        }
+        Ok(drift) => Paragraph::new(
+            drift
+                .diff
+                .iter()
+                .map(|line| Line::from(Span::styled(line.clone(), highlight::diff_line_style(line))))
+                .collect::<Vec<_>>(),
+        ),
+        Err(_) => Paragraph::new(""),
+    };
+    f.render_widget(diff.scroll((state.scroll, 0)).block(diff_block), chunks[1]);
+
+    let footer = match &state.message {
+        Some((message, color)) => Paragraph::new(message.clone()).style(Style::default().fg(*color)),
+        None if state.has_drifted() => Paragraph::new(
+            "S: Save running state to file | A: Reapply file to device | R: Refresh | PgUp/PgDn: Scroll | Esc: Back",
+        ),
+        None => Paragraph::new("R: Refresh | Esc: Back"),
+    };
+    f.render_widget(footer.block(Block::default().borders(Borders::ALL)), chunks[2]);
+
+    if let Some(confirm) = &state.confirm {
+        prompt::draw(f, area, confirm);
+    }
+}
@@ -0,0 +1,1852 @@
+//! The Vim-like config editor screen.
+
+use std::fs;
+use std::ops::Range;
+
+use crossterm::{
+    cursor,
+    event::{KeyCode, KeyEvent, KeyModifiers},
+    execute,
+};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListItem, Paragraph},
+    Frame,
+};
+
+use rustguard::config::Config;
+use rustguard::diff::{diff_lines, has_changes, unified, DEFAULT_CONTEXT};
+use rustguard::keys;
+use rustguard::profiles::profile_path;
+use rustguard::validate::{self, Diagnostic, Severity};
+
+use crate::centered_rect;
+use crate::highlight;
+use crate::motions::{self, Position};
+use crate::search::{self, Search};
+use crate::prompt::{self, PromptState};
+
+/// Width of the diagnostics gutter to the left of each line.
+const GUTTER_WIDTH: u16 = 2;
+
+/// Most diagnostics listed below the buffer before the list scrolls off.
+const MAX_DIAGNOSTIC_ROWS: u16 = 5;
+
+/// Most changes that can be undone; older ones are forgotten.
+const MAX_UNDO_STEPS: usize = 1000;
+
+/// How editing finished.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EditorAction {
+    /// The buffer should be written and the editor closed: the user confirmed
+    /// the reviewed changes, or ran `:wq`.
+    Save,
+    /// The buffer should be written and editing continues (`:w`).
+    Write,
+    /// The user left without saving.
+    Cancel,
+}
+
+/// The changes about to be saved, shown before writing them.
+#[derive(Clone)]
+struct Review {
+    /// Unified diff from the file on disk to the buffer.
+    diff: Result<Vec<String>, String>,
+    scroll: u16,
+}
+
+/// The buffer and cursor before or after a change, for undo and redo.
+#[derive(Clone)]
+struct UndoStep {
+    lines: Vec<String>,
+    cursor_row: usize,
+    cursor_col: usize,
+}
+
+/// Minimal Vim–like editor mode.
+#[derive(Clone, Debug, PartialEq)]
+pub enum EditorMode {
+    Normal,
+    Insert,
+    /// Characters between the anchor and the cursor are selected (`v`).
+    Visual,
+    /// Lines between the anchor and the cursor are selected (`V`).
+    VisualLine,
+}
+
+/// What yanked or deleted text is kept as, for `p` and `P`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Register {
+    /// Part of a line, or text spanning lines joined with `\n`.
+    Chars(String),
+    /// Whole lines.
+    Lines(Vec<String>),
+}
+
+/// An operator waiting for a motion, as in `d2w`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Operator {
+    Delete,
+    Yank,
+    Change,
+}
+
+impl Operator {
+    fn from_key(c: char) -> Option<Self> {
+        match c {
+            'd' => Some(Operator::Delete),
+            'y' => Some(Operator::Yank),
+            'c' => Some(Operator::Change),
+            _ => None,
+        }
+    }
+}
+
+/// Cursor motions, usable on their own, after an operator or in Visual mode.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Motion {
+    Left,
+    Right,
+    Up,
+    Down,
+    WordStart,
+    WordBack,
+    WordEnd,
+    LineStart,
+    LineEnd,
+    /// `gg`: the first line, or line N with a count.
+    FirstLine,
+    /// `G`: the last line, or line N with a count.
+    LastLine,
+}
+
+/// Which text between the cursor and a motion's target an operator acts on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RangeKind {
+    /// Up to the target, not including it.
+    Exclusive,
+    /// Up to and including the character at the target.
+    Inclusive,
+    /// Every line from the cursor's to the target's.
+    Linewise,
+}
+
+impl Motion {
+    fn from_key(key: KeyEvent) -> Option<Self> {
+        match key.code {
+            KeyCode::Char('h') | KeyCode::Left => Some(Motion::Left),
+            KeyCode::Char('l') | KeyCode::Right => Some(Motion::Right),
+            KeyCode::Char('k') | KeyCode::Up => Some(Motion::Up),
+            KeyCode::Char('j') | KeyCode::Down => Some(Motion::Down),
+            KeyCode::Char('w') => Some(Motion::WordStart),
+            KeyCode::Char('b') => Some(Motion::WordBack),
+            KeyCode::Char('e') => Some(Motion::WordEnd),
+            KeyCode::Char('0') | KeyCode::Home => Some(Motion::LineStart),
+            KeyCode::Char('$') | KeyCode::End => Some(Motion::LineEnd),
+            KeyCode::Char('G') => Some(Motion::LastLine),
+            _ => None,
+        }
+    }
+
+    fn range_kind(self) -> RangeKind {
+        match self {
+            Motion::Up | Motion::Down | Motion::FirstLine | Motion::LastLine => RangeKind::Linewise,
+            Motion::WordEnd | Motion::LineEnd => RangeKind::Inclusive,
+            _ => RangeKind::Exclusive,
+        }
+    }
+}
+
+/// A Normal or Visual mode command being typed, such as `3d` waiting for
+/// its motion.
+#[derive(Clone, Debug, Default)]
+struct Pending {
+    /// Count typed so far, before the operator or the motion.
+    count: Option<usize>,
+    /// Operator waiting for its motion, with the count typed before it.
+    operator: Option<(Operator, Option<usize>)>,
+    /// `g` was typed, waiting for the second `g`.
+    g: bool,
+    /// Keys typed so far, shown in the footer.
+    keys: String,
+}
+
+impl Pending {
+    fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// The count to use: both counts multiplied, as in `2d3w`, or None if
+    /// neither was typed.
+    fn total_count(&self) -> Option<usize> {
+        let before_operator = self.operator.and_then(|(_, count)| count);
+        match (before_operator, self.count) {
+            (None, None) => None,
+            (a, b) => Some(a.unwrap_or(1).saturating_mul(b.unwrap_or(1))),
+        }
+    }
+}
+
+/// A minimal multi–line editor state.
+#[derive(Clone)]
+pub struct EditorState {
+    pub profile: String,
+    pub lines: Vec<String>,
+    cursor_row: usize,
+    cursor_col: usize,
+    mode: EditorMode,
+    /// When true, the editor overlay cheatsheet is visible.
+    show_cheatsheet: bool,
+    /// Validation results for the current buffer, refreshed after every key.
+    diagnostics: Vec<Diagnostic>,
+    /// Set after a Ctrl+S was refused because of errors; a second Ctrl+S saves anyway.
+    force_save_armed: bool,
+    /// One-line message shown in place of the footer, and its colour.
+    message: Option<(String, Color)>,
+    /// The buffer as it was loaded, to tell whether it was modified.
+    original: Vec<String>,
+    /// How lines end in the file, `\n` or `\r\n`, and whether the last one
+    /// does, so saving writes them back as they were.
+    line_ending: &'static str,
+    final_newline: bool,
+    /// Set while the changes are being reviewed before saving.
+    review: Option<Review>,
+    /// Set while asking whether to discard unsaved changes.
+    discard: Option<PromptState<()>>,
+    /// States to go back to with `u`, oldest first.
+    undo: Vec<UndoStep>,
+    /// States undone, to go forward to again with Ctrl+R; the next undone last.
+    redo: Vec<UndoStep>,
+    /// Set once the current Insert mode session has an undo step, so the
+    /// whole session is undone at once.
+    insert_recorded: bool,
+    /// The command being typed in Normal or Visual mode.
+    pending: Pending,
+    /// Text last yanked or deleted.
+    register: Option<Register>,
+    /// Where the selection started, in Visual modes.
+    visual_anchor: (usize, usize),
+    /// First and last row of the last Visual selection, for the `'<,'>` range.
+    last_visual: (usize, usize),
+    /// The `:` command being typed, without the colon.
+    command_line: Option<String>,
+    /// Whether line numbers are shown (`:set number`).
+    line_numbers: bool,
+    /// The `/` or `?` search being typed, and where the cursor was before it.
+    searching: Option<(Search, Position)>,
+    /// The search `n` and `N` repeat.
+    last_search: Option<Search>,
+    /// Whether the matches of the last search are highlighted; `:noh` turns this off.
+    highlight_matches: bool,
+}
+
+impl EditorState {
+    pub fn new(profile: String, content: String) -> Self {
+        let lines = buffer_lines(&content);
+        let (line_ending, final_newline) = line_format(&content);
+        Self {
+            profile,
+            original: lines.clone(),
+            lines,
+            line_ending,
+            final_newline,
+            cursor_row: 0,
+            cursor_col: 0,
+            mode: EditorMode::Normal,
+            show_cheatsheet: false,
+            diagnostics: Vec::new(),
+            force_save_armed: false,
+            message: None,
+            review: None,
+            discard: None,
+            undo: Vec::new(),
+            redo: Vec::new(),
+            insert_recorded: false,
+            pending: Pending::default(),
+            register: None,
+            visual_anchor: (0, 0),
+            last_visual: (0, 0),
+            command_line: None,
+            line_numbers: false,
+            searching: None,
+            last_search: None,
+            highlight_matches: false,
+        }
+        .validated()
+    }
+
+    fn validated(mut self) -> Self {
+        self.revalidate();
+        self
+    }
+
+    /// Re-run config validation on the current buffer.
+    fn revalidate(&mut self) {
+        let config = Config::parse(&self.lines.join("\n"));
+        self.diagnostics = validate::validate(&self.profile, &config);
+    }
+
+    fn undo_step(&self) -> UndoStep {
+        UndoStep { lines: self.lines.clone(), cursor_row: self.cursor_row, cursor_col: self.cursor_col }
+    }
+
+    fn restore(&mut self, step: UndoStep) {
+        self.lines = step.lines;
+        self.cursor_row = step.cursor_row.min(self.lines.len() - 1);
+        self.cursor_col = char_start(&self.lines[self.cursor_row], step.cursor_col);
+    }
+
+    /// Remember the state before a key that changed the buffer. All changes
+    /// made in one Insert mode session share the step recorded for the first.
+    fn record_change(&mut self, before: UndoStep, was_insert: bool) {
+        if self.lines != before.lines && !(was_insert && self.insert_recorded) {
+            self.undo.push(before);
+            if self.undo.len() > MAX_UNDO_STEPS {
+                self.undo.remove(0);
+            }
+            self.redo.clear();
+            self.insert_recorded = self.mode == EditorMode::Insert;
+        }
+        if self.mode == EditorMode::Normal {
+            self.insert_recorded = false;
+        }
+    }
+
+    /// `u`: go back to the state before the last `count` changes.
+    fn undo(&mut self, count: usize) {
+        if self.undo.is_empty() {
+            self.message = Some(("Already at oldest change".into(), Color::Yellow));
+        }
+        for _ in 0..count {
+            let Some(step) = self.undo.pop() else { break };
+            self.redo.push(self.undo_step());
+            self.restore(step);
+        }
+    }
+
+    /// Ctrl+R: redo the last `count` undone changes.
+    fn redo(&mut self, count: usize) {
+        if self.redo.is_empty() {
+            self.message = Some(("Already at newest change".into(), Color::Yellow));
+        }
+        for _ in 0..count {
+            let Some(step) = self.redo.pop() else { break };
+            self.undo.push(self.undo_step());
+            self.restore(step);
+        }
+    }
+
+    /// The buffer as file contents, with the line endings the file had.
+    pub fn contents(&self) -> String {
+        let mut contents = self.lines.join(self.line_ending);
+        if self.final_newline {
+            contents.push_str(self.line_ending);
+        }
+        contents
+    }
+
+    /// Whether the buffer differs from what was loaded.
+    pub fn is_modified(&self) -> bool {
+        self.lines != self.original
+    }
+
+    /// After a save that keeps the editor open, such as `:w` or one that
+    /// failed: whether the buffer was written, and the status message.
+    pub fn written(&mut self, saved: bool, message: String) {
+        self.review = None;
+        if saved {
+            self.original = self.lines.clone();
+        }
+        self.message = Some((message, if saved { Color::Green } else { Color::Red }));
+    }
+
+    /// The profile as it is on disk; empty if it does not exist yet.
+    fn read_disk(&self) -> Result<String, String> {
+        let path = profile_path(&self.profile);
+        match fs::read_to_string(&path) {
+            Ok(text) => Ok(text),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(String::new()),
+            Err(e) => Err(format!("Failed to read {}: {}", path, e)),
+        }
+    }
+
+    /// Ctrl+S: review the changes, unless the buffer has errors and the user
+    /// has not yet confirmed by pressing Ctrl+S a second time.
+    fn request_save(&mut self) {
+        let errors = self.diagnostics.iter().filter(|d| d.is_error()).count();
+        if errors > 0 && !self.force_save_armed {
+            self.force_save_armed = true;
+            self.message = Some((
+                format!("{} error(s) in config. Press Ctrl+S again to save anyway.", errors),
+                Color::Red,
+            ));
+            return;
+        }
+        let diff = self.read_disk().map(|on_disk| diff_lines(&on_disk, &self.contents()));
+        if diff.as_ref().is_ok_and(|diff| !has_changes(diff)) {
+            self.message = Some(("No changes to save.".into(), Color::Yellow));
+            return;
+        }
+        self.review = Some(Review { diff: diff.map(|diff| unified(&diff, DEFAULT_CONTEXT)), scroll: 0 });
+    }
+
+    /// Esc in Normal mode: leave, asking first if that would lose changes.
+    fn request_cancel(&mut self) -> Option<EditorAction> {
+        if !self.is_modified() {
+            return Some(EditorAction::Cancel);
+        }
+        self.discard = Some(PromptState::confirm(
+            (),
+            "Discard Changes",
+            &format!("Discard your unsaved changes to {}?", self.profile),
+        ));
+        None
+    }
+
+    /// Keys of the review shown before saving.
+    fn handle_review(&mut self, key: KeyEvent) -> Option<EditorAction> {
+        let review = self.review.as_mut()?;
+        match key.code {
+            KeyCode::Enter | KeyCode::Char('y') if review.diff.is_ok() => return Some(EditorAction::Save),
+            KeyCode::Esc | KeyCode::Char('n') | KeyCode::Char('q') => self.review = None,
+            KeyCode::Down | KeyCode::Char('j') => review.scroll = review.scroll.saturating_add(1),
+            KeyCode::Up | KeyCode::Char('k') => review.scroll = review.scroll.saturating_sub(1),
+            KeyCode::PageDown => review.scroll = review.scroll.saturating_add(10),
+            KeyCode::PageUp => review.scroll = review.scroll.saturating_sub(10),
+            _ => {}
+        }
+        None
+    }
+
+    /// Put a newly generated PrivateKey into [Interface], replacing any
+    /// existing one, and show the matching public key.
+    fn generate_private_key(&mut self) {
+        let pair = match keys::generate_key_pair() {
+            Ok(pair) => pair,
+            Err(e) => {
+                self.message = Some((e, Color::Red));
+                return;
+            }
+        };
+        let mut config = Config::parse(&self.lines.join("\n"));
+        let verb = match config.interface_mut() {
+            Some(interface) => {
+                let verb = if interface.get("PrivateKey").is_some() { "Replaced" } else { "Added" };
+                interface.set("PrivateKey", &pair.private_key);
+                self.lines = config.to_string().lines().map(str::to_string).collect();
+                verb
+            }
+            None => {
+                let mut header = vec!["[Interface]".to_string(), format!("PrivateKey = {}", pair.private_key)];
+                if self.lines.iter().all(|line| line.trim().is_empty()) {
+                    self.lines = header;
+                } else {
+                    header.push(String::new());
+                    self.lines.splice(0..0, header);
+                }
+                "Added"
+            }
+        };
+        self.cursor_row = self
+            .lines
+            .iter()
+            .position(|line| line.contains(&pair.private_key))
+            .unwrap_or(0);
+        self.cursor_col = 0;
+        self.message = Some((
+            format!("{} PrivateKey. Public key: {}", verb, pair.public_key),
+            Color::Green,
+        ));
+    }
+
+    /// Keys of Normal and Visual mode: counts, operators, motions and commands.
+    fn handle_command(&mut self, key: KeyEvent) -> Option<EditorAction> {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let visual = self.mode != EditorMode::Normal;
+        if key.code == KeyCode::Esc {
+            if !self.pending.is_empty() {
+                self.pending = Pending::default();
+            } else if visual {
+                self.mode = EditorMode::Normal;
+            } else {
+                return self.request_cancel();
+            }
+            return None;
+        }
+        let KeyCode::Char(c) = key.code else {
+            if key.code == KeyCode::F(1) {
+                self.show_cheatsheet = true;
+            } else if let Some(motion) = Motion::from_key(key) {
+                self.motion(motion);
+            } else {
+                self.pending = Pending::default();
+            }
+            return None;
+        };
+        if ctrl {
+            let count = self.pending.total_count().unwrap_or(1);
+            self.pending = Pending::default();
+            match c {
+                's' => self.request_save(),
+                'r' if !visual => self.redo(count),
+                _ => {}
+            }
+            return None;
+        }
+
+        self.pending.keys.push(c);
+        if c.is_ascii_digit() && !self.pending.g && (c != '0' || self.pending.count.is_some()) {
+            let digit = c.to_digit(10).unwrap_or(0) as usize;
+            self.pending.count = Some(self.pending.count.unwrap_or(0).saturating_mul(10).saturating_add(digit));
+            return None;
+        }
+        if std::mem::take(&mut self.pending.g) {
+            if c == 'g' {
+                self.motion(Motion::FirstLine);
+            } else {
+                self.pending = Pending::default();
+            }
+            return None;
+        }
+        if c == 'g' {
+            self.pending.g = true;
+            return None;
+        }
+        if let Some(motion) = Motion::from_key(key) {
+            self.motion(motion);
+            return None;
+        }
+
+        let count = self.pending.total_count().unwrap_or(1);
+        let operator = self.pending.operator.map(|(operator, _)| operator);
+        if self.search_key(c, count) {
+            self.pending = Pending::default();
+            return None;
+        }
+        if visual {
+            self.pending = Pending::default();
+            self.handle_visual(c);
+            return None;
+        }
+        if let Some(operator) = operator {
+            self.pending = Pending::default();
+            // dd, yy and cc act on whole lines.
+            if Operator::from_key(c) == Some(operator) {
+                let last = (self.cursor_row + count - 1).min(self.lines.len() - 1);
+                self.operate(operator, (self.cursor_row, 0), (last, 0), RangeKind::Linewise);
+            }
+            return None;
+        }
+        if let Some(operator) = Operator::from_key(c) {
+            self.pending.operator = Some((operator, self.pending.count.take()));
+            return None;
+        }
+
+        self.pending = Pending::default();
+        let (row, col) = (self.cursor_row, self.cursor_col);
+        match c {
+            'i' => self.enter_insert(),
+            'a' => {
+                self.cursor_col = next_char(&self.lines[row], col);
+                self.enter_insert();
+            }
+            'o' => {
+                self.cursor_row += 1;
+                self.lines.insert(self.cursor_row, String::new());
+                self.cursor_col = 0;
+                self.enter_insert();
+            }
+            'x' if col < self.lines[row].len() => {
+                let end = (0..count).fold(col, |end, _| next_char(&self.lines[row], end));
+                self.operate(Operator::Delete, (row, col), (row, end), RangeKind::Exclusive);
+            }
+            'D' => {
+                let last = (row + count - 1).min(self.lines.len() - 1);
+                self.operate(Operator::Delete, (row, 0), (last, 0), RangeKind::Linewise);
+            }
+            'p' => self.paste(true, count),
+            'P' => self.paste(false, count),
+            'v' => self.start_visual(EditorMode::Visual),
+            'V' => self.start_visual(EditorMode::VisualLine),
+            'K' => self.generate_private_key(),
+            ':' => self.command_line = Some(String::new()),
+            'u' => self.undo(count),
+            _ => {}
+        }
+        None
+    }
+
+    /// Keys of Visual mode other than motions.
+    fn handle_visual(&mut self, c: char) {
+        let cursor = (self.cursor_row, self.cursor_col);
+        match c {
+            'v' | 'V' => {
+                let mode = if c == 'v' { EditorMode::Visual } else { EditorMode::VisualLine };
+                self.mode = if self.mode == mode { EditorMode::Normal } else { mode };
+            }
+            'o' => {
+                (self.cursor_row, self.cursor_col) = self.visual_anchor;
+                self.visual_anchor = cursor;
+            }
+            ':' => {
+                self.last_visual = (self.visual_anchor.0.min(cursor.0), self.visual_anchor.0.max(cursor.0));
+                self.mode = EditorMode::Normal;
+                self.command_line = Some("'<,'>".into());
+            }
+            'y' | 'd' | 'x' | 'c' => {
+                let operator = Operator::from_key(c).unwrap_or(Operator::Delete);
+                let kind = if self.mode == EditorMode::VisualLine { RangeKind::Linewise } else { RangeKind::Inclusive };
+                self.mode = EditorMode::Normal;
+                self.operate(operator, self.visual_anchor, cursor, kind);
+            }
+            _ => {}
+        }
+    }
+
+    fn start_visual(&mut self, mode: EditorMode) {
+        self.mode = mode;
+        self.visual_anchor = (self.cursor_row, self.cursor_col);
+    }
+
+    fn enter_insert(&mut self) {
+        self.mode = EditorMode::Insert;
+        execute!(std::io::stdout(), cursor::Show).ok();
+    }
+
+    /// Where a motion from the cursor ends.
+    fn motion_target(&self, motion: Motion, count: Option<usize>) -> (usize, usize) {
+        let n = count.unwrap_or(1);
+        let (row, col) = (self.cursor_row, self.cursor_col);
+        let last = self.lines.len() - 1;
+        let repeat = |step: fn(&[String], Position) -> Position| {
+            (0..n).fold((row, col), |pos, _| step(&self.lines, pos))
+        };
+        let (row, col) = match motion {
+            Motion::Left => (row, (0..n).fold(col, |col, _| prev_char(&self.lines[row], col))),
+            Motion::Right => (row, (0..n).fold(col, |col, _| next_char(&self.lines[row], col))),
+            Motion::Up | Motion::Down => {
+                let row = if motion == Motion::Up { row.saturating_sub(n) } else { row.saturating_add(n).min(last) };
+                (row, col)
+            }
+            Motion::WordStart => repeat(motions::next_word_start),
+            Motion::WordBack => repeat(motions::prev_word_start),
+            Motion::WordEnd => repeat(motions::word_end),
+            Motion::LineStart => (row, 0),
+            Motion::LineEnd => {
+                let row = (row + n - 1).min(last);
+                (row, prev_char(&self.lines[row], self.lines[row].len()))
+            }
+            Motion::FirstLine | Motion::LastLine => {
+                let row = match count {
+                    Some(n) => n.saturating_sub(1).min(last),
+                    None if motion == Motion::FirstLine => 0,
+                    None => last,
+                };
+                (row, motions::first_non_blank(&self.lines[row]))
+            }
+        };
+        // Word motions step over bytes; land on the character they are in.
+        (row, char_start(&self.lines[row], col))
+    }
+
+    /// Complete a motion: move the cursor, or apply the pending operator.
+    fn motion(&mut self, motion: Motion) {
+        let count = self.pending.total_count();
+        let operator = self.pending.operator.map(|(operator, _)| operator);
+        self.pending = Pending::default();
+        let from = (self.cursor_row, self.cursor_col);
+        let mut to = self.motion_target(motion, count);
+        let Some(operator) = operator else {
+            (self.cursor_row, self.cursor_col) = to;
+            return;
+        };
+        let mut kind = motion.range_kind();
+        if motion == Motion::WordStart {
+            if operator == Operator::Change && !motions::is_blank_at(&self.lines[from.0], from.1) {
+                // cw changes to the end of the word, like ce.
+                to = (0..count.unwrap_or(1)).fold(from, |pos, _| motions::word_end(&self.lines, pos));
+                kind = RangeKind::Inclusive;
+            } else if to.0 > from.0 {
+                // The line break after the last word is not part of it.
+                to = (to.0 - 1, self.lines[to.0 - 1].len());
+            }
+        }
+        self.operate(operator, from, to, kind);
+    }
+
+    /// Apply an operator to the text between two positions, keeping it in the register.
+    fn operate(&mut self, operator: Operator, from: (usize, usize), to: (usize, usize), kind: RangeKind) {
+        let (start, end) = if from <= to { (from, to) } else { (to, from) };
+        if kind == RangeKind::Linewise {
+            let lines = self.lines[start.0..=end.0].to_vec();
+            let count = lines.len();
+            self.register = Some(Register::Lines(lines));
+            self.cursor_row = start.0;
+            match operator {
+                Operator::Yank => {
+                    self.cursor_col = char_start(&self.lines[start.0], self.cursor_col);
+                    if count > 2 {
+                        self.message = Some((format!("{} lines yanked", count), Color::Yellow));
+                    }
+                }
+                Operator::Delete => {
+                    self.lines.drain(start.0..=end.0);
+                    if self.lines.is_empty() {
+                        self.lines.push(String::new());
+                    }
+                    self.cursor_row = start.0.min(self.lines.len() - 1);
+                    self.cursor_col = motions::first_non_blank(&self.lines[self.cursor_row]);
+                    if count > 2 {
+                        self.message = Some((format!("{} fewer lines", count), Color::Yellow));
+                    }
+                }
+                Operator::Change => {
+                    self.lines.splice(start.0..=end.0, [String::new()]);
+                    self.cursor_col = 0;
+                    self.enter_insert();
+                }
+            }
+            return;
+        }
+
+        let start_col = char_start(&self.lines[start.0], start.1);
+        let end_col = match kind {
+            RangeKind::Inclusive => next_char(&self.lines[end.0], end.1),
+            _ => char_start(&self.lines[end.0], end.1),
+        };
+        let text = if start.0 == end.0 {
+            self.lines[start.0][start_col..end_col].to_string()
+        } else {
+            let mut parts = vec![&self.lines[start.0][start_col..]];
+            parts.extend(self.lines[start.0 + 1..end.0].iter().map(String::as_str));
+            parts.push(&self.lines[end.0][..end_col]);
+            parts.join("\n")
+        };
+        if !text.is_empty() {
+            self.register = Some(Register::Chars(text));
+        }
+        (self.cursor_row, self.cursor_col) = (start.0, start_col);
+        if operator != Operator::Yank {
+            let tail = self.lines[end.0][end_col..].to_string();
+            self.lines.drain(start.0 + 1..=end.0);
+            self.lines[start.0].truncate(start_col);
+            self.lines[start.0].push_str(&tail);
+        }
+        if operator == Operator::Change {
+            self.enter_insert();
+        }
+    }
+
+    /// `p` and `P`: put the register after or before the cursor, `count` times.
+    fn paste(&mut self, after: bool, count: usize) {
+        let Some(register) = self.register.clone() else {
+            self.message = Some(("Nothing to paste; yank or delete something first.".into(), Color::Yellow));
+            return;
+        };
+        match register {
+            Register::Lines(lines) => {
+                let at = if after { self.cursor_row + 1 } else { self.cursor_row };
+                let block: Vec<String> = (0..count).flat_map(|_| lines.iter().cloned()).collect();
+                self.lines.splice(at..at, block);
+                self.cursor_row = at;
+                self.cursor_col = motions::first_non_blank(&self.lines[at]);
+            }
+            Register::Chars(text) => {
+                let row = self.cursor_row;
+                let col = if after {
+                    next_char(&self.lines[row], self.cursor_col)
+                } else {
+                    char_start(&self.lines[row], self.cursor_col)
+                };
+                let text = text.repeat(count);
+                let tail = self.lines[row].split_off(col);
+                let mut pieces = text.split('\n');
+                self.lines[row].push_str(pieces.next().unwrap_or_default());
+                let mut last = row;
+                for piece in pieces {
+                    last += 1;
+                    self.lines.insert(last, piece.to_string());
+                }
+                // The cursor goes to the last character put, or to the start of text spanning lines.
+                self.cursor_col = if last == row { prev_char(&self.lines[row], self.lines[row].len()) } else { col };
+                self.lines[last].push_str(&tail);
+            }
+        }
+    }
+
+    /// The selection in Visual modes, as its first and last position.
+    fn selection(&self) -> Option<((usize, usize), (usize, usize))> {
+        let cursor = (self.cursor_row, self.cursor_col);
+        match self.mode {
+            EditorMode::Visual | EditorMode::VisualLine => {
+                Some((self.visual_anchor.min(cursor), self.visual_anchor.max(cursor)))
+            }
+            _ => None,
+        }
+    }
+
+    /// `/`, `?`, `n`, `N`, `*` and `#`. Returns whether `c` was one of them.
+    fn search_key(&mut self, c: char, count: usize) -> bool {
+        let cursor = (self.cursor_row, self.cursor_col);
+        match c {
+            '/' | '?' => self.searching = Some((Search { forward: c == '/', ..Search::default() }, cursor)),
+            'n' | 'N' => match self.last_search.clone() {
+                Some(search) => self.search_next(&search, search.forward == (c == 'n'), count),
+                None => self.message = Some(("No previous search".into(), Color::Red)),
+            },
+            '*' | '#' => match search::token_at(&self.lines[self.cursor_row], self.cursor_col) {
+                Some(token) => {
+                    let pattern = self.lines[self.cursor_row][token.clone()].to_string();
+                    let search = Search { pattern, forward: c == '*', whole: true };
+                    // Start from the token itself, so a cursor inside it does not find it again.
+                    self.cursor_col = token.start;
+                    self.search_next(&search, search.forward, count);
+                    self.last_search = Some(search);
+                    self.highlight_matches = true;
+                }
+                None => self.message = Some(("No string under cursor".into(), Color::Red)),
+            },
+            _ => return false,
+        }
+        true
+    }
+
+    /// Keys while typing a `/` or `?` search. The cursor moves to the first
+    /// match as the pattern is typed, and back if the search is cancelled.
+    fn handle_search_line(&mut self, key: KeyEvent) {
+        let Some((search, origin)) = &mut self.searching else {
+            return;
+        };
+        let origin = *origin;
+        match key.code {
+            KeyCode::Esc => {
+                self.searching = None;
+                (self.cursor_row, self.cursor_col) = origin;
+                return;
+            }
+            KeyCode::Backspace if search.pattern.pop().is_none() => {
+                self.searching = None;
+                return;
+            }
+            KeyCode::Enter => {
+                let (mut search, _) = self.searching.take().unwrap_or_default();
+                // An empty pattern searches for the last one again, in the new direction.
+                if search.pattern.is_empty() {
+                    match &self.last_search {
+                        Some(last) => search = Search { forward: search.forward, ..last.clone() },
+                        None => return,
+                    }
+                }
+                (self.cursor_row, self.cursor_col) = origin;
+                self.search_next(&search, search.forward, 1);
+                self.last_search = Some(search);
+                self.highlight_matches = true;
+                return;
+            }
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => search.pattern.push(c),
+            _ => {}
+        }
+        let found = search.find(&self.lines, origin, search.forward);
+        (self.cursor_row, self.cursor_col) = found.map_or(origin, |(position, _)| position);
+    }
+
+    /// Move the cursor to the `count`th match of a search, saying when it
+    /// wrapped around the buffer or found nothing.
+    fn search_next(&mut self, search: &Search, forward: bool, count: usize) {
+        let mut wrapped = false;
+        for _ in 0..count {
+            match search.find(&self.lines, (self.cursor_row, self.cursor_col), forward) {
+                Some((position, wrap)) => {
+                    (self.cursor_row, self.cursor_col) = position;
+                    wrapped |= wrap;
+                }
+                None => {
+                    self.message = Some((format!("Pattern not found: {}", search.pattern), Color::Red));
+                    return;
+                }
+            }
+        }
+        if wrapped {
+            let message = if forward {
+                "search hit BOTTOM, continuing at TOP"
+            } else {
+                "search hit TOP, continuing at BOTTOM"
+            };
+            self.message = Some((message.into(), Color::Yellow));
+        }
+    }
+
+    /// The search whose matches are highlighted: the one being typed, or the last one.
+    fn highlighted_search(&self) -> Option<&Search> {
+        match &self.searching {
+            Some((search, _)) => Some(search),
+            None => self.last_search.as_ref().filter(|_| self.highlight_matches),
+        }
+    }
+
+    /// Keys while typing a `:` command.
+    fn handle_command_line(&mut self, key: KeyEvent) -> Option<EditorAction> {
+        let line = self.command_line.as_mut()?;
+        match key.code {
+            KeyCode::Esc => self.command_line = None,
+            // Backspace on an empty command line closes it, as in Vim.
+            KeyCode::Backspace if line.pop().is_none() => self.command_line = None,
+            KeyCode::Enter => {
+                let command = self.command_line.take().unwrap_or_default();
+                return self.execute(&command);
+            }
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => line.push(c),
+            _ => {}
+        }
+        None
+    }
+
+    /// Run a `:` command. Errors are shown in the footer.
+    fn execute(&mut self, command: &str) -> Option<EditorAction> {
+        let command = command.trim();
+        let range_len = command
+            .find(|c: char| !(c.is_ascii_digit() || ",.$%'<>".contains(c)))
+            .unwrap_or(command.len());
+        let (range, rest) = command.split_at(range_len);
+        let name_len = rest.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(rest.len());
+        let (name, rest) = rest.split_at(name_len);
+        let (force, arg) = match rest.strip_prefix('!') {
+            Some(arg) => (true, arg.trim()),
+            None => (false, rest.trim()),
+        };
+        let unsaved = || Err("No write since last change (add ! to override)".to_string());
+        let result = match name {
+            "s" | "substitute" => self.resolve_range(range).and_then(|rows| self.substitute(rows, rest.trim_start())),
+            "se" | "set" => self.set_option(arg).map(|_| None),
+            _ if !arg.is_empty() => Err(format!("Trailing characters: {}", arg)),
+            "" if range.is_empty() => Ok(None),
+            "" => self.resolve_range(range).map(|(_, last)| {
+                self.cursor_row = last;
+                self.cursor_col = motions::first_non_blank(&self.lines[last]);
+                None
+            }),
+            "w" | "write" => self.write_command(force, EditorAction::Write),
+            "wq" | "x" | "xit" => self.write_command(force, EditorAction::Save),
+            "q" | "quit" if !force && self.is_modified() => unsaved(),
+            "q" | "quit" => Ok(Some(EditorAction::Cancel)),
+            "e" | "edit" if !force && self.is_modified() => unsaved(),
+            "e" | "edit" => self.reload().map(|_| None),
+            "noh" | "nohlsearch" => {
+                self.highlight_matches = false;
+                Ok(None)
+            }
+            "h" | "help" => {
+                self.show_cheatsheet = true;
+                Ok(None)
+            }
+            _ => Err(format!("Not an editor command: {}", command)),
+        };
+        result.unwrap_or_else(|e| {
+            self.message = Some((e, Color::Red));
+            None
+        })
+    }
+
+    /// `:w`, `:wq` and `:x`: `action` if the buffer can be written. With
+    /// nothing to write, `:wq` and `:x` just close the editor.
+    fn write_command(&mut self, force: bool, action: EditorAction) -> Result<Option<EditorAction>, String> {
+        let errors = self.diagnostics.iter().filter(|d| d.is_error()).count();
+        if errors > 0 && !force {
+            return Err(format!("{} error(s) in config (add ! to write anyway)", errors));
+        }
+        let on_disk = self.read_disk()?;
+        if has_changes(&diff_lines(&on_disk, &self.contents())) {
+            Ok(Some(action))
+        } else if action == EditorAction::Write {
+            self.message = Some(("No changes to write.".into(), Color::Yellow));
+            Ok(None)
+        } else {
+            Ok(Some(EditorAction::Cancel))
+        }
+    }
+
+    /// `:e!`: replace the buffer with the file on disk.
+    fn reload(&mut self) -> Result<(), String> {
+        let content = self.read_disk()?;
+        self.lines = buffer_lines(&content);
+        (self.line_ending, self.final_newline) = line_format(&content);
+        self.original = self.lines.clone();
+        self.cursor_row = self.cursor_row.min(self.lines.len() - 1);
+        self.cursor_col = char_start(&self.lines[self.cursor_row], self.cursor_col);
+        self.message = Some((format!("Reloaded {}", profile_path(&self.profile)), Color::Green));
+        Ok(())
+    }
+
+    /// First and last row of a range such as `%`, `3,7`, `.,$` or `'<,'>`;
+    /// the cursor's row if there is none.
+    fn resolve_range(&self, range: &str) -> Result<(usize, usize), String> {
+        if range.is_empty() {
+            return Ok((self.cursor_row, self.cursor_row));
+        }
+        if range == "%" {
+            return Ok((0, self.lines.len() - 1));
+        }
+        let (first, last) = range.split_once(',').unwrap_or((range, range));
+        let (first, last) = (self.address(first)?, self.address(last)?);
+        if first > last {
+            return Err("Backwards range given".into());
+        }
+        Ok((first, last))
+    }
+
+    /// The row of one end of a range.
+    fn address(&self, address: &str) -> Result<usize, String> {
+        let last = self.lines.len() - 1;
+        match address {
+            "." => Ok(self.cursor_row),
+            "$" => Ok(last),
+            "'<" => Ok(self.last_visual.0.min(last)),
+            "'>" => Ok(self.last_visual.1.min(last)),
+            line => match line.parse::<usize>() {
+                Ok(line) => Ok(line.saturating_sub(1).min(last)),
+                Err(_) => Err(format!("Invalid range: {}", address)),
+            },
+        }
+    }
+
+    /// `:s/old/new/[g]`: replace text, taken literally, on the given rows.
+    fn substitute(&mut self, (first, last): (usize, usize), arg: &str) -> Result<Option<EditorAction>, String> {
+        let usage = || "Usage: :s/old/new/ or :%s/old/new/g".to_string();
+        let delimiter = arg.chars().next().filter(|c| !c.is_alphanumeric() && *c != '\\').ok_or_else(usage)?;
+        let parts = split_substitute(&arg[delimiter.len_utf8()..], delimiter);
+        // As in Vim, an empty pattern reuses the last search, e.g. after `*`.
+        let pattern = match (parts[0].as_str(), &self.last_search) {
+            ("", Some(search)) => search.pattern.clone(),
+            ("", None) => return Err(usage()),
+            (pattern, _) => pattern.to_string(),
+        };
+        let pattern = pattern.as_str();
+        let replacement = parts.get(1).map_or("", String::as_str);
+        let flags = parts.get(2).map_or("", String::as_str);
+        if !flags.chars().all(|flag| flag == 'g') {
+            return Err(format!("Trailing characters: {}", flags));
+        }
+
+        let (mut substitutions, mut lines) = (0, 0);
+        for row in first..=last {
+            let matches = self.lines[row].matches(pattern).count();
+            if matches == 0 {
+                continue;
+            }
+            let count = if flags.is_empty() { 1 } else { matches };
+            self.lines[row] = self.lines[row].replacen(pattern, replacement, count);
+            substitutions += count;
+            lines += 1;
+            self.cursor_row = row;
+        }
+        if substitutions == 0 {
+            return Err(format!("Pattern not found: {}", pattern));
+        }
+        self.cursor_col = motions::first_non_blank(&self.lines[self.cursor_row]);
+        let plural = |n: usize| if n == 1 { "" } else { "s" };
+        self.message = Some((
+            format!("{} substitution{} on {} line{}", substitutions, plural(substitutions), lines, plural(lines)),
+            Color::Green,
+        ));
+        Ok(None)
+    }
+
+    /// `:set`: the only option is `number` (`nu`), with its `no`, `inv` and `!` forms.
+    fn set_option(&mut self, option: &str) -> Result<(), String> {
+        match option {
+            "number" | "nu" => self.line_numbers = true,
+            "nonumber" | "nonu" => self.line_numbers = false,
+            "invnumber" | "invnu" | "number!" | "nu!" => self.line_numbers = !self.line_numbers,
+            "" => return Err("Usage: :set number or :set nonumber".into()),
+            _ => return Err(format!("Unknown option: {}", option)),
+        }
+        Ok(())
+    }
+
+    /// The cursor's column on screen, counting characters rather than bytes.
+    fn display_col(&self) -> usize {
+        let line = &self.lines[self.cursor_row];
+        line[..char_start(line, self.cursor_col)].chars().count()
+    }
+
+    /// Columns to the left of the text: diagnostics markers and line numbers.
+    fn gutter_width(&self) -> u16 {
+        GUTTER_WIDTH + if self.line_numbers { self.number_width() as u16 } else { 0 }
+    }
+
+    /// Width of the line numbers and the space after them, as Vim's `numberwidth`.
+    fn number_width(&self) -> usize {
+        self.lines.len().to_string().len().max(3) + 1
+    }
+
+    /// Handle key events while editing.
+    ///
+    /// Returns Some(action) if editing is finished:
+    /// - Some(EditorAction::Save) once the user reviewed and confirmed the changes,
+    /// - Some(EditorAction::Cancel) if the user pressed Esc (in Normal mode) to
+    ///   leave, and confirmed discarding any changes.
+    ///
+    /// Otherwise returns None.
+    pub fn handle_event(&mut self, key: KeyEvent) -> Option<EditorAction> {
+        if self.review.is_some() {
+            return self.handle_review(key);
+        }
+        if let Some(discard) = &mut self.discard {
+            return match discard.handle_event(key) {
+                None => None,
+                Some(answer) => {
+                    self.discard = None;
+                    answer.map(|_| EditorAction::Cancel)
+                }
+            };
+        }
+        let is_save = key.code == KeyCode::Char('s') && key.modifiers.contains(KeyModifiers::CONTROL);
+        if !is_save {
+            self.force_save_armed = false;
+            self.message = None;
+        }
+        let before = self.undo_step();
+        let was_insert = self.mode == EditorMode::Insert;
+        let history = (self.undo.len(), self.redo.len());
+        let result = self.handle_key(key);
+        // Undo and redo move between steps themselves and are not changes.
+        if (self.undo.len(), self.redo.len()) == history {
+            self.record_change(before, was_insert);
+        }
+        self.revalidate();
+        result
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> Option<EditorAction> {
+        if self.command_line.is_some() {
+            return self.handle_command_line(key);
+        }
+        if self.searching.is_some() {
+            self.handle_search_line(key);
+            return None;
+        }
+        match self.mode {
+            EditorMode::Normal | EditorMode::Visual | EditorMode::VisualLine => {
+                if self.show_cheatsheet {
+                    self.show_cheatsheet = false;
+                    return None;
+                }
+                return self.handle_command(key);
+            }
+            EditorMode::Insert => {
+                match key.code {
+                    KeyCode::Esc => {
+                        self.mode = EditorMode::Normal;
+                    }
+                    KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        if let Some(line) = self.lines.get_mut(self.cursor_row) {
+                            line.insert(self.cursor_col, c);
+                            self.cursor_col += c.len_utf8();
+                        }
+                    }
+                    KeyCode::Enter => {
+                        if let Some(line) = self.lines.get_mut(self.cursor_row) {
+                            let new_line = line.split_off(self.cursor_col);
+                            self.lines.insert(self.cursor_row + 1, new_line);
+                            self.cursor_row += 1;
+                            self.cursor_col = 0;
+                        }
+                    }
+                    KeyCode::Backspace => {
+                        if self.cursor_col > 0 {
+                            if let Some(line) = self.lines.get_mut(self.cursor_row) {
+                                self.cursor_col = prev_char(line, self.cursor_col);
+                                line.remove(self.cursor_col);
+                            }
+                        } else if self.cursor_row > 0 {
+                            let current_line = self.lines.remove(self.cursor_row);
+                            self.cursor_row -= 1;
+                            self.cursor_col = self.lines[self.cursor_row].len();
+                            self.lines[self.cursor_row].push_str(&current_line);
+                        }
+                    }
+                    KeyCode::Left => {
+                        if self.cursor_col > 0 {
+                            self.cursor_col = prev_char(&self.lines[self.cursor_row], self.cursor_col);
+                        } else if self.cursor_row > 0 {
+                            self.cursor_row -= 1;
+                            self.cursor_col = self.lines[self.cursor_row].len();
+                        }
+                    }
+                    KeyCode::Right => {
+                        if let Some(line) = self.lines.get(self.cursor_row) {
+                            if self.cursor_col < line.len() {
+                                self.cursor_col = next_char(line, self.cursor_col);
+                            } else if self.cursor_row + 1 < self.lines.len() {
+                                self.cursor_row += 1;
+                                self.cursor_col = 0;
+                            }
+                        }
+                    }
+                    KeyCode::Up if self.cursor_row > 0 => {
+                        self.cursor_row -= 1;
+                        self.cursor_col = char_start(&self.lines[self.cursor_row], self.cursor_col);
+                    }
+                    KeyCode::Down if self.cursor_row + 1 < self.lines.len() => {
+                        self.cursor_row += 1;
+                        self.cursor_col = char_start(&self.lines[self.cursor_row], self.cursor_col);
+                    }
+                    KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        self.request_save();
+                    }
+                    _ => {}
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Lines of a file, as the editor holds them: at least one, possibly empty.
+fn buffer_lines(content: &str) -> Vec<String> {
+    if content.is_empty() {
+        vec![String::new()]
+    } else {
+        content.lines().map(str::to_string).collect()
+    }
+}
+
+/// The line ending a file uses and whether its last line has one. New,
+/// empty files get Unix line endings and a final newline.
+fn line_format(content: &str) -> (&'static str, bool) {
+    let line_ending = if content.contains("\r\n") { "\r\n" } else { "\n" };
+    (line_ending, content.is_empty() || content.ends_with('\n'))
+}
+
+/// `col` moved onto the line and back to the start of the character it is in.
+fn char_start(line: &str, col: usize) -> usize {
+    let mut col = col.min(line.len());
+    while !line.is_char_boundary(col) {
+        col -= 1;
+    }
+    col
+}
+
+/// Column of the character after the one at `col`, or the end of the line.
+fn next_char(line: &str, col: usize) -> usize {
+    let col = char_start(line, col);
+    line[col..].chars().next().map_or(col, |c| col + c.len_utf8())
+}
+
+/// Column of the character before the one at `col`, or 0.
+fn prev_char(line: &str, col: usize) -> usize {
+    let col = char_start(line, col);
+    line[..col].char_indices().next_back().map_or(0, |(i, _)| i)
+}
+
+/// Split the argument of `:s` after its first delimiter into the pattern,
+/// the replacement and the flags. `\` escapes the delimiter and itself.
+fn split_substitute(arg: &str, delimiter: char) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut chars = arg.chars();
+    while let Some(c) = chars.next() {
+        if c == delimiter && parts.len() < 3 {
+            parts.push(String::new());
+            continue;
+        }
+        let part = parts.last_mut().expect("there is always a part");
+        match c {
+            '\\' => match chars.next() {
+                Some(next) if next == delimiter || next == '\\' => part.push(next),
+                Some(next) => {
+                    part.push('\\');
+                    part.push(next);
+                }
+                None => part.push('\\'),
+            },
+            c => part.push(c),
+        }
+    }
+    parts
+}
+
+fn severity_color(severity: Severity) -> Color {
+    match severity {
+        Severity::Error => Color::Red,
+        Severity::Warning => Color::Yellow,
+    }
+}
+
+/// Draw the editor screen.
+pub fn draw(f: &mut Frame, area: Rect, editor_state: &EditorState) {
+    let diagnostic_rows = if editor_state.diagnostics.is_empty() {
+        0
+    } else {
+        (editor_state.diagnostics.len() as u16).min(MAX_DIAGNOSTIC_ROWS) + 2
+    };
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Min(3),
+                Constraint::Length(diagnostic_rows),
+                Constraint::Length(1),
+            ]
+            .as_ref(),
+        )
+        .split(area);
+    let text_area = chunks[0];
+
+    // Keep the cursor line visible once the buffer is taller than the screen.
+    let visible_rows = text_area.height.saturating_sub(2) as usize;
+    let scroll = editor_state.cursor_row.saturating_sub(visible_rows.saturating_sub(1));
+
+    let selection = editor_state.selection();
+    let linewise = editor_state.mode == EditorMode::VisualLine;
+    let lines: Vec<Line> = highlight::highlight(&editor_state.lines)
+        .into_iter()
+        .enumerate()
+        .map(|(i, spans)| {
+            let worst = editor_state
+                .diagnostics
+                .iter()
+                .filter(|d| d.line == i + 1)
+                .map(|d| d.severity)
+                .min_by_key(|s| *s != Severity::Error);
+            let marker = match worst {
+                Some(severity) => {
+                    let marker = if severity == Severity::Error { "E " } else { "W " };
+                    Span::styled(
+                        marker,
+                        Style::default().fg(severity_color(severity)).add_modifier(Modifier::BOLD),
+                    )
+                }
+                None => Span::raw("  "),
+            };
+            let underline = if worst.is_some() { Modifier::UNDERLINED } else { Modifier::empty() };
+            let mut spans = spans;
+            if let Some(search) = editor_state.highlighted_search() {
+                for range in search.matches(&editor_state.lines[i]) {
+                    // While typing, the match the cursor will land on stands out, as with Vim's incsearch.
+                    let current = editor_state.searching.is_some()
+                        && (i, range.start) == (editor_state.cursor_row, editor_state.cursor_col);
+                    let color = if current { Color::LightRed } else { Color::Yellow };
+                    spans = restyle(spans, range, Style::default().bg(color).fg(Color::Black));
+                }
+            }
+            let spans = match selection {
+                Some((start, end)) if (start.0..=end.0).contains(&i) => {
+                    let len = editor_state.lines[i].len();
+                    let from = if i == start.0 && !linewise { start.1 } else { 0 };
+                    let to = if i == end.0 && !linewise {
+                        next_char(&editor_state.lines[i], end.1).max(end.1 + 1)
+                    } else {
+                        len + 1
+                    };
+                    mark_selected(spans, from..to)
+                }
+                _ => spans,
+            };
+            let mut line = vec![marker];
+            if editor_state.line_numbers {
+                let width = editor_state.number_width() - 1;
+                line.push(Span::styled(format!("{:>width$} ", i + 1), Style::default().fg(Color::DarkGray)));
+            }
+            line.extend(spans.into_iter().map(|span| {
+                let style = span.style.add_modifier(underline);
+                span.style(style)
+            }));
+            Line::from(line)
+        })
+        .collect();
+
+    let block = Block::default().title(format!(
+        " Editing {}{} (Ctrl+S: Save, Esc: Cancel) ",
+        profile_path(&editor_state.profile),
+        if editor_state.is_modified() { " [modified]" } else { "" }
+    ))
+    .borders(Borders::ALL);
+    let paragraph = Paragraph::new(lines).block(block).scroll((scroll as u16, 0));
+    f.render_widget(paragraph, text_area);
+
+    if diagnostic_rows > 0 {
+        let errors = editor_state.diagnostics.iter().filter(|d| d.is_error()).count();
+        let warnings = editor_state.diagnostics.len() - errors;
+        let items: Vec<ListItem> = editor_state
+            .diagnostics
+            .iter()
+            .map(|d| ListItem::new(d.to_string()).style(Style::default().fg(severity_color(d.severity))))
+            .collect();
+        let list = List::new(items).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(" Diagnostics: {} error(s), {} warning(s) ", errors, warnings)),
+        );
+        f.render_widget(list, chunks[1]);
+    }
+
+    let mode_str = match editor_state.mode {
+        EditorMode::Normal => "NORMAL",
+        EditorMode::Insert => "INSERT",
+        EditorMode::Visual => "VISUAL",
+        EditorMode::VisualLine => "VISUAL LINE",
+    };
+    let footer = match (&editor_state.command_line, &editor_state.message) {
+        (Some(command), _) => Paragraph::new(format!(":{}", command)),
+        (None, _) if editor_state.searching.is_some() => {
+            let (search, _) = editor_state.searching.as_ref().unwrap_or_else(|| unreachable!());
+            Paragraph::new(format!("{}{}", if search.forward { '/' } else { '?' }, search.pattern))
+        }
+        (None, Some((message, color))) => Paragraph::new(message.clone()).style(Style::default().fg(*color)),
+        (None, None) => Paragraph::new(format!(
+            "Mode: {} | Line: {} Col: {}{}",
+            mode_str,
+            editor_state.cursor_row + 1,
+            editor_state.display_col() + 1,
+            if editor_state.pending.is_empty() { String::new() } else { format!(" | {}", editor_state.pending.keys) }
+        ))
+        .style(Style::default().fg(Color::Yellow)),
+    };
+    f.render_widget(footer, chunks[2]);
+
+    if editor_state.show_cheatsheet {
+        let help_text = "Editor Cheatsheet (Normal mode):
+i / a / o    : Insert before / after the cursor, on a new line below
+h j k l      : Move (or the arrow keys)
+w / b / e    : Next word / previous word / end of word
+0 / $        : Start / end of line
+gg / G       : First / last line (5G: line 5)
+3j, 5dd, 2w  : A count before a command repeats it
+x / dd       : Delete character / line (D also deletes the line)
+dw, cw, y$   : Delete, change or yank up to where a motion moves
+yy           : Yank line
+p / P        : Paste after / before the cursor
+v / V        : Select characters / lines; then y, d, c, or o for the other end
+u / Ctrl+R   : Undo (a whole Insert mode session at once) / Redo
+/ or ?       : Search forward / backward as you type (Enter: go, Esc: back)
+n / N        : Next / previous match
+* / #        : Search forward / backward for the key or address under the cursor
+K            : Generate a new PrivateKey and show its public key
+F1 or :help  : Show this help
+Ctrl+S       : Review the changes, then save and exit
+               (press twice to save a config with errors)
+:w / :wq / :q: Write / write and exit / exit (:q! drops changes)
+:e!          : Reload the file from disk, dropping changes
+:12 / :$     : Go to line 12 / the last line
+:%s/old/new/g: Replace text in every line (:s: this line; from v: the selection)
+:set number  : Show line numbers (:set nonumber hides them)
+:noh         : Stop highlighting the matches of the last search
+Esc          : Cancel editing / return to Normal mode
+Press any key (in Normal mode) to hide this help.";
+        let overlay_area = centered_rect(70, 90, area);
+        let help_block = Block::default().title("Editor Help").borders(Borders::ALL);
+        let help_paragraph = Paragraph::new(help_text)
+            .block(help_block)
+            .style(Style::default().fg(Color::Magenta));
+        f.render_widget(help_paragraph, overlay_area);
+    }
+
+    if let Some(review) = &editor_state.review {
+        draw_review(f, area, &editor_state.profile, review);
+        return;
+    }
+    if let Some(discard) = &editor_state.discard {
+        prompt::draw(f, area, discard);
+        return;
+    }
+
+    let prompt = match (&editor_state.command_line, &editor_state.searching) {
+        (Some(command), _) => Some(command),
+        (None, Some((search, _))) => Some(&search.pattern),
+        (None, None) => None,
+    };
+    if let Some(prompt) = prompt {
+        f.set_cursor_position((chunks[2].x + 1 + prompt.chars().count() as u16, chunks[2].y));
+        return;
+    }
+    let cursor_x = text_area.x + editor_state.gutter_width() + editor_state.display_col() as u16 + 1;
+    let cursor_y = text_area.y + (editor_state.cursor_row - scroll) as u16 + 1;
+    f.set_cursor_position((cursor_x, cursor_y));
+}
+
+/// Show the bytes in `range` of a highlighted line as selected. A selection
+/// reaching past the end of an empty line is shown as one selected blank.
+fn mark_selected(spans: Vec<Span<'_>>, range: Range<usize>) -> Vec<Span<'_>> {
+    let is_empty = spans.iter().all(|span| span.content.is_empty());
+    let mut marked = restyle(spans, range.clone(), Style::default().add_modifier(Modifier::REVERSED));
+    if is_empty && range.end > 0 {
+        marked.push(Span::styled(" ", Style::default().add_modifier(Modifier::REVERSED)));
+    }
+    marked
+}
+
+/// Patch the style of the bytes in `range` of a highlighted line.
+fn restyle(spans: Vec<Span<'_>>, range: Range<usize>, patch: Style) -> Vec<Span<'_>> {
+    let mut marked = Vec::with_capacity(spans.len() + 2);
+    let mut offset = 0;
+    for span in spans {
+        let text = span.content.to_string();
+        let (start, end) = (offset, offset + text.len());
+        offset = end;
+        let from = range.start.clamp(start, end) - start;
+        let to = range.end.clamp(start, end) - start;
+        if from == to || !text.is_char_boundary(from) || !text.is_char_boundary(to) {
+            marked.push(span);
+            continue;
+        }
+        let selected = span.style.patch(patch);
+        for (piece, style) in [(&text[..from], span.style), (&text[from..to], selected), (&text[to..], span.style)] {
+            if !piece.is_empty() {
+                marked.push(Span::styled(piece.to_string(), style));
+            }
+        }
+    }
+    marked
+}
+
+/// Draw the changes about to be saved over the editor.
+fn draw_review(f: &mut Frame, area: Rect, profile: &str, review: &Review) {
+    let overlay_area = centered_rect(80, 80, area);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(3)].as_ref())
+        .split(overlay_area);
+    let block = Block::default()
+        .title(format!(" Changes to {} ", profile_path(profile)))
+        .borders(Borders::ALL);
+    let (paragraph, help) = match &review.diff {
+        Ok(diff) => (
+            Paragraph::new(
+                diff.iter()
+                    .map(|line| Line::from(Span::styled(line.clone(), highlight::diff_line_style(line))))
+                    .collect::<Vec<_>>(),
+            ),
+            "Enter/y: Save | Esc/n: Keep editing | ↑/↓, PgUp/PgDn: Scroll",
+        ),
+        Err(e) => (
+            Paragraph::new(e.clone()).style(Style::default().fg(Color::Red)),
+            "Esc: Keep editing",
+        ),
+    };
+    f.render_widget(Clear, overlay_area);
+    f.render_widget(paragraph.scroll((review.scroll, 0)).block(block), chunks[0]);
+    f.render_widget(
+        Paragraph::new(help).block(Block::default().borders(Borders::ALL)),
+        chunks[1],
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustguard::testutil::{server_config, TestDirs};
+
+    const TEXT: &str = "[Interface]\nAddress = 10.0.0.1/24\nListenPort = 51820\nMTU = 1420\n\n[Peer]";
+
+    fn editor(text: &str) -> EditorState {
+        EditorState::new("rgt-editor".into(), text.into())
+    }
+
+    /// Type keys as a user would: `\n` is Enter, `\x1b` is Esc and `\x08` is
+    /// Backspace. Returns the last action the editor asked for.
+    fn type_keys(editor: &mut EditorState, keys: &str) -> Option<EditorAction> {
+        let mut action = None;
+        for c in keys.chars() {
+            let code = match c {
+                '\n' => KeyCode::Enter,
+                '\x1b' => KeyCode::Esc,
+                '\x08' => KeyCode::Backspace,
+                c => KeyCode::Char(c),
+            };
+            action = editor.handle_event(KeyEvent::new(code, KeyModifiers::NONE)).or(action);
+        }
+        action
+    }
+
+    fn ctrl(editor: &mut EditorState, c: char) -> Option<EditorAction> {
+        editor.handle_event(KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL))
+    }
+
+    fn message(editor: &EditorState) -> Option<&str> {
+        editor.message.as_ref().map(|(message, _)| message.as_str())
+    }
+
+    #[test]
+    fn edits_whole_characters_of_non_ascii_text() {
+        let mut editor = editor("# Name = Zürich");
+        type_keys(&mut editor, "$x");
+        assert_eq!(editor.lines[0], "# Name = Züric");
+        type_keys(&mut editor, "010lx");
+        assert_eq!(editor.lines[0], "# Name = Zric");
+        assert_eq!(editor.cursor_col, 10);
+
+        type_keys(&mut editor, "udlp");
+        assert_eq!(editor.lines[0], "# Name = Zrüic");
+        assert_eq!(editor.cursor_col, 11);
+        type_keys(&mut editor, "ylP");
+        assert_eq!(editor.lines[0], "# Name = Zrüüic");
+        type_keys(&mut editor, "3x");
+        assert_eq!(editor.lines[0], "# Name = Zrc");
+        type_keys(&mut editor, "u$");
+        assert_eq!((editor.cursor_col, editor.display_col()), (16, 14));
+        assert_eq!(editor.register, Some(Register::Chars("üüi".into())));
+    }
+
+    #[test]
+    fn moves_and_types_over_non_ascii_text() {
+        let mut editor = editor("Zürich Genè\nGenève");
+        type_keys(&mut editor, "ll");
+        assert_eq!(editor.cursor_col, 3);
+        type_keys(&mut editor, "h");
+        assert_eq!(editor.cursor_col, 1);
+        type_keys(&mut editor, "$");
+        assert_eq!(editor.cursor_col, 11);
+        type_keys(&mut editor, "0ee");
+        assert_eq!(editor.cursor_col, 11);
+
+        type_keys(&mut editor, "j0lllaé\x1b");
+        assert_eq!(editor.lines[1], "Genèéve");
+        type_keys(&mut editor, "vhd");
+        assert_eq!(editor.lines[1], "Genèe");
+        assert_eq!(editor.register, Some(Register::Chars("év".into())));
+
+        type_keys(&mut editor, "k0a€\x08\x08\x1b");
+        assert_eq!(editor.lines[0], "ürich Genè");
+    }
+
+    #[test]
+    fn substitutes_on_a_range() {
+        let mut editor = editor(TEXT);
+        type_keys(&mut editor, ":2,4s/0/5/\n");
+        assert_eq!(editor.lines[1..4], ["Address = 15.0.0.1/24", "ListenPort = 51825", "MTU = 1425"]);
+        assert_eq!(message(&editor), Some("3 substitutions on 3 lines"));
+        assert_eq!(editor.cursor_row, 3);
+
+        type_keys(&mut editor, ":%s/0/9/g\n");
+        assert_eq!(editor.lines[1], "Address = 15.9.9.1/24");
+        assert_eq!(message(&editor), Some("2 substitutions on 1 line"));
+        type_keys(&mut editor, ":4s#MTU#Mtu\n");
+        assert_eq!(editor.lines[3], "Mtu = 1425");
+
+        for (command, error) in [
+            (":s/nothing/x/", "Pattern not found: nothing"),
+            (":s/a/b/q", "Trailing characters: q"),
+            (":3,1s/a/b/", "Backwards range given"),
+            (":x,2s/a/b/", "Trailing characters: ,2s/a/b/"),
+            (":1,.$s/a/b/", "Invalid range: .$"),
+            (":s", "Usage: :s/old/new/ or :%s/old/new/g"),
+        ] {
+            type_keys(&mut editor, &format!("{}\n", command));
+            assert_eq!(message(&editor), Some(error), "{}", command);
+        }
+    }
+
+    #[test]
+    fn write_and_quit_commands() {
+        let _dirs = TestDirs::new();
+        let mut editor = editor(TEXT);
+        assert_eq!(type_keys(&mut editor, ":q\n"), Some(EditorAction::Cancel));
+        type_keys(&mut editor, "x");
+        assert_eq!(type_keys(&mut editor, ":q\n"), None);
+        assert_eq!(message(&editor), Some("No write since last change (add ! to override)"));
+        assert_eq!(type_keys(&mut editor, ":q!\n"), Some(EditorAction::Cancel));
+
+        // The profile does not exist, and the buffer lacks a PrivateKey.
+        assert_eq!(type_keys(&mut editor, ":w\n"), None);
+        assert!(message(&editor).is_some_and(|m| m.ends_with("error(s) in config (add ! to write anyway)")));
+        assert_eq!(type_keys(&mut editor, ":w!\n"), Some(EditorAction::Write));
+        assert_eq!(type_keys(&mut editor, ":wq!\n"), Some(EditorAction::Save));
+        assert_eq!(type_keys(&mut editor, ":x!\n"), Some(EditorAction::Save));
+        assert_eq!(type_keys(&mut editor, ":w! now\n"), None);
+        assert_eq!(message(&editor), Some("Trailing characters: now"));
+        assert_eq!(type_keys(&mut editor, ":wqa\n"), None);
+        assert_eq!(message(&editor), Some("Not an editor command: wqa"));
+    }
+
+    #[test]
+    fn a_failed_write_keeps_the_changes() {
+        let mut editor = editor(TEXT);
+        type_keys(&mut editor, "dd");
+        editor.written(false, "Error saving rgt-editor: disk full".into());
+        assert!(editor.is_modified());
+        assert_eq!(message(&editor), Some("Error saving rgt-editor: disk full"));
+        assert_eq!(type_keys(&mut editor, ":q\n"), None);
+
+        editor.written(true, "Updated config for rgt-editor".into());
+        assert!(!editor.is_modified());
+        assert_eq!(type_keys(&mut editor, ":q\n"), Some(EditorAction::Cancel));
+    }
+
+    const PEERS: &str = "[Peer]\nAllowedIPs = 10.0.0.2/32, 10.0.0.20/32\n\n[Peer]\nAllowedIPs = 10.0.0.2/32";
+
+    fn cursor(editor: &EditorState) -> Position {
+        (editor.cursor_row, editor.cursor_col)
+    }
+
+    #[test]
+    fn search_repeats_and_wraps_around() {
+        let mut editor = editor(PEERS);
+        type_keys(&mut editor, "/Peer\n");
+        assert_eq!(cursor(&editor), (0, 1));
+        assert_eq!(message(&editor), None);
+
+        type_keys(&mut editor, "n");
+        assert_eq!(cursor(&editor), (3, 1));
+        assert_eq!(message(&editor), None);
+        type_keys(&mut editor, "n");
+        assert_eq!(cursor(&editor), (0, 1));
+        assert_eq!(message(&editor), Some("search hit BOTTOM, continuing at TOP"));
+        type_keys(&mut editor, "N");
+        assert_eq!(cursor(&editor), (3, 1));
+        assert_eq!(message(&editor), Some("search hit TOP, continuing at BOTTOM"));
+
+        // An empty pattern repeats the last search, here backwards.
+        type_keys(&mut editor, "?\n");
+        assert_eq!(cursor(&editor), (0, 1));
+        type_keys(&mut editor, "3N");
+        assert_eq!(cursor(&editor), (3, 1));
+        assert_eq!(message(&editor), Some("search hit BOTTOM, continuing at TOP"));
+    }
+
+    #[test]
+    fn search_without_a_match_stays_put() {
+        let mut editor = editor(PEERS);
+        type_keys(&mut editor, "n");
+        assert_eq!(message(&editor), Some("No previous search"));
+
+        type_keys(&mut editor, "j/10.0.0.20");
+        assert_eq!(cursor(&editor), (1, 26));
+        type_keys(&mut editor, "x");
+        assert_eq!(cursor(&editor), (1, 0));
+        type_keys(&mut editor, "\n");
+        assert_eq!(cursor(&editor), (1, 0));
+        assert_eq!(message(&editor), Some("Pattern not found: 10.0.0.20x"));
+
+        type_keys(&mut editor, "/AllowedIPs\x1b");
+        assert_eq!(cursor(&editor), (1, 0));
+        type_keys(&mut editor, "n");
+        assert_eq!(message(&editor), Some("Pattern not found: 10.0.0.20x"));
+    }
+
+    #[test]
+    fn star_searches_for_the_token_under_the_cursor() {
+        let mut editor = editor(PEERS);
+        type_keys(&mut editor, "j*");
+        assert_eq!(cursor(&editor), (4, 0));
+        type_keys(&mut editor, "0w");
+        assert_eq!(cursor(&editor), (4, 11));
+        type_keys(&mut editor, "w*");
+        assert_eq!(cursor(&editor), (1, 13));
+        assert_eq!(message(&editor), Some("search hit BOTTOM, continuing at TOP"));
+        assert_eq!(editor.last_search.as_ref().map(|search| search.pattern.as_str()), Some("10.0.0.2/32"));
+
+        // Only whole tokens match, so 10.0.0.20/32 is skipped.
+        type_keys(&mut editor, "#");
+        assert_eq!(cursor(&editor), (4, 13));
+        type_keys(&mut editor, "kk*");
+        assert_eq!(message(&editor), Some("No string under cursor"));
+    }
+
+    #[test]
+    fn contents_keep_the_line_endings_of_the_file() {
+        for text in ["[Interface]\nMTU = 1420\n", "[Interface]\nMTU = 1420", "[Interface]\r\nMTU = 1420\r\n", ""] {
+            assert_eq!(editor(text).contents(), if text.is_empty() { "\n" } else { text });
+        }
+        let mut editor = editor("[Interface]\r\nMTU = 1420\r\n");
+        type_keys(&mut editor, "jo# note\x1b");
+        assert_eq!(editor.contents(), "[Interface]\r\nMTU = 1420\r\n# note\r\n");
+    }
+
+    #[test]
+    fn unchanged_buffers_are_not_written() {
+        let dirs = TestDirs::new();
+        let profile = dirs.profile("wg0", "[Interface]\r\nListenPort = 51820\r\n");
+        let mut editor = EditorState::new(profile.name.clone(), fs::read_to_string(profile.path()).unwrap());
+        assert_eq!(type_keys(&mut editor, ":w!\n"), None);
+        assert_eq!(message(&editor), Some("No changes to write."));
+        ctrl(&mut editor, 's');
+        ctrl(&mut editor, 's');
+        assert_eq!(message(&editor), Some("No changes to save."));
+    }
+
+    #[test]
+    fn saving_with_errors_needs_a_second_ctrl_s() {
+        let dirs = TestDirs::new();
+        let profile = dirs.profile("wg0", &server_config());
+        let text = server_config().replace("ListenPort = 51820", "ListenPort = 99999");
+        let mut editor = EditorState::new(profile.name.clone(), text);
+        assert_eq!(ctrl(&mut editor, 's'), None);
+        assert!(editor.review.is_none());
+        assert_eq!(message(&editor), Some("1 error(s) in config. Press Ctrl+S again to save anyway."));
+
+        // Any other key disarms it.
+        type_keys(&mut editor, "j");
+        ctrl(&mut editor, 's');
+        assert!(editor.review.is_none());
+
+        ctrl(&mut editor, 's');
+        let review = editor.review.as_ref().expect("the second Ctrl+S reviews the changes");
+        assert!(review.diff.as_ref().unwrap().contains(&"+ListenPort = 99999".to_string()));
+        assert_eq!(type_keys(&mut editor, "\n"), Some(EditorAction::Save));
+    }
+
+    #[test]
+    fn saving_without_errors_reviews_at_once() {
+        let dirs = TestDirs::new();
+        let profile = dirs.profile("wg0", &server_config());
+        let text = server_config().replace("ListenPort = 51820", "ListenPort = 51821");
+        let mut editor = EditorState::new(profile.name.clone(), text);
+        ctrl(&mut editor, 's');
+        assert!(editor.review.is_some());
+        type_keys(&mut editor, "\x1b");
+        assert!(editor.review.is_none());
+    }
+
+    #[test]
+    fn undo_after_a_counted_delete_restores_every_line() {
+        let mut editor = editor(TEXT);
+        type_keys(&mut editor, "j3dd");
+        assert_eq!(editor.lines, ["[Interface]", "", "[Peer]"]);
+        assert_eq!(message(&editor), Some("3 fewer lines"));
+
+        type_keys(&mut editor, "u");
+        assert_eq!(editor.lines.join("\n"), TEXT);
+        assert_eq!((editor.cursor_row, editor.cursor_col), (1, 0));
+        assert!(!editor.is_modified());
+
+        ctrl(&mut editor, 'r');
+        assert_eq!(editor.lines, ["[Interface]", "", "[Peer]"]);
+        ctrl(&mut editor, 'r');
+        assert_eq!(message(&editor), Some("Already at newest change"));
+    }
+
+    #[test]
+    fn undo_takes_back_an_insert_session_at_once() {
+        let mut editor = editor(TEXT);
+        type_keys(&mut editor, "GoPublicKey = x\nAllowedIPs = y\x1b");
+        assert_eq!(editor.lines.len(), 8);
+
+        type_keys(&mut editor, "u");
+        assert_eq!(editor.lines.join("\n"), TEXT);
+        type_keys(&mut editor, "u");
+        assert_eq!(message(&editor), Some("Already at oldest change"));
+    }
+
+    #[test]
+    fn counted_undo_and_redo() {
+        let mut editor = editor(TEXT);
+        type_keys(&mut editor, "ddddxx");
+        assert_eq!(editor.lines[0], "stenPort = 51820");
+
+        type_keys(&mut editor, "3u");
+        assert_eq!(editor.lines[0], "Address = 10.0.0.1/24");
+        type_keys(&mut editor, "2");
+        ctrl(&mut editor, 'r');
+        assert_eq!(editor.lines[0], "istenPort = 51820");
+
+        // A new change forgets what was undone.
+        type_keys(&mut editor, "x");
+        ctrl(&mut editor, 'r');
+        assert_eq!(editor.lines[0], "stenPort = 51820");
+        assert_eq!(message(&editor), Some("Already at newest change"));
+        type_keys(&mut editor, "10u");
+        assert_eq!(editor.lines.join("\n"), TEXT);
+    }
+}
@@ -0,0 +1,169 @@
+//! Syntax highlighting for wg-quick configs, built on the config tokenizer.
+
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::Span,
+};
+
+use rustguard::config::{tokenize_line, SectionKind, TokenKind};
+use rustguard::validate::{check_cidr, check_key};
+
+fn section_style(kind: SectionKind) -> Style {
+    match kind {
+        SectionKind::Unknown => Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        _ => Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+    }
+}
+
+fn key_style(known: bool) -> Style {
+    if known {
+        Style::default().fg(Color::LightBlue)
+    } else {
+        Style::default().fg(Color::Red).add_modifier(Modifier::UNDERLINED)
+    }
+}
+
+/// Style for a single comma-separated item of a value.
+fn value_item_style(item: &str) -> Style {
+    if check_key(item).is_ok() {
+        Style::default().fg(Color::Magenta)
+    } else if item.contains(['.', ':']) && check_cidr(item).is_ok() {
+        Style::default().fg(Color::Green)
+    } else {
+        Style::default().fg(Color::White)
+    }
+}
+
+/// Color each line of a config buffer.
+///
+/// The section each line belongs to is tracked so that keys unknown to that
+/// section can be flagged.
+pub fn highlight(lines: &[String]) -> Vec<Vec<Span<'_>>> {
+    // Keys before any header are errors, so treat them like an unknown section.
+    let mut section = SectionKind::Unknown;
+    lines.iter().map(|line| highlight_line(line, &mut section)).collect()
+}
+
+fn highlight_line<'a>(line: &'a str, section: &mut SectionKind) -> Vec<Span<'a>> {
+    let mut spans = Vec::new();
+    let mut pos = 0;
+    for token in tokenize_line(line) {
+        if token.range.start > pos {
+            spans.push(Span::raw(&line[pos..token.range.start]));
+        }
+        let text = &line[token.range.clone()];
+        match token.kind {
+            TokenKind::Section => {
+                *section = SectionKind::from_name(text[1..text.len() - 1].trim());
+                spans.push(Span::styled(text, section_style(*section)));
+            }
+            TokenKind::Key => {
+                let known = section.known_keys().iter().any(|k| k.eq_ignore_ascii_case(text));
+                spans.push(Span::styled(text, key_style(known)));
+            }
+            TokenKind::Equals => {
+                spans.push(Span::styled(text, Style::default().fg(Color::DarkGray)));
+            }
+            TokenKind::Value => {
+                let mut item_start = token.range.start;
+                for item in text.split(',') {
+                    let item_end = item_start + item.len();
+                    let trimmed = item.trim();
+                    let lead = item.len() - item.trim_start().len();
+                    spans.push(Span::raw(&line[item_start..item_start + lead]));
+                    spans.push(Span::styled(
+                        &line[item_start + lead..item_start + lead + trimmed.len()],
+                        value_item_style(trimmed),
+                    ));
+                    spans.push(Span::raw(&line[item_start + lead + trimmed.len()..item_end]));
+                    if item_end < token.range.end {
+                        spans.push(Span::styled(",", Style::default().fg(Color::DarkGray)));
+                    }
+                    item_start = item_end + 1;
+                }
+            }
+            TokenKind::Comment => spans.push(Span::styled(
+                text,
+                Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+            )),
+            TokenKind::Invalid => {
+                spans.push(Span::styled(text, Style::default().fg(Color::White).bg(Color::Red)))
+            }
+        }
+        pos = token.range.end;
+    }
+    if pos < line.len() {
+        spans.push(Span::raw(&line[pos..]));
+    }
+    spans.retain(|span| !span.content.is_empty());
+    spans
+}
+
+/// Style for a line of a unified diff.
+pub fn diff_line_style(line: &str) -> Style {
+    match line.chars().next() {
+        Some('+') => Style::default().fg(Color::Green),
+        Some('-') => Style::default().fg(Color::Red),
+        Some('@') => Style::default().fg(Color::Cyan),
+        _ => Style::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn joined(spans: &[Span]) -> String {
+        spans.iter().map(|span| span.content.as_ref()).collect()
+    }
+
+    fn style_of(spans: &[Span], text: &str) -> Style {
+        spans.iter().find(|span| span.content == text).map(|span| span.style).unwrap_or_default()
+    }
+
+    #[test]
+    fn spans_cover_the_whole_line() {
+        let lines: Vec<String> = [
+            "[Interface]",
+            "  PrivateKey = GHuMwljFfqd2a7cs6BaUOmHflK23zME8VNvC5B37S3k= # secret",
+            "Address=10.0.0.1/24 ,  fd00::1/64,",
+            "# Name = laptop",
+            "   ",
+            "",
+            "[Peer]\r",
+            "AllowedIPs = 0.0.0.0/0, ::/0\r",
+            "= no key",
+            "garbage # and a # comment\r",
+            "Endpoint = vpn.example.com:51820 #",
+        ]
+        .iter()
+        .map(|line| line.to_string())
+        .collect();
+        for (line, spans) in lines.iter().zip(highlight(&lines)) {
+            assert_eq!(&joined(&spans), line);
+            assert!(spans.iter().all(|span| !span.content.is_empty()), "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn keys_are_checked_against_their_section() {
+        let lines: Vec<String> = ["MTU = 1420", "[Interface]", "MTU = 1420", "[Peer]", "MTU = 1420"]
+            .iter()
+            .map(|line| line.to_string())
+            .collect();
+        let spans = highlight(&lines);
+        assert_eq!(style_of(&spans[0], "MTU"), key_style(false));
+        assert_eq!(style_of(&spans[2], "MTU"), key_style(true));
+        assert_eq!(style_of(&spans[4], "MTU"), key_style(false));
+    }
+
+    #[test]
+    fn comments_and_values_are_styled() {
+        let mut section = SectionKind::Peer;
+        let spans = highlight_line("AllowedIPs = 10.0.0.2/32, foo # note", &mut section);
+        assert_eq!(style_of(&spans, "10.0.0.2/32"), value_item_style("10.0.0.2/32"));
+        assert_eq!(style_of(&spans, "foo"), value_item_style("foo"));
+        assert!(style_of(&spans, "# note").add_modifier.contains(Modifier::ITALIC));
+        assert_eq!(section, SectionKind::Peer);
+    }
+}
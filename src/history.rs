@@ -0,0 +1,183 @@
+//! The history of every saved version of each profile.
+//!
+//! Each profile has a directory in the history store holding its versions,
+//! one file per distinct content named after a hash of it, and a `log` file
+//! with one line per saved change: when, by whom, which content and why.
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::profiles::{create_private_dir, rooted, write_private};
+
+/// Returns the directory RustGuard keeps its state in, based on the OS.
+pub fn state_path() -> String {
+    let system = match std::env::consts::OS {
+        "windows" => r"C:\ProgramData\rustguard\",
+        "macos" => "/usr/local/var/rustguard/",
+        _ => "/var/lib/rustguard/",
+    };
+    rooted(system, "state/")
+}
+
+/// Directory of the history store.
+pub fn history_path() -> String {
+    format!("{}history", state_path())
+}
+
+fn profile_dir(profile: &str) -> PathBuf {
+    Path::new(&history_path()).join(profile)
+}
+
+/// A recorded version of a profile.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Version {
+    /// Hash of the contents, naming the file they are stored in.
+    pub id: String,
+    pub saved_at: SystemTime,
+    pub author: String,
+    pub message: String,
+}
+
+impl Version {
+    fn parse(line: &str) -> Option<Self> {
+        let mut fields = line.splitn(4, '\t');
+        let millis: u64 = fields.next()?.parse().ok()?;
+        let id = fields.next()?.to_string();
+        let author = fields.next()?.to_string();
+        let message = fields.next().unwrap_or_default().to_string();
+        Some(Self { id, saved_at: UNIX_EPOCH + Duration::from_millis(millis), author, message })
+    }
+}
+
+/// 64-bit FNV-1a hash of the contents, in hex.
+fn content_id(contents: &str) -> String {
+    let hash = contents.bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("{:016x}", hash)
+}
+
+/// Who is making a change: the user who ran sudo, or the current user.
+pub fn author() -> String {
+    ["SUDO_USER", "USER", "USERNAME"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|user| !user.is_empty())
+        .unwrap_or_else(|| "unknown".into())
+}
+
+/// Record a new version of a profile. Nothing is recorded if the contents
+/// are those of the latest version.
+pub fn record(profile: &str, contents: &str, message: &str) -> Result<Option<Version>, String> {
+    let dir = profile_dir(profile);
+    create_private_dir(&history_path())?;
+    create_private_dir(&dir.display().to_string())?;
+
+    let id = content_id(contents);
+    if versions(profile).first().is_some_and(|latest| latest.id == id) {
+        return Ok(None);
+    }
+    let object = dir.join(&id);
+    match fs::read_to_string(&object) {
+        Ok(stored) if stored == contents => {}
+        Ok(_) => return Err(format!("{} holds different contents with the same hash", object.display())),
+        Err(_) => write_private(&object, contents.as_bytes())?,
+    }
+
+    let version = Version {
+        id,
+        saved_at: SystemTime::now(),
+        author: author(),
+        // The log has one version per line and tab-separated fields.
+        message: message.replace(['\t', '\n', '\r'], " "),
+    };
+    let millis = version.saved_at.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis();
+    let log = dir.join("log");
+    let mut options = fs::OpenOptions::new();
+    options.append(true).create(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options
+        .open(&log)
+        .and_then(|mut file| {
+            writeln!(file, "{}\t{}\t{}\t{}", millis, version.id, version.author, version.message)?;
+            file.sync_all()
+        })
+        .map_err(|e| format!("Failed to write {}: {}", log.display(), e))?;
+    Ok(Some(version))
+}
+
+/// Recorded versions of a profile, newest first.
+pub fn versions(profile: &str) -> Vec<Version> {
+    let log = fs::read_to_string(profile_dir(profile).join("log")).unwrap_or_default();
+    let mut versions: Vec<Version> = log.lines().filter_map(Version::parse).collect();
+    versions.reverse();
+    versions
+}
+
+/// The contents of a recorded version.
+pub fn read_version(profile: &str, version: &Version) -> Result<String, String> {
+    let path = profile_dir(profile).join(&version.id);
+    fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+}
+
+/// Move the history of a renamed profile along with it. If the new name
+/// already has a history, from a profile deleted earlier, both are kept
+/// and the renamed profile continues that one.
+pub fn rename_history(profile: &str, new_name: &str) -> Result<(), String> {
+    let (from, to) = (profile_dir(profile), profile_dir(new_name));
+    if !from.exists() || to.exists() {
+        return Ok(());
+    }
+    fs::rename(&from, &to).map_err(|e| format!("Failed to move the history of {}: {}", profile, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::TestDirs;
+
+    #[test]
+    fn parses_log_lines() {
+        let version = Version::parse("1700000000123\tabc\talice\tAdded a peer\twith a tab").unwrap();
+        assert_eq!(version.saved_at, UNIX_EPOCH + Duration::from_millis(1_700_000_000_123));
+        assert_eq!((version.id.as_str(), version.author.as_str()), ("abc", "alice"));
+        assert_eq!(version.message, "Added a peer\twith a tab");
+        assert_eq!(Version::parse("1\tabc\talice").unwrap().message, "");
+        assert_eq!(Version::parse("soon\tabc\talice\tmessage"), None);
+        assert_eq!(Version::parse("1\tabc"), None);
+        assert_eq!(Version::parse(""), None);
+    }
+
+    #[test]
+    fn content_ids_are_fnv1a() {
+        assert_eq!(content_id(""), "cbf29ce484222325");
+        assert_eq!(content_id("a"), "af63dc4c8601ec8c");
+        assert_ne!(content_id("[Interface]\n"), content_id("[Interface]\r\n"));
+    }
+
+    #[test]
+    fn records_versions_and_rolls_back() {
+        let _dirs = TestDirs::new();
+        let first = record("wg0", "one\n", "Created").unwrap().unwrap();
+        assert_eq!(first.id, content_id("one\n"));
+        // Saving the same contents again records nothing.
+        assert_eq!(record("wg0", "one\n", "Saved again").unwrap(), None);
+        let second = record("wg0", "two\n", "Changed\tthe\nport").unwrap().unwrap();
+        assert_eq!(second.message, "Changed the port");
+
+        // A rollback records the old contents as the newest version, stored once.
+        let contents = read_version("wg0", &first).unwrap();
+        assert_eq!(contents, "one\n");
+        let rollback = record("wg0", &contents, "Rolled back").unwrap().unwrap();
+        assert_eq!(rollback.id, first.id);
+
+        let messages: Vec<String> = versions("wg0").into_iter().map(|version| version.message).collect();
+        assert_eq!(messages, ["Rolled back", "Changed the port", "Created"]);
+        let objects = fs::read_dir(profile_dir("wg0")).unwrap().count();
+        assert_eq!(objects, 3, "the log and two distinct contents");
+        assert!(versions("wg1").is_empty());
+    }
+}
@@ -0,0 +1,75 @@
+//! Curve25519 key generation, equivalent to `wg genkey`, `wg pubkey` and `wg genpsk`.
+//!
+//! Keys are passed around base64-encoded, as they appear in config files.
+
+use wgctrl::types::Key;
+
+/// A private key and the public key derived from it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyPair {
+    pub private_key: String,
+    pub public_key: String,
+}
+
+/// Generate a new private key.
+pub fn generate_private_key() -> Result<String, String> {
+    Key::generate_private_key()
+        .map(String::from)
+        .map_err(|e| format!("Failed to generate private key: {}", e))
+}
+
+/// Derive the public key of a base64-encoded private key.
+pub fn public_key(private_key: &str) -> Result<String, String> {
+    let key = Key::try_from(private_key.trim()).map_err(|_| "Invalid private key".to_string())?;
+    Ok(String::from(key.public_key()))
+}
+
+/// Generate a new private key together with its public key.
+pub fn generate_key_pair() -> Result<KeyPair, String> {
+    let private_key = generate_private_key()?;
+    let public_key = public_key(&private_key)?;
+    Ok(KeyPair { private_key, public_key })
+}
+
+/// Generate a new preshared key.
+pub fn generate_preshared_key() -> Result<String, String> {
+    Key::generate_key()
+        .map(String::from)
+        .map_err(|e| format!("Failed to generate preshared key: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derives_public_keys_of_known_vectors() {
+        // Alice's and Bob's keys from RFC 7748, section 6.1.
+        let alice = public_key("dwdtCnMYpX08FsFyUbJmRd9ML4frwJkqsXf7pR25LCo=").unwrap();
+        assert_eq!(alice, "hSDwCYkwp1R0i33ctD73Wg2/Og0mOBr066SpjqqbTmo=");
+        let bob = public_key(" XasIfmJKikt54X+Lg4AO5m87sSkmGLb9HC+LJ/+I4Os=\n").unwrap();
+        assert_eq!(bob, "3p7bfXt9wbTTW2HC7OQ1Nz+DQ8hbeGdNrfx+FG+IK08=");
+    }
+
+    #[test]
+    fn rejects_malformed_private_keys() {
+        for key in [
+            "",
+            "not base64 at all!",
+            "dwdtCnMYpX08FsFyUbJmRd9ML4frwJkqsXf7pR25LC==",
+            // 31 and 33 bytes.
+            "dwdtCnMYpX08FsFyUbJmRd9ML4frwJkqsXf7pR25LA==",
+            "dwdtCnMYpX08FsFyUbJmRd9ML4frwJkqsXf7pR25LCoA",
+        ] {
+            assert_eq!(public_key(key), Err("Invalid private key".to_string()), "{:?}", key);
+        }
+    }
+
+    #[test]
+    fn generates_matching_key_pairs() {
+        let keys = generate_key_pair().unwrap();
+        assert_eq!(public_key(&keys.private_key), Ok(keys.public_key.clone()));
+        assert_ne!(keys.private_key, keys.public_key);
+        assert_ne!(generate_preshared_key().unwrap(), generate_preshared_key().unwrap());
+    }
+}
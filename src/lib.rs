@@ -0,0 +1,39 @@
+//! RustGuard: a WireGuard VPN manager.
+//!
+//! The library exposes everything the `rustguard` binary is built on, so the
+//! same profile handling, config model and connection control can be used
+//! from other tools:
+//!
+//! - [`profiles`]: where profiles live and which exist
+//! - [`history`]: every saved version of each profile
+//! - [`watcher`]: noticing when profiles are added, removed or renamed
+//! - [`config`]: a lossless, typed model of wg-quick config files
+//! - [`validate`]: pre-flight checks for configs
+//! - [`diff`]: line diffs between versions of a config
+//! - [`keys`]: key pair and preshared key generation
+//! - [`client`]: configs for new clients of a server profile
+//! - [`qr`]: profiles as QR codes, for the mobile apps
+//! - [`backend`]: bringing interfaces up and down, behind the [`backend::VpnBackend`] trait
+//! - [`status`]: runtime state of active interfaces
+//! - [`sync`]: applying profile edits to running interfaces, and noticing when they drift
+//! - [`poller`]: polling that state in the background
+//! - [`logging`]: the persistent status log
+
+pub mod backend;
+pub mod client;
+pub mod config;
+pub mod diff;
+pub mod history;
+pub mod keys;
+pub mod logging;
+#[cfg(target_os = "linux")]
+mod netlink;
+pub mod poller;
+pub mod profiles;
+pub mod qr;
+pub mod status;
+pub mod sync;
+#[doc(hidden)]
+pub mod testutil;
+pub mod validate;
+pub mod watcher;
@@ -0,0 +1,23 @@
+//! The persistent status log.
+
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+
+use crate::profiles::rooted;
+
+/// Returns the log file path based on the OS.
+pub fn log_file_path() -> String {
+    let system = match std::env::consts::OS {
+        "windows" => r"C:\ProgramData\rustguard\rustguard.log",
+        "macos" => "/usr/local/var/log/rustguard.log",
+        _ => "/var/log/rustguard.log",
+    };
+    rooted(system, "rustguard.log")
+}
+
+/// Write a persistent log entry. Logging is best effort: callers report a
+/// failure, e.g. a read-only log directory, but carry on.
+pub fn log_status(message: &str) -> io::Result<()> {
+    let mut file = OpenOptions::new().append(true).create(true).open(log_file_path())?;
+    writeln!(file, "{}", message)
+}
@@ -1,13 +1,11 @@
 use std::error::Error;
 use std::fs;
-use std::fs::OpenOptions;
-use std::io::Write;
-use std::process::Command;
+use std::sync::Arc;
 use std::time::Duration;
 
 use crossterm::{
     cursor,
-    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
+    event::{self, Event, KeyCode},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
@@ -15,10 +13,45 @@ use ratatui::{
     backend::CrosstermBackend,
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
-    widgets::{Block, Borders, List, ListItem, Paragraph},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
     Terminal,
 };
 
+use rustguard::backend::{self, toggle_vpn, VpnBackend};
+use rustguard::config::Config;
+use rustguard::logging::{log_file_path, log_status};
+use rustguard::poller::{self, Poller};
+use rustguard::profiles::{
+    clone_profile, delete_profile, import_profile, list_vpn_profiles, profile_path, rename_profile, save_profile,
+    trash_path,
+};
+use rustguard::sync;
+use rustguard::validate;
+use rustguard::watcher::{ProfileChange, ProfileWatcher};
+
+use backups::BackupsState;
+use details::DetailsState;
+use drift::DriftState;
+use editor::{EditorAction, EditorState};
+use prompt::{PromptAction, PromptState};
+use share::ShareState;
+use versions::HistoryState;
+use wizard::WizardState;
+
+mod backups;
+mod cli;
+mod details;
+mod drift;
+mod editor;
+mod highlight;
+mod motions;
+mod prompt;
+mod search;
+mod share;
+mod versions;
+mod wizard;
+
 /// Returns a centered rectangle with the given width and height percentages of the given rect.
 fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     let popup_layout = Layout::default()
@@ -47,334 +80,122 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     horizontal_layout[1]
 }
 
-/// Returns the configuration directory based on the OS.
-fn config_path() -> &'static str {
-    match std::env::consts::OS {
-        "windows" => r"C:\ProgramData\rustguard\wireguard\",
-        "macos" => "/usr/local/etc/wireguard/",
-        _ => "/etc/wireguard/",
-    }
-}
-
-/// Returns the log file path based on the OS.
-fn log_file_path() -> &'static str {
-    match std::env::consts::OS {
-        "windows" => r"C:\ProgramData\rustguard\rustguard.log",
-        "macos" => "/usr/local/var/log/rustguard.log",
-        _ => "/var/log/rustguard.log",
-    }
+/// All the screens our application can show.
+enum Screen {
+    Manager,    // Main manager UI
+    Status,     // Persistent status log
+    Help,       // Global keybindings help
+    Details(Box<DetailsState>), // VPN details view
+    Editor(Box<EditorState>), // Config editor
+    Wizard(WizardState), // New-profile wizard
+    Prompt(PromptState), // Question popup over the manager
+    Share(ShareState), // Profile as a QR code
+    Backups(BackupsState), // Previous versions of a profile
+    History(HistoryState), // Recorded changes to a profile
+    Drift(DriftState), // Running interface vs its profile
 }
 
-/// Write a persistent log entry.
-fn log_status(message: &str) {
-    let log_path = log_file_path();
-    let mut file = OpenOptions::new()
-        .append(true)
-        .create(true)
-        .open(log_path)
-        .expect("Failed to open log file");
-    writeln!(file, "{}", message).expect("Failed to write to log file");
+/// Re-read the profile list, keeping the selection on the same profile, or
+/// moving it to `select` if given, where possible.
+fn reload_profiles(profiles: &mut Vec<String>, selected_index: &mut usize, select: Option<&str>) {
+    let wanted = select.map(str::to_string).or_else(|| profiles.get(*selected_index).cloned());
+    *profiles = list_vpn_profiles();
+    *selected_index = wanted
+        .and_then(|wanted| profiles.iter().position(|p| *p == wanted))
+        .unwrap_or_else(|| (*selected_index).min(profiles.len().saturating_sub(1)));
 }
 
-/// List all VPN profiles (config files) in the configuration directory (without the ".conf" suffix).
-fn list_vpn_profiles() -> Vec<String> {
-    let path = config_path();
-    if let Ok(entries) = fs::read_dir(path) {
-        entries
-            .filter_map(|entry| entry.ok())
-            .map(|entry| entry.file_name().into_string().unwrap())
-            .filter(|name| name.ends_with(".conf"))
-            .map(|name| name.trim_end_matches(".conf").to_string())
-            .collect()
-    } else {
-        vec![]
+/// Show a message in the status log and keep it in the log file. A log
+/// file that cannot be written is reported in the status log instead.
+fn record_status(status_log: &mut Vec<String>, msg: &str) {
+    status_log.push(msg.to_string());
+    if let Err(e) = log_status(msg) {
+        status_log.push(format!("⚠️ Failed to write to {}: {}", log_file_path(), e));
     }
 }
 
-/// Toggle the VPN connection using "wg-quick up/down".
-/// For the "up" action, check that the configuration file is not empty.
-fn toggle_vpn(profile: &str, action: &str) -> String {
-    if action == "up" {
-        let filename = format!("{}{}.conf", config_path(), profile);
-        if let Ok(content) = fs::read_to_string(&filename) {
-            if content.trim().is_empty() {
-                return format!("❌ Failed to start VPN: configuration file {} is empty.", filename);
-            }
-        } else {
-            return format!("❌ Failed to read configuration file {}.", filename);
-        }
-    }
-
-    let output = Command::new("sudo")
-        .arg("wg-quick")
-        .arg(action)
-        .arg(profile)
-        .output()
-        .expect("Failed to execute wg-quick command");
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let stderr = String::from_utf8_lossy(&output.stderr);
-
-    if output.status.success() {
-        format!("✅ {} VPN {} successfully\n{}", profile, action, stdout)
-    } else {
-        format!("❌ Failed to {} VPN {}:\n{}", action, profile, stderr)
-    }
+/// Whether the interface of a profile is currently up.
+fn is_active(vpn_backend: &dyn VpnBackend, profile: &str) -> bool {
+    vpn_backend
+        .list_interfaces()
+        .is_ok_and(|active| active.iter().any(|a| a == profile))
 }
 
-/// Get active VPN interfaces by parsing "wg show" output.
-fn get_active_vpns() -> Vec<String> {
-    let output = Command::new("wg")
-        .arg("show")
-        .output()
-        .expect("Failed to get VPN status");
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let mut active_vpns = Vec::new();
-
-    for line in stdout.lines() {
-        if line.contains("interface:") {
-            if let Some(interface) = line.split_whitespace().nth(1) {
-                active_vpns.push(interface.to_string());
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match cli::parse(&args) {
+        Ok(None) => run_tui(Arc::from(backend::from_env()), Screen::Manager, false),
+        Ok(Some(cli::Command::Edit(profile))) => {
+            if !cli::require_profile(&profile) {
+                std::process::exit(cli::EXIT_FAILURE);
             }
+            let filename = profile_path(&profile);
+            let content = fs::read_to_string(&filename).unwrap_or_default();
+            let editor_state = EditorState::new(profile, content);
+            run_tui(Arc::from(backend::from_env()), Screen::Editor(Box::new(editor_state)), true)
         }
-    }
-    active_vpns
-}
-
-/// Get full details for a VPN interface (using "wg show <interface>").
-fn get_vpn_details(interface: &str) -> String {
-    let output = Command::new("wg")
-        .arg("show")
-        .arg(interface)
-        .output()
-        .expect("Failed to get VPN details");
-    String::from_utf8_lossy(&output.stdout).to_string()
-}
-
-/// Minimal Vim–like editor mode.
-#[derive(Clone, Debug, PartialEq)]
-enum EditorMode {
-    Normal,
-    Insert,
-}
-
-/// A minimal multi–line editor state.
-#[derive(Clone)]
-struct EditorState {
-    profile: String,
-    lines: Vec<String>,
-    cursor_row: usize,
-    cursor_col: usize,
-    mode: EditorMode,
-    /// When true, the editor overlay cheatsheet is visible.
-    show_cheatsheet: bool,
-}
-
-impl EditorState {
-    fn new(profile: String, content: String) -> Self {
-        let lines: Vec<String> = if content.is_empty() {
-            vec![String::new()]
-        } else {
-            content.lines().map(|l| l.to_string()).collect()
-        };
-        Self {
-            profile,
-            lines,
-            cursor_row: 0,
-            cursor_col: 0,
-            mode: EditorMode::Normal,
-            show_cheatsheet: false,
-        }
-    }
-
-    /// Handle key events while editing.
-    ///
-    /// Returns Some(result) if editing is finished:
-    /// - Some("saved") if the user pressed Ctrl+S to save,
-    /// - Some("cancel") if the user pressed Esc (in Normal mode) to cancel.
-    /// Otherwise returns None.
-    fn handle_event(&mut self, key: KeyEvent) -> Option<&'static str> {
-        match self.mode {
-            EditorMode::Normal => {
-                if self.show_cheatsheet {
-                    self.show_cheatsheet = false;
-                    return None;
-                }
-                match key.code {
-                    KeyCode::Char('h') | KeyCode::Left => {
-                        if self.cursor_col > 0 {
-                            self.cursor_col -= 1;
-                        }
-                    }
-                    KeyCode::Char('l') | KeyCode::Right => {
-                        if let Some(line) = self.lines.get(self.cursor_row) {
-                            if self.cursor_col < line.len() {
-                                self.cursor_col += 1;
-                            }
-                        }
-                    }
-                    KeyCode::Char('k') | KeyCode::Up => {
-                        if self.cursor_row > 0 {
-                            self.cursor_row -= 1;
-                            self.cursor_col = self.cursor_col.min(self.lines[self.cursor_row].len());
-                        }
-                    }
-                    KeyCode::Char('j') | KeyCode::Down => {
-                        if self.cursor_row + 1 < self.lines.len() {
-                            self.cursor_row += 1;
-                            self.cursor_col = self.cursor_col.min(self.lines[self.cursor_row].len());
-                        }
-                    }
-                    KeyCode::Char('i') => {
-                        self.mode = EditorMode::Insert;
-                        execute!(std::io::stdout(), cursor::Show).ok();
-                    }
-                    KeyCode::Char('a') => {
-                        if let Some(line) = self.lines.get(self.cursor_row) {
-                            if self.cursor_col < line.len() {
-                                self.cursor_col += 1;
-                            }
-                        }
-                        self.mode = EditorMode::Insert;
-                        execute!(std::io::stdout(), cursor::Show).ok();
-                    }
-                    KeyCode::Char('o') => {
-                        self.cursor_row += 1;
-                        self.lines.insert(self.cursor_row, String::new());
-                        self.cursor_col = 0;
-                        self.mode = EditorMode::Insert;
-                        execute!(std::io::stdout(), cursor::Show).ok();
-                    }
-                    KeyCode::Char('x') => {
-                        if let Some(line) = self.lines.get_mut(self.cursor_row) {
-                            if self.cursor_col < line.len() {
-                                line.remove(self.cursor_col);
-                            }
-                        }
-                    }
-                    KeyCode::Char('D') => {
-                        if self.lines.len() > 1 {
-                            self.lines.remove(self.cursor_row);
-                            if self.cursor_row >= self.lines.len() {
-                                self.cursor_row = self.lines.len() - 1;
-                            }
-                            self.cursor_col = self.cursor_col.min(self.lines[self.cursor_row].len());
-                        } else {
-                            self.lines[0].clear();
-                            self.cursor_col = 0;
-                        }
-                    }
-                    KeyCode::Char('?') => {
-                        self.show_cheatsheet = !self.show_cheatsheet;
-                    }
-                    KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        return Some("saved");
-                    }
-                    KeyCode::Esc => return Some("cancel"),
-                    _ => {}
-                }
-            }
-            EditorMode::Insert => {
-                match key.code {
-                    KeyCode::Esc => {
-                        self.mode = EditorMode::Normal;
-                    }
-                    KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        if let Some(line) = self.lines.get_mut(self.cursor_row) {
-                            line.insert(self.cursor_col, c);
-                            self.cursor_col += 1;
-                        }
-                    }
-                    KeyCode::Enter => {
-                        if let Some(line) = self.lines.get_mut(self.cursor_row) {
-                            let new_line = line.split_off(self.cursor_col);
-                            self.lines.insert(self.cursor_row + 1, new_line);
-                            self.cursor_row += 1;
-                            self.cursor_col = 0;
-                        }
-                    }
-                    KeyCode::Backspace => {
-                        if self.cursor_col > 0 {
-                            if let Some(line) = self.lines.get_mut(self.cursor_row) {
-                                line.remove(self.cursor_col - 1);
-                                self.cursor_col -= 1;
-                            }
-                        } else if self.cursor_row > 0 {
-                            let current_line = self.lines.remove(self.cursor_row);
-                            self.cursor_row -= 1;
-                            self.cursor_col = self.lines[self.cursor_row].len();
-                            self.lines[self.cursor_row].push_str(&current_line);
-                        }
-                    }
-                    KeyCode::Left => {
-                        if self.cursor_col > 0 {
-                            self.cursor_col -= 1;
-                        } else if self.cursor_row > 0 {
-                            self.cursor_row -= 1;
-                            self.cursor_col = self.lines[self.cursor_row].len();
-                        }
-                    }
-                    KeyCode::Right => {
-                        if let Some(line) = self.lines.get(self.cursor_row) {
-                            if self.cursor_col < line.len() {
-                                self.cursor_col += 1;
-                            } else if self.cursor_row + 1 < self.lines.len() {
-                                self.cursor_row += 1;
-                                self.cursor_col = 0;
-                            }
-                        }
-                    }
-                    KeyCode::Up => {
-                        if self.cursor_row > 0 {
-                            self.cursor_row -= 1;
-                            self.cursor_col = self.cursor_col.min(self.lines[self.cursor_row].len());
-                        }
-                    }
-                    KeyCode::Down => {
-                        if self.cursor_row + 1 < self.lines.len() {
-                            self.cursor_row += 1;
-                            self.cursor_col = self.cursor_col.min(self.lines[self.cursor_row].len());
-                        }
-                    }
-                    KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        return Some("saved");
-                    }
-                    _ => {}
-                }
-            }
+        Ok(Some(command)) => std::process::exit(cli::run(&*backend::from_env(), command)),
+        Err(e) => {
+            eprintln!("rustguard: {}\n\n{}", e, cli::USAGE);
+            std::process::exit(cli::EXIT_USAGE);
         }
-        None
     }
 }
 
-/// All the screens our application can show.
-enum Screen {
-    Manager,    // Main manager UI
-    Status,     // Persistent status log
-    Help,       // Global keybindings help
-    Details { interface: String, details: String }, // VPN details view
-    Editor(EditorState), // Config editor
-}
-
-fn main() -> Result<(), Box<dyn Error>> {
+/// Run the interactive UI starting on the given screen.
+/// With `exit_after_edit`, the UI quits as soon as the editor is closed.
+///
+/// Interface state is polled on a background thread; the UI only draws the
+/// latest snapshot.
+fn run_tui(
+    vpn_backend: Arc<dyn VpnBackend>,
+    mut screen: Screen,
+    exit_after_edit: bool,
+) -> Result<(), Box<dyn Error>> {
     enable_raw_mode()?;
     let mut stdout = std::io::stdout();
     execute!(stdout, EnterAlternateScreen, cursor::Hide)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let profiles = list_vpn_profiles();
+    let mut profiles = list_vpn_profiles();
     let mut selected_index: usize = 0;
     let mut status_log: Vec<String> = Vec::new();
-    let mut screen = Screen::Manager;
+    let poller = Poller::spawn(Arc::clone(&vpn_backend), poller::interval_from_env());
+    let mut active_vpns: Vec<String> = Vec::new();
+    let mut drifted_vpns: Vec<String> = Vec::new();
+    let watcher = ProfileWatcher::spawn();
+    if let Screen::Editor(_) = screen {
+        execute!(terminal.backend_mut(), cursor::Show)?;
+    }
 
     loop {
-        let active_vpns = get_active_vpns();
+        let changes = watcher.changes();
+        if !changes.is_empty() {
+            // Follow the selected profile if another tool renamed it.
+            let selected = changes.iter().fold(profiles.get(selected_index).cloned(), |selected, change| {
+                match change {
+                    ProfileChange::Renamed { from, to } if selected.as_ref() == Some(from) => Some(to.clone()),
+                    _ => selected,
+                }
+            });
+            reload_profiles(&mut profiles, &mut selected_index, selected.as_deref());
+            poller.refresh();
+        }
+        if let Some(snapshot) = poller.latest() {
+            active_vpns = snapshot.active.unwrap_or_default();
+            drifted_vpns = snapshot.drifted;
+            if let (Screen::Details(details_state), Some((interface, status))) = (&mut screen, snapshot.watched) {
+                if interface == details_state.interface {
+                    details_state.update(snapshot.taken_at, status);
+                }
+            }
+        }
 
         terminal.draw(|f| {
             let area = f.area();
-            match &screen {
-                Screen::Manager => {
+            match &mut screen {
+                Screen::Manager | Screen::Prompt(_) => {
                     let chunks = Layout::default()
                         .direction(Direction::Vertical)
                         .constraints(
@@ -406,7 +227,17 @@ fn main() -> Result<(), Box<dyn Error>> {
                             } else {
                                 Style::default()
                             };
-                            ListItem::new(p.clone()).style(style)
+                            if drifted_vpns.contains(p) {
+                                ListItem::new(Line::from(vec![
+                                    Span::styled(p.clone(), style),
+                                    Span::styled(
+                                        " ⚠ differs from its file (F)",
+                                        Style::default().fg(Color::Yellow),
+                                    ),
+                                ]))
+                            } else {
+                                ListItem::new(p.clone()).style(style)
+                            }
                         })
                         .collect();
 
@@ -423,13 +254,19 @@ fn main() -> Result<(), Box<dyn Error>> {
                             active_vpns.join(", ")
                         },
                     )
-                    .block(Block::default().borders(Borders::ALL).title(" Active Connections "));
+                    .block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .title(format!(" Active Connections ({}) ", vpn_backend.name())),
+                    );
                     f.render_widget(active_conns, chunks[1]);
 
                     let instructions = Paragraph::new(
                         "↑/k, ↓/j: Navigate | Enter: Connect/Disconnect | D: Details | \
-                         E: Edit Config | Q: Quit",
+                         E: Edit Config | N: New | C: Clone | R: Rename | X: Delete | I: Import | \
+                         A: Apply Live | F: Drift | O: QR Code | B: Backups | V: History | Q: Quit",
                     )
+                    .wrap(Wrap { trim: true })
                     .block(Block::default().borders(Borders::ALL));
                     f.render_widget(instructions, chunks[2]);
 
@@ -464,6 +301,16 @@ fn main() -> Result<(), Box<dyn Error>> {
 Enter: Connect/Disconnect VPN
 D: VPN Details
 E: Edit Config
+A: Apply Config Edits to a Running VPN
+F: Compare a Running VPN with its Config File
+N: New Profile
+C: Clone Profile
+R: Rename Profile
+X: Delete Profile (moved to the trash)
+I: Import Profile
+O: Share Profile as a QR Code
+B: Profile Backups
+V: Profile History
 S: View Status Log
 W: WireGuard Manager
 H: Show Help
@@ -474,66 +321,16 @@ Press any key to return.";
                     let paragraph = Paragraph::new(help_message).block(block);
                     f.render_widget(paragraph, area);
                 }
-                Screen::Details { interface, details } => {
-                    let block = Block::default()
-                        .title(format!(" VPN Details: {} (Press any key to return)", interface))
-                        .borders(Borders::ALL);
-                    let paragraph = Paragraph::new(details.clone()).block(block);
-                    f.render_widget(paragraph, area);
-                }
-                Screen::Editor(editor_state) => {
-                    let content = editor_state.lines.join("\n");
-                    let block = Block::default().title(format!(
-                        " Editing {}{}.conf (Ctrl+S: Save, Esc: Cancel) ",
-                        config_path(),
-                        editor_state.profile
-                    ))
-                    .borders(Borders::ALL);
-                    let paragraph = Paragraph::new(content).block(block);
-                    f.render_widget(paragraph, area);
-
-                    let mode_str = match editor_state.mode {
-                        EditorMode::Normal => "NORMAL",
-                        EditorMode::Insert => "INSERT",
-                    };
-                    let footer_text = format!(
-                        "Mode: {} | Line: {} Col: {}",
-                        mode_str,
-                        editor_state.cursor_row + 1,
-                        editor_state.cursor_col + 1
-                    );
-                    let footer_area = Layout::default()
-                        .direction(Direction::Vertical)
-                        .constraints([Constraint::Min(0), Constraint::Length(1)].as_ref())
-                        .split(area);
-                    let footer = Paragraph::new(footer_text)
-                        .style(Style::default().fg(Color::Yellow));
-                    f.render_widget(footer, footer_area[1]);
-
-                    if editor_state.show_cheatsheet {
-                        let help_text = "Editor Cheatsheet (Normal mode):
-i      : Enter Insert mode
-a      : Append (move right then insert)
-o      : Open new line below
-h/j/k/l or ←/↓/↑/→: Move cursor
-x      : Delete character under cursor
-D      : Delete current line
-?      : Toggle this help
-Ctrl+S : Save and exit
-Esc    : Cancel editing / return to Normal mode
-Press any key (in Normal mode) to hide this help.";
-                        let overlay_area = centered_rect(60, 40, area);
-                        let help_block = Block::default().title("Editor Help").borders(Borders::ALL);
-                        let help_paragraph = Paragraph::new(help_text)
-                            .block(help_block)
-                            .style(Style::default().fg(Color::Magenta));
-                        f.render_widget(help_paragraph, overlay_area);
-                    }
-
-                    let cursor_x = area.x + editor_state.cursor_col as u16 + 1;
-                    let cursor_y = area.y + editor_state.cursor_row as u16 + 1;
-                    f.set_cursor_position((cursor_x, cursor_y));
-                }
+                Screen::Details(details_state) => details::draw(f, area, details_state),
+                Screen::Editor(editor_state) => editor::draw(f, area, editor_state),
+                Screen::Wizard(wizard_state) => wizard::draw(f, area, wizard_state),
+                Screen::Share(share_state) => share::draw(f, area, share_state),
+                Screen::Backups(backups_state) => backups::draw(f, area, backups_state),
+                Screen::History(history_state) => versions::draw(f, area, history_state),
+                Screen::Drift(drift_state) => drift::draw(f, area, drift_state),
+            }
+            if let Screen::Prompt(prompt_state) = &screen {
+                prompt::draw(f, area, prompt_state);
             }
         })?;
 
@@ -549,34 +346,120 @@ Press any key (in Normal mode) to hide this help.";
                             KeyCode::Char('d') => {
                                 if profiles.is_empty() { continue; }
                                 let selected = profiles[selected_index].clone();
-                                let details = get_vpn_details(&selected);
-                                screen = Screen::Details { interface: selected, details };
+                                poller.watch(Some(selected.clone()));
+                                screen = Screen::Details(Box::new(DetailsState::new(&*vpn_backend, selected)));
+                            }
+                            KeyCode::Char('n') => { screen = Screen::Wizard(WizardState::new()); }
+                            KeyCode::Char('c') => {
+                                if profiles.is_empty() { continue; }
+                                let selected = profiles[selected_index].clone();
+                                let copy = format!("{}-copy", selected);
+                                let valid = validate::check_interface_name(&copy).is_ok();
+                                let initial = if valid { &copy } else { &selected };
+                                let question = format!("Name of the copy of {}:", selected);
+                                screen = Screen::Prompt(PromptState::input(
+                                    PromptAction::Clone(selected.clone()),
+                                    "Clone Profile",
+                                    &question,
+                                    initial,
+                                ));
+                            }
+                            KeyCode::Char('r') => {
+                                if profiles.is_empty() { continue; }
+                                let selected = profiles[selected_index].clone();
+                                if is_active(&*vpn_backend, &selected) {
+                                    status_log.push(format!(
+                                        "Cannot rename {} while it is up; bring it down first.",
+                                        selected
+                                    ));
+                                    continue;
+                                }
+                                let question = format!("New name for {}:", selected);
+                                screen = Screen::Prompt(PromptState::input(
+                                    PromptAction::Rename(selected.clone()),
+                                    "Rename Profile",
+                                    &question,
+                                    &selected,
+                                ));
+                            }
+                            KeyCode::Char('x') => {
+                                if profiles.is_empty() { continue; }
+                                let selected = profiles[selected_index].clone();
+                                if is_active(&*vpn_backend, &selected) {
+                                    status_log.push(format!(
+                                        "Cannot delete {} while it is up; bring it down first.",
+                                        selected
+                                    ));
+                                    continue;
+                                }
+                                let question = format!(
+                                    "Delete profile {}? The file will be moved to {}.",
+                                    selected,
+                                    trash_path()
+                                );
+                                screen = Screen::Prompt(PromptState::confirm(
+                                    PromptAction::Delete(selected),
+                                    "Delete Profile",
+                                    &question,
+                                ));
+                            }
+                            KeyCode::Char('i') => {
+                                screen = Screen::Prompt(PromptState::input(
+                                    PromptAction::Import,
+                                    "Import Profile",
+                                    "Path of the .conf file to import (the profile is named after the file):",
+                                    "",
+                                ));
+                            }
+                            KeyCode::Char('a') => {
+                                if profiles.is_empty() { continue; }
+                                let msg = match sync::apply_live(&*vpn_backend, &profiles[selected_index]) {
+                                    Ok(msg) | Err(msg) => msg,
+                                };
+                                poller.refresh();
+                                record_status(&mut status_log, &msg);
+                            }
+                            KeyCode::Char('f') => {
+                                if profiles.is_empty() { continue; }
+                                let selected = profiles[selected_index].clone();
+                                screen = Screen::Drift(DriftState::new(&*vpn_backend, selected));
+                            }
+                            KeyCode::Char('o') => {
+                                if profiles.is_empty() { continue; }
+                                screen = Screen::Share(ShareState::new(profiles[selected_index].clone()));
+                            }
+                            KeyCode::Char('b') => {
+                                if profiles.is_empty() { continue; }
+                                screen = Screen::Backups(BackupsState::new(profiles[selected_index].clone()));
+                            }
+                            KeyCode::Char('v') => {
+                                if profiles.is_empty() { continue; }
+                                screen = Screen::History(HistoryState::new(profiles[selected_index].clone()));
                             }
                             KeyCode::Char('e') => {
                                 if profiles.is_empty() { continue; }
                                 let selected = profiles[selected_index].clone();
-                                let filename = format!("{}{}.conf", config_path(), selected);
+                                let filename = profile_path(&selected);
                                 let content = fs::read_to_string(&filename).unwrap_or_default();
                                 let editor_state = EditorState::new(selected, content);
-                                screen = Screen::Editor(editor_state);
+                                screen = Screen::Editor(Box::new(editor_state));
                                 execute!(std::io::stdout(), cursor::Show).ok();
                             }
                             KeyCode::Up | KeyCode::Char('k') => {
-                                if selected_index > 0 { selected_index -= 1; }
+                                selected_index = selected_index.saturating_sub(1);
                             }
-                            KeyCode::Down | KeyCode::Char('j') => {
-                                if selected_index + 1 < profiles.len() { selected_index += 1; }
+                            KeyCode::Down | KeyCode::Char('j') if selected_index + 1 < profiles.len() => {
+                                selected_index += 1;
                             }
                             KeyCode::Enter => {
                                 if profiles.is_empty() { continue; }
                                 let selected = profiles[selected_index].clone();
-                                let msg = if active_vpns.contains(&selected) {
-                                    toggle_vpn(&selected, "down")
-                                } else {
-                                    toggle_vpn(&selected, "up")
+                                let action = if active_vpns.contains(&selected) { "down" } else { "up" };
+                                let msg = match toggle_vpn(&*vpn_backend, &selected, action) {
+                                    Ok(msg) | Err(msg) => msg,
                                 };
-                                status_log.push(msg.clone());
-                                log_status(&msg);
+                                poller.refresh();
+                                record_status(&mut status_log, &msg);
                             }
                             _ => {}
                         }
@@ -594,34 +477,161 @@ Press any key (in Normal mode) to hide this help.";
                 Screen::Help => {
                     if let Event::Key(_) = ev { screen = Screen::Manager; }
                 }
-                Screen::Details { .. } => {
-                    if let Event::Key(_) = ev { screen = Screen::Manager; }
+                Screen::Details(details_state) => {
+                    if let Event::Key(key) = ev {
+                        if details_state.handle_event(key) {
+                            poller.watch(None);
+                            screen = Screen::Manager;
+                        }
+                    }
                 }
                 Screen::Editor(editor_state) => {
                     if let Event::Key(key) = ev {
-                        if let Some(result) = editor_state.handle_event(key) {
-                            if result == "saved" {
-                                let filename = format!("{}{}.conf", config_path(), editor_state.profile);
-                                if let Err(e) = fs::write(&filename, editor_state.lines.join("\n")) {
-                                    let err_msg = format!("Error saving file {}: {}", filename, e);
-                                    status_log.push(err_msg.clone());
-                                    log_status(&err_msg);
-                                } else {
-                                    let msg = format!("Updated config for {}", editor_state.profile);
-                                    status_log.push(msg.clone());
-                                    log_status(&msg);
+                        if let Some(action) = editor_state.handle_event(key) {
+                            if action != EditorAction::Cancel {
+                                let content = editor_state.contents();
+                                let result = save_profile(&editor_state.profile, &content, "Edited in the editor");
+                                let msg = match &result {
+                                    Err(e) => format!("Error saving {}: {}", editor_state.profile, e),
+                                    Ok(outcome) => {
+                                        let mut msg = format!("Updated config for {}", editor_state.profile);
+                                        if is_active(&*vpn_backend, &editor_state.profile) {
+                                            msg.push_str("; it is up, press A to apply the changes live");
+                                        }
+                                        outcome.report(msg)
+                                    }
+                                };
+                                record_status(&mut status_log, &msg);
+                                // Stay in the editor after `:w`, or when saving failed, so no edits are lost.
+                                if action == EditorAction::Write || result.is_err() {
+                                    editor_state.written(result.is_ok(), msg);
+                                    continue;
                                 }
                             }
+                            if exit_after_edit {
+                                break;
+                            }
                             screen = Screen::Manager;
                             execute!(std::io::stdout(), cursor::Hide).ok();
                         }
                     }
                 }
+                Screen::Prompt(prompt_state) => {
+                    if let Event::Key(key) = ev {
+                        match prompt_state.handle_event(key) {
+                            None => {}
+                            Some(None) => screen = Screen::Manager,
+                            Some(Some(answer)) => {
+                                // On success: the profile to select afterwards and a status message.
+                                let result = match &prompt_state.action {
+                                    PromptAction::Clone(profile) => clone_profile(profile, &answer)
+                                        .map(|_| (answer.clone(), format!("Cloned {} to {}", profile, answer))),
+                                    PromptAction::Rename(profile) if is_active(&*vpn_backend, profile) => {
+                                        Err(format!("Cannot rename {} while it is up; bring it down first.", profile))
+                                    }
+                                    PromptAction::Rename(profile) => rename_profile(profile, &answer)
+                                        .map(|_| (answer.clone(), format!("Renamed {} to {}", profile, answer))),
+                                    PromptAction::Delete(profile) if is_active(&*vpn_backend, profile) => {
+                                        Err(format!("Cannot delete {} while it is up; bring it down first.", profile))
+                                    }
+                                    PromptAction::Delete(profile) => delete_profile(profile)
+                                        .map(|path| (String::new(), format!("Moved {} to {}", profile, path))),
+                                    PromptAction::Import => import_profile(&answer).map(|name| {
+                                        let mut msg = format!("Imported {} from {}", name, answer);
+                                        let config = Config::load(profile_path(&name)).unwrap_or_default();
+                                        let errors = validate::validate(&name, &config)
+                                            .iter()
+                                            .filter(|d| d.is_error())
+                                            .count();
+                                        if errors > 0 {
+                                            msg.push_str(&format!(" ({} error(s); press E to fix them)", errors));
+                                        }
+                                        (name, msg)
+                                    }),
+                                };
+                                match result {
+                                    Ok((select, msg)) => {
+                                        record_status(&mut status_log, &msg);
+                                        reload_profiles(&mut profiles, &mut selected_index, Some(&select));
+                                        screen = Screen::Manager;
+                                    }
+                                    Err(e) => prompt_state.fail(e),
+                                }
+                            }
+                        }
+                    }
+                }
+                Screen::Drift(drift_state) => {
+                    if let Event::Key(key) = ev {
+                        let (done, msg) = drift_state.handle_event(&*vpn_backend, key);
+                        if let Some(msg) = msg {
+                            poller.refresh();
+                            record_status(&mut status_log, &msg);
+                        }
+                        if done {
+                            screen = Screen::Manager;
+                        }
+                    }
+                }
+                Screen::History(history_state) => {
+                    if let Event::Key(key) = ev {
+                        let (done, msg) = history_state.handle_event(key);
+                        if let Some(msg) = msg {
+                            record_status(&mut status_log, &msg);
+                        }
+                        if done {
+                            screen = Screen::Manager;
+                        }
+                    }
+                }
+                Screen::Backups(backups_state) => {
+                    if let Event::Key(key) = ev {
+                        let (done, msg) = backups_state.handle_event(key);
+                        if let Some(msg) = msg {
+                            record_status(&mut status_log, &msg);
+                        }
+                        if done {
+                            screen = Screen::Manager;
+                        }
+                    }
+                }
+                Screen::Share(share_state) => {
+                    if let Event::Key(key) = ev {
+                        let (done, msg) = share_state.handle_event(key);
+                        if let Some(msg) = msg {
+                            record_status(&mut status_log, &msg);
+                        }
+                        if done {
+                            screen = Screen::Manager;
+                        }
+                    }
+                }
+                Screen::Wizard(wizard_state) => {
+                    if let Event::Key(key) = ev {
+                        if wizard_state.handle_event(key) {
+                            if let Some(created) = wizard_state.created.clone() {
+                                let msg = format!(
+                                    "Created profile {} (public key: {})",
+                                    created,
+                                    wizard_state.public_key().unwrap_or("unknown")
+                                );
+                                record_status(&mut status_log, &msg);
+                                reload_profiles(&mut profiles, &mut selected_index, Some(&created));
+                            }
+                            screen = Screen::Manager;
+                        }
+                    }
+                }
             }
         }
     }
 
     disable_raw_mode()?;
     execute!(terminal.backend_mut(), LeaveAlternateScreen, cursor::Show)?;
+    if exit_after_edit {
+        if let Some(msg) = status_log.last() {
+            println!("{}", msg);
+        }
+    }
     Ok(())
 }
@@ -0,0 +1,112 @@
+//! Vim word motions over the editor buffer.
+//!
+//! Columns are in bytes, as in the editor. As in Vim, a word is a run of
+//! letters, digits and underscores or a run of other non-blank characters,
+//! and an empty line counts as a word.
+
+/// A `(row, column)` position in the buffer.
+pub type Position = (usize, usize);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Class {
+    Blank,
+    Punctuation,
+    Word,
+}
+
+fn class(byte: u8) -> Class {
+    match byte {
+        b' ' | b'\t' => Class::Blank,
+        b'_' => Class::Word,
+        // Bytes of non-ASCII characters are treated as letters.
+        b if b.is_ascii_alphanumeric() || !b.is_ascii() => Class::Word,
+        _ => Class::Punctuation,
+    }
+}
+
+/// `w`: the start of the next word. Stops at the end of the last line.
+pub fn next_word_start(lines: &[String], (mut row, mut col): Position) -> Position {
+    let line = lines[row].as_bytes();
+    if col < line.len() && class(line[col]) != Class::Blank {
+        let start = class(line[col]);
+        while col < line.len() && class(line[col]) == start {
+            col += 1;
+        }
+    }
+    loop {
+        let line = lines[row].as_bytes();
+        while col < line.len() && class(line[col]) == Class::Blank {
+            col += 1;
+        }
+        if col < line.len() || row + 1 == lines.len() {
+            return (row, col.min(line.len()));
+        }
+        row += 1;
+        col = 0;
+        if lines[row].is_empty() {
+            return (row, 0);
+        }
+    }
+}
+
+/// `b`: the start of the word before the cursor, or of the word it is in.
+pub fn prev_word_start(lines: &[String], (mut row, col): Position) -> Position {
+    let mut col = col.min(lines[row].len());
+    loop {
+        if col == 0 {
+            if row == 0 {
+                return (0, 0);
+            }
+            row -= 1;
+            col = lines[row].len();
+            if col == 0 {
+                return (row, 0);
+            }
+        } else if class(lines[row].as_bytes()[col - 1]) == Class::Blank {
+            col -= 1;
+        } else {
+            break;
+        }
+    }
+    let line = lines[row].as_bytes();
+    let start = class(line[col - 1]);
+    while col > 0 && class(line[col - 1]) == start {
+        col -= 1;
+    }
+    (row, col)
+}
+
+/// `e`: the last character of the word after the cursor, or of the word it is in.
+pub fn word_end(lines: &[String], (mut row, col): Position) -> Position {
+    let mut col = col + 1;
+    loop {
+        let line = lines[row].as_bytes();
+        while col < line.len() && class(line[col]) == Class::Blank {
+            col += 1;
+        }
+        if col < line.len() {
+            break;
+        }
+        if row + 1 == lines.len() {
+            return (row, line.len().saturating_sub(1));
+        }
+        row += 1;
+        col = 0;
+    }
+    let line = lines[row].as_bytes();
+    let start = class(line[col]);
+    while col + 1 < line.len() && class(line[col + 1]) == start {
+        col += 1;
+    }
+    (row, col)
+}
+
+/// Column of the first non-blank character of a line, as `^` finds it.
+pub fn first_non_blank(line: &str) -> usize {
+    line.bytes().position(|b| class(b) != Class::Blank).unwrap_or(0)
+}
+
+/// Whether the character at `col` is blank, or past the end of the line.
+pub fn is_blank_at(line: &str, col: usize) -> bool {
+    line.as_bytes().get(col).is_none_or(|b| class(*b) == Class::Blank)
+}
@@ -0,0 +1,532 @@
+//! A minimal generic-netlink client for the kernel WireGuard API.
+//!
+//! Only the parts needed to read device state are implemented; see
+//! `include/uapi/linux/wireguard.h` in the kernel tree for the protocol.
+
+use std::fs;
+use std::io;
+use std::mem;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::time::{Duration, UNIX_EPOCH};
+
+use wgctrl::types::Key;
+
+use crate::status::{InterfaceStatus, PeerStatus};
+
+const NLMSG_HDRLEN: usize = 16;
+const GENL_HDRLEN: usize = 4;
+const NLA_HDRLEN: usize = 4;
+
+const NLM_F_REQUEST: u16 = 0x1;
+const NLM_F_ACK: u16 = 0x4;
+const NLM_F_DUMP: u16 = 0x300;
+const NLMSG_ERROR: u16 = 2;
+const NLMSG_DONE: u16 = 3;
+const NLA_F_NESTED: u16 = 1 << 15;
+const NLA_TYPE_MASK: u16 = !(NLA_F_NESTED | (1 << 14));
+
+const GENL_ID_CTRL: u16 = 0x10;
+const CTRL_CMD_GETFAMILY: u8 = 3;
+const CTRL_ATTR_FAMILY_ID: u16 = 1;
+const CTRL_ATTR_FAMILY_NAME: u16 = 2;
+
+const WG_GENL_NAME: &str = "wireguard";
+const WG_GENL_VERSION: u8 = 1;
+const WG_CMD_GET_DEVICE: u8 = 0;
+
+const WGDEVICE_A_IFNAME: u16 = 2;
+const WGDEVICE_A_PRIVATE_KEY: u16 = 3;
+const WGDEVICE_A_PUBLIC_KEY: u16 = 4;
+const WGDEVICE_A_LISTEN_PORT: u16 = 6;
+const WGDEVICE_A_FWMARK: u16 = 7;
+const WGDEVICE_A_PEERS: u16 = 8;
+
+const WGPEER_A_PUBLIC_KEY: u16 = 1;
+const WGPEER_A_PRESHARED_KEY: u16 = 2;
+const WGPEER_A_ENDPOINT: u16 = 4;
+const WGPEER_A_PERSISTENT_KEEPALIVE_INTERVAL: u16 = 5;
+const WGPEER_A_LAST_HANDSHAKE_TIME: u16 = 6;
+const WGPEER_A_RX_BYTES: u16 = 7;
+const WGPEER_A_TX_BYTES: u16 = 8;
+const WGPEER_A_ALLOWEDIPS: u16 = 9;
+
+const WGALLOWEDIP_A_FAMILY: u16 = 1;
+const WGALLOWEDIP_A_IPADDR: u16 = 2;
+const WGALLOWEDIP_A_CIDR_MASK: u16 = 3;
+
+/// Names of the kernel WireGuard interfaces, as reported by sysfs.
+pub fn device_names() -> io::Result<Vec<String>> {
+    let mut names: Vec<String> = fs::read_dir("/sys/class/net")?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            fs::read_to_string(entry.path().join("uevent"))
+                .is_ok_and(|uevent| uevent.lines().any(|l| l == "DEVTYPE=wireguard"))
+        })
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect();
+    names.sort();
+    Ok(names)
+}
+
+/// Read the state of every kernel WireGuard interface.
+pub fn list_devices() -> io::Result<Vec<InterfaceStatus>> {
+    let mut socket = Socket::open()?;
+    let family = socket.resolve_family(WG_GENL_NAME)?;
+    device_names()?
+        .iter()
+        .map(|name| socket.get_device(family, name))
+        .collect()
+}
+
+/// Read the state of a single kernel WireGuard interface.
+pub fn get_device(name: &str) -> io::Result<InterfaceStatus> {
+    let mut socket = Socket::open()?;
+    let family = socket.resolve_family(WG_GENL_NAME)?;
+    socket.get_device(family, name)
+}
+
+/// Append a netlink attribute, padded to 4 bytes.
+fn put_attr(buf: &mut Vec<u8>, kind: u16, data: &[u8]) {
+    let len = NLA_HDRLEN + data.len();
+    buf.extend_from_slice(&(len as u16).to_ne_bytes());
+    buf.extend_from_slice(&kind.to_ne_bytes());
+    buf.extend_from_slice(data);
+    buf.resize(buf.len() + (align(len) - len), 0);
+}
+
+fn put_str_attr(buf: &mut Vec<u8>, kind: u16, value: &str) {
+    let mut data = value.as_bytes().to_vec();
+    data.push(0);
+    put_attr(buf, kind, &data);
+}
+
+fn align(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// Split a buffer of netlink attributes into `(type, payload)` pairs.
+/// Attributes cut short, or trailing bytes too few for a header, are an error.
+fn parse_attrs(mut data: &[u8]) -> io::Result<Vec<(u16, &[u8])>> {
+    let mut attrs = Vec::new();
+    while !data.is_empty() {
+        if data.len() < NLA_HDRLEN {
+            return Err(invalid_data("truncated netlink attribute header"));
+        }
+        let len = u16::from_ne_bytes([data[0], data[1]]) as usize;
+        let kind = u16::from_ne_bytes([data[2], data[3]]) & NLA_TYPE_MASK;
+        if len < NLA_HDRLEN || len > data.len() {
+            return Err(invalid_data("truncated netlink attribute"));
+        }
+        attrs.push((kind, &data[NLA_HDRLEN..len]));
+        data = &data[align(len).min(data.len())..];
+    }
+    Ok(attrs)
+}
+
+fn read_u16(data: &[u8]) -> u16 {
+    data.get(..2).map(|b| u16::from_ne_bytes([b[0], b[1]])).unwrap_or(0)
+}
+
+fn read_u32(data: &[u8]) -> u32 {
+    data.get(..4)
+        .map(|b| u32::from_ne_bytes(b.try_into().unwrap_or_default()))
+        .unwrap_or(0)
+}
+
+fn read_u64(data: &[u8]) -> u64 {
+    data.get(..8)
+        .map(|b| u64::from_ne_bytes(b.try_into().unwrap_or_default()))
+        .unwrap_or(0)
+}
+
+/// Encode a key as base64, treating an all-zero key as absent.
+fn read_key(data: &[u8]) -> Option<String> {
+    if data.iter().all(|b| *b == 0) {
+        return None;
+    }
+    Key::try_from(data).ok().map(String::from)
+}
+
+/// Decode a `struct sockaddr_in` or `struct sockaddr_in6`.
+fn read_sockaddr(data: &[u8]) -> Option<SocketAddr> {
+    let family = i32::from(read_u16(data));
+    let port = u16::from_be_bytes([*data.get(2)?, *data.get(3)?]);
+    match family {
+        libc::AF_INET => {
+            let octets: [u8; 4] = data.get(4..8)?.try_into().ok()?;
+            Some(SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::from(octets), port)))
+        }
+        libc::AF_INET6 => {
+            let octets: [u8; 16] = data.get(8..24)?.try_into().ok()?;
+            let scope_id = data.get(24..28).map(read_u32).unwrap_or(0);
+            Some(SocketAddr::V6(SocketAddrV6::new(Ipv6Addr::from(octets), port, 0, scope_id)))
+        }
+        _ => None,
+    }
+}
+
+/// An allowed IP as `address/prefix`, or None for an unknown address family.
+fn read_allowed_ip(data: &[u8]) -> io::Result<Option<String>> {
+    let mut family = 0;
+    let mut addr = None;
+    let mut cidr = 0;
+    for (kind, value) in parse_attrs(data)? {
+        match kind {
+            WGALLOWEDIP_A_FAMILY => family = i32::from(read_u16(value)),
+            WGALLOWEDIP_A_IPADDR => addr = Some(value),
+            WGALLOWEDIP_A_CIDR_MASK => cidr = value.first().copied().unwrap_or(0),
+            _ => {}
+        }
+    }
+    let addr = addr.ok_or_else(|| invalid_data("allowed IP without an address"))?;
+    let length = || invalid_data("allowed IP address of the wrong length");
+    let ip = match family {
+        libc::AF_INET => Ipv4Addr::from(<[u8; 4]>::try_from(addr).map_err(|_| length())?).to_string(),
+        libc::AF_INET6 => Ipv6Addr::from(<[u8; 16]>::try_from(addr).map_err(|_| length())?).to_string(),
+        _ => return Ok(None),
+    };
+    Ok(Some(format!("{}/{}", ip, cidr)))
+}
+
+fn read_peer(data: &[u8]) -> io::Result<PeerStatus> {
+    let mut peer = PeerStatus::default();
+    for (kind, value) in parse_attrs(data)? {
+        match kind {
+            WGPEER_A_PUBLIC_KEY => peer.public_key = read_key(value).unwrap_or_default(),
+            WGPEER_A_PRESHARED_KEY => peer.preshared_key = read_key(value),
+            WGPEER_A_ENDPOINT => peer.endpoint = read_sockaddr(value),
+            WGPEER_A_PERSISTENT_KEEPALIVE_INTERVAL => {
+                peer.persistent_keepalive = Some(read_u16(value)).filter(|k| *k > 0)
+            }
+            WGPEER_A_LAST_HANDSHAKE_TIME => {
+                // struct __kernel_timespec { s64 tv_sec; s64 tv_nsec; }
+                let secs = read_u64(value);
+                let nanos = value.get(8..).map(read_u64).unwrap_or(0);
+                if secs > 0 || nanos > 0 {
+                    peer.last_handshake =
+                        Some(UNIX_EPOCH + Duration::from_secs(secs) + Duration::from_nanos(nanos));
+                }
+            }
+            WGPEER_A_RX_BYTES => peer.rx_bytes = read_u64(value),
+            WGPEER_A_TX_BYTES => peer.tx_bytes = read_u64(value),
+            WGPEER_A_ALLOWEDIPS => {
+                for (_, ip) in parse_attrs(value)? {
+                    peer.allowed_ips.extend(read_allowed_ip(ip)?);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(peer)
+}
+
+/// Read a device from the replies to `WG_CMD_GET_DEVICE`.
+fn parse_device(name: &str, replies: &[Vec<u8>]) -> io::Result<InterfaceStatus> {
+    let mut device = InterfaceStatus { name: name.to_string(), ..Default::default() };
+    // Large devices are split across several messages; a peer whose
+    // allowed IPs did not fit is continued at the start of the next one.
+    for reply in replies {
+        for (kind, value) in parse_attrs(reply)? {
+            match kind {
+                WGDEVICE_A_PRIVATE_KEY => device.private_key = read_key(value),
+                WGDEVICE_A_PUBLIC_KEY => device.public_key = read_key(value),
+                WGDEVICE_A_LISTEN_PORT => device.listen_port = read_u16(value),
+                WGDEVICE_A_FWMARK => device.fwmark = read_u32(value),
+                WGDEVICE_A_PEERS => {
+                    for (_, peer) in parse_attrs(value)? {
+                        let peer = read_peer(peer)?;
+                        match device.peers.last_mut() {
+                            Some(last) if last.public_key == peer.public_key => {
+                                last.allowed_ips.extend(peer.allowed_ips)
+                            }
+                            _ => device.peers.push(peer),
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(device)
+}
+
+/// A generic netlink socket.
+struct Socket {
+    fd: OwnedFd,
+    seq: u32,
+}
+
+impl Socket {
+    fn open() -> io::Result<Self> {
+        // SAFETY: plain socket(2)/bind(2) calls; the returned descriptor is
+        // owned by the OwnedFd from here on.
+        unsafe {
+            let fd = libc::socket(
+                libc::AF_NETLINK,
+                libc::SOCK_RAW | libc::SOCK_CLOEXEC,
+                libc::NETLINK_GENERIC,
+            );
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let fd = OwnedFd::from_raw_fd(fd);
+            let mut addr: libc::sockaddr_nl = mem::zeroed();
+            addr.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+            let ret = libc::bind(
+                fd.as_raw_fd(),
+                &addr as *const libc::sockaddr_nl as *const libc::sockaddr,
+                mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+            );
+            if ret < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(Self { fd, seq: 0 })
+        }
+    }
+
+    /// Send a generic netlink request and collect the payloads of every reply,
+    /// with the netlink and genl headers stripped.
+    fn request(&mut self, family: u16, flags: u16, cmd: u8, attrs: &[u8]) -> io::Result<Vec<Vec<u8>>> {
+        self.seq = self.seq.wrapping_add(1);
+        let len = NLMSG_HDRLEN + GENL_HDRLEN + attrs.len();
+        let mut msg = Vec::with_capacity(len);
+        msg.extend_from_slice(&(len as u32).to_ne_bytes());
+        msg.extend_from_slice(&family.to_ne_bytes());
+        msg.extend_from_slice(&(NLM_F_REQUEST | flags).to_ne_bytes());
+        msg.extend_from_slice(&self.seq.to_ne_bytes());
+        msg.extend_from_slice(&0u32.to_ne_bytes());
+        msg.extend_from_slice(&[cmd, WG_GENL_VERSION, 0, 0]);
+        msg.extend_from_slice(attrs);
+
+        // SAFETY: the buffer is valid for `msg.len()` bytes.
+        let sent = unsafe { libc::send(self.fd.as_raw_fd(), msg.as_ptr().cast(), msg.len(), 0) };
+        if sent < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let dump = flags & NLM_F_DUMP == NLM_F_DUMP;
+        let mut replies = Vec::new();
+        let mut buf = vec![0u8; 64 * 1024];
+        loop {
+            // SAFETY: the buffer is valid for `buf.len()` bytes.
+            let received =
+                unsafe { libc::recv(self.fd.as_raw_fd(), buf.as_mut_ptr().cast(), buf.len(), 0) };
+            if received < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let mut data = &buf[..received as usize];
+            while data.len() >= NLMSG_HDRLEN {
+                let msg_len = read_u32(data) as usize;
+                let msg_type = read_u16(&data[4..]);
+                let msg_seq = read_u32(&data[8..]);
+                if msg_len < NLMSG_HDRLEN || msg_len > data.len() {
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, "truncated netlink message"));
+                }
+                let payload = &data[NLMSG_HDRLEN..msg_len];
+                data = &data[align(msg_len).min(data.len())..];
+                if msg_seq != self.seq {
+                    continue;
+                }
+                match msg_type {
+                    NLMSG_ERROR => {
+                        let errno = read_u32(payload) as i32;
+                        if errno != 0 {
+                            return Err(io::Error::from_raw_os_error(-errno));
+                        }
+                        return Ok(replies);
+                    }
+                    NLMSG_DONE => return Ok(replies),
+                    _ => {
+                        replies.push(payload.get(GENL_HDRLEN..).unwrap_or_default().to_vec());
+                        if !dump && flags & NLM_F_ACK == 0 {
+                            return Ok(replies);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Look up the numeric id of a generic netlink family.
+    fn resolve_family(&mut self, name: &str) -> io::Result<u16> {
+        let mut attrs = Vec::new();
+        put_str_attr(&mut attrs, CTRL_ATTR_FAMILY_NAME, name);
+        let replies = self.request(GENL_ID_CTRL, NLM_F_ACK, CTRL_CMD_GETFAMILY, &attrs)?;
+        let attrs = replies.iter().map(|reply| parse_attrs(reply)).collect::<io::Result<Vec<_>>>()?;
+        attrs
+            .into_iter()
+            .flatten()
+            .find(|(kind, _)| *kind == CTRL_ATTR_FAMILY_ID)
+            .map(|(_, value)| read_u16(value))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "wireguard netlink family not found"))
+    }
+
+    fn get_device(&mut self, family: u16, name: &str) -> io::Result<InterfaceStatus> {
+        let mut attrs = Vec::new();
+        put_str_attr(&mut attrs, WGDEVICE_A_IFNAME, name);
+        let replies = self.request(family, NLM_F_DUMP, WG_CMD_GET_DEVICE, &attrs)?;
+        parse_device(name, &replies)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY_1: &str = "AQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQE=";
+    const KEY_2: &str = "AgICAgICAgICAgICAgICAgICAgICAgICAgICAgICAgI=";
+
+    fn attr(kind: u16, data: &[u8]) -> Vec<u8> {
+        let mut buf = Vec::new();
+        put_attr(&mut buf, kind, data);
+        buf
+    }
+
+    fn nested(kind: u16, attrs: &[Vec<u8>]) -> Vec<u8> {
+        attr(kind | NLA_F_NESTED, &attrs.concat())
+    }
+
+    fn sockaddr_in(ip: [u8; 4], port: u16) -> Vec<u8> {
+        let mut data = (libc::AF_INET as u16).to_ne_bytes().to_vec();
+        data.extend(port.to_be_bytes());
+        data.extend(ip);
+        data.extend([0; 8]);
+        data
+    }
+
+    fn sockaddr_in6(ip: Ipv6Addr, port: u16, scope_id: u32) -> Vec<u8> {
+        let mut data = (libc::AF_INET6 as u16).to_ne_bytes().to_vec();
+        data.extend(port.to_be_bytes());
+        data.extend(0u32.to_ne_bytes());
+        data.extend(ip.octets());
+        data.extend(scope_id.to_ne_bytes());
+        data
+    }
+
+    fn allowed_ip(family: i32, addr: &[u8], cidr: u8) -> Vec<u8> {
+        nested(
+            0,
+            &[
+                attr(WGALLOWEDIP_A_FAMILY, &(family as u16).to_ne_bytes()),
+                attr(WGALLOWEDIP_A_IPADDR, addr),
+                attr(WGALLOWEDIP_A_CIDR_MASK, &[cidr]),
+            ],
+        )
+    }
+
+    fn timespec(secs: u64, nanos: u64) -> Vec<u8> {
+        [secs.to_ne_bytes(), nanos.to_ne_bytes()].concat()
+    }
+
+    fn peer(key: u8, attrs: &[Vec<u8>]) -> Vec<u8> {
+        let mut all = vec![attr(WGPEER_A_PUBLIC_KEY, &[key; 32])];
+        all.extend_from_slice(attrs);
+        nested(0, &all)
+    }
+
+    #[test]
+    fn reads_devices_and_peers() {
+        let v4 = peer(
+            1,
+            &[
+                attr(WGPEER_A_ENDPOINT, &sockaddr_in([1, 2, 3, 4], 51820)),
+                attr(WGPEER_A_LAST_HANDSHAKE_TIME, &timespec(1_700_000_000, 5)),
+                attr(WGPEER_A_RX_BYTES, &1234u64.to_ne_bytes()),
+                attr(WGPEER_A_TX_BYTES, &99u64.to_ne_bytes()),
+                attr(WGPEER_A_PERSISTENT_KEEPALIVE_INTERVAL, &25u16.to_ne_bytes()),
+                nested(WGPEER_A_ALLOWEDIPS, &[allowed_ip(libc::AF_INET, &[10, 0, 0, 2], 32)]),
+            ],
+        );
+        let v6 = peer(
+            2,
+            &[
+                attr(WGPEER_A_PRESHARED_KEY, &[0; 32]),
+                attr(WGPEER_A_ENDPOINT, &sockaddr_in6("fd00::1".parse().unwrap(), 443, 3)),
+                attr(WGPEER_A_LAST_HANDSHAKE_TIME, &timespec(0, 0)),
+                attr(WGPEER_A_PERSISTENT_KEEPALIVE_INTERVAL, &0u16.to_ne_bytes()),
+                nested(WGPEER_A_ALLOWEDIPS, &[allowed_ip(libc::AF_INET6, &[0xfd; 16], 128)]),
+            ],
+        );
+        let reply = [
+            attr(WGDEVICE_A_IFNAME, b"wg0\0"),
+            attr(WGDEVICE_A_PUBLIC_KEY, &[1; 32]),
+            attr(WGDEVICE_A_PRIVATE_KEY, &[0; 32]),
+            attr(WGDEVICE_A_LISTEN_PORT, &51820u16.to_ne_bytes()),
+            attr(WGDEVICE_A_FWMARK, &0x51u32.to_ne_bytes()),
+            nested(WGDEVICE_A_PEERS, &[v4, v6]),
+        ]
+        .concat();
+        // The second peer's allowed IPs continue in a second message.
+        let more = nested(
+            WGDEVICE_A_PEERS,
+            &[peer(2, &[nested(WGPEER_A_ALLOWEDIPS, &[allowed_ip(libc::AF_INET, &[10, 0, 1, 0], 24)])])],
+        );
+
+        let device = parse_device("wg0", &[reply, more]).unwrap();
+        assert_eq!(device.public_key.as_deref(), Some(KEY_1));
+        assert_eq!(device.private_key, None);
+        assert_eq!((device.listen_port, device.fwmark), (51820, 0x51));
+        assert_eq!(device.peers.len(), 2);
+
+        let v4 = &device.peers[0];
+        assert_eq!(v4.public_key, KEY_1);
+        assert_eq!(v4.endpoint, Some("1.2.3.4:51820".parse().unwrap()));
+        assert_eq!(v4.last_handshake, Some(UNIX_EPOCH + Duration::new(1_700_000_000, 5)));
+        assert_eq!((v4.rx_bytes, v4.tx_bytes, v4.persistent_keepalive), (1234, 99, Some(25)));
+        assert_eq!(v4.allowed_ips, ["10.0.0.2/32"]);
+
+        let v6 = &device.peers[1];
+        assert_eq!(v6.public_key, KEY_2);
+        assert_eq!(v6.preshared_key, None);
+        assert_eq!(v6.endpoint, Some("[fd00::1%3]:443".parse().unwrap()));
+        assert_eq!(v6.last_handshake, None);
+        assert_eq!(v6.persistent_keepalive, None);
+        assert_eq!(v6.allowed_ips, ["fdfd:fdfd:fdfd:fdfd:fdfd:fdfd:fdfd:fdfd/128", "10.0.1.0/24"]);
+    }
+
+    #[test]
+    fn malformed_attributes_are_errors() {
+        let port = attr(WGDEVICE_A_LISTEN_PORT, &51820u16.to_ne_bytes());
+        let peers = nested(WGDEVICE_A_PEERS, &[peer(1, &[])]);
+        let mut longer = port.clone();
+        longer[0] = 12;
+        let mut short = port.clone();
+        short[0] = 3;
+        // An unpadded attribute followed by another one.
+        let mut misaligned = attr(WGDEVICE_A_IFNAME, b"wg0")[..7].to_vec();
+        misaligned.extend(&port);
+
+        for (what, reply) in [
+            ("length past the end", longer),
+            ("length shorter than the header", short),
+            ("trailing bytes", [port.clone(), vec![0, 0]].concat()),
+            ("misaligned", misaligned),
+            ("cut inside a peer", peers[..peers.len() - 8].to_vec()),
+            ("a peer cut short", nested(WGDEVICE_A_PEERS, &[peers[8..peers.len() - 8].to_vec()])),
+        ] {
+            let error = parse_device("wg0", &[reply]).expect_err(what);
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData, "{}", what);
+        }
+
+        let bad_address = peer(1, &[nested(WGPEER_A_ALLOWEDIPS, &[allowed_ip(libc::AF_INET, &[10, 0], 8)])]);
+        assert!(parse_device("wg0", &[nested(WGDEVICE_A_PEERS, &[bad_address])]).is_err());
+        assert_eq!(parse_device("wg0", &[]).unwrap(), InterfaceStatus { name: "wg0".into(), ..Default::default() });
+    }
+
+    #[test]
+    fn short_socket_addresses_are_ignored() {
+        let v4 = sockaddr_in([1, 2, 3, 4], 51820);
+        let v6 = sockaddr_in6(Ipv6Addr::LOCALHOST, 443, 0);
+        assert_eq!(read_sockaddr(&v4), Some("1.2.3.4:51820".parse().unwrap()));
+        assert_eq!(read_sockaddr(&v6), Some("[::1]:443".parse().unwrap()));
+        for data in [&v4[..6], &v6[..20], &v4[..1], &[]] {
+            assert_eq!(read_sockaddr(data), None);
+        }
+        assert_eq!(read_sockaddr(&[0; 16]), None);
+    }
+}
@@ -0,0 +1,248 @@
+//! Background polling of interface state.
+//!
+//! A [`Poller`] owns a worker thread that queries the backend on a fixed
+//! interval and publishes [`Snapshot`]s over a channel, so a UI can redraw
+//! from the latest snapshot without touching the system on every frame.
+
+use std::collections::HashMap;
+use std::fs;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::backend::VpnBackend;
+use crate::config::Config;
+use crate::profiles::{self, profile_path};
+use crate::status::InterfaceStatus;
+use crate::sync;
+
+/// Environment variable with the poll interval in seconds, e.g. `0.5` or `5`.
+pub const POLL_INTERVAL_ENV: &str = "RUSTGUARD_POLL_INTERVAL";
+
+/// Poll interval used when `RUSTGUARD_POLL_INTERVAL` is unset or invalid.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Shortest interval accepted, to keep a typo from spinning the CPU.
+const MIN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// State of the system at one point in time.
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    pub taken_at: Instant,
+    /// Names of the active interfaces.
+    pub active: Result<Vec<String>, String>,
+    /// Active interfaces whose state differs from their profile.
+    pub drifted: Vec<String>,
+    /// Details of the watched interface, if one is being watched.
+    pub watched: Option<(String, Result<Option<InterfaceStatus>, String>)>,
+}
+
+enum Request {
+    Refresh,
+    Watch(Option<String>),
+    Stop,
+}
+
+/// Handle to the polling thread. Dropping it stops the thread.
+pub struct Poller {
+    requests: Sender<Request>,
+    snapshots: Receiver<Snapshot>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl Poller {
+    /// Start polling `backend` every `interval`. The first snapshot is taken immediately.
+    pub fn spawn(backend: Arc<dyn VpnBackend>, interval: Duration) -> Self {
+        let (requests, request_rx) = mpsc::channel();
+        let (snapshot_tx, snapshots) = mpsc::channel();
+        // Profiles are read from the same place as on the spawning thread.
+        let root = profiles::root();
+        let worker = thread::Builder::new()
+            .name("rustguard-poller".into())
+            .spawn(move || {
+                profiles::set_root(root);
+                poll_loop(&*backend, interval, request_rx, snapshot_tx)
+            })
+            .expect("Failed to spawn poller thread");
+        Self { requests, snapshots, worker: Some(worker) }
+    }
+
+    /// Take a snapshot now instead of waiting for the next interval, e.g. after a change.
+    pub fn refresh(&self) {
+        self.requests.send(Request::Refresh).ok();
+    }
+
+    /// Also query the details of `interface` on every poll, or stop doing so with None.
+    pub fn watch(&self, interface: Option<String>) {
+        self.requests.send(Request::Watch(interface)).ok();
+    }
+
+    /// The newest snapshot published since the last call, if any.
+    pub fn latest(&self) -> Option<Snapshot> {
+        self.snapshots.try_iter().last()
+    }
+}
+
+impl Drop for Poller {
+    fn drop(&mut self) {
+        self.requests.send(Request::Stop).ok();
+        if let Some(worker) = self.worker.take() {
+            worker.join().ok();
+        }
+    }
+}
+
+/// Read the poll interval from `RUSTGUARD_POLL_INTERVAL`.
+pub fn interval_from_env() -> Duration {
+    std::env::var(POLL_INTERVAL_ENV)
+        .ok()
+        .and_then(|value| value.trim().parse::<f64>().ok())
+        .filter(|secs| secs.is_finite() && *secs > 0.0)
+        .map(|secs| Duration::from_secs_f64(secs).max(MIN_POLL_INTERVAL))
+        .unwrap_or(DEFAULT_POLL_INTERVAL)
+}
+
+/// Parsed profiles, re-read only when their file changes.
+#[derive(Default)]
+struct ProfileCache {
+    /// Modification time and size of each file when it was parsed, and what it held.
+    profiles: HashMap<String, (SystemTime, u64, Config)>,
+}
+
+impl ProfileCache {
+    /// The profile of an interface, or None if it has no readable profile.
+    fn get(&mut self, profile: &str) -> Option<&Config> {
+        let Ok(metadata) = fs::metadata(profile_path(profile)) else {
+            self.profiles.remove(profile);
+            return None;
+        };
+        let stamp = (metadata.modified().ok()?, metadata.len());
+        let fresh = self.profiles.get(profile).is_some_and(|(modified, len, _)| (*modified, *len) == stamp);
+        if !fresh {
+            let config = Config::load(profile_path(profile)).ok()?;
+            self.profiles.insert(profile.to_string(), (stamp.0, stamp.1, config));
+        }
+        self.profiles.get(profile).map(|(_, _, config)| config)
+    }
+
+    /// Whether an interface differs from its profile. Interfaces without a
+    /// readable profile never count as drifted.
+    fn has_drifted(&mut self, running: &InterfaceStatus) -> bool {
+        self.get(&running.name).is_some_and(|config| !sync::drift(running, config).is_empty())
+    }
+}
+
+fn poll_loop(
+    backend: &dyn VpnBackend,
+    interval: Duration,
+    requests: Receiver<Request>,
+    snapshots: Sender<Snapshot>,
+) {
+    let mut watched: Option<String> = None;
+    let mut profiles = ProfileCache::default();
+    loop {
+        // One query per tick serves the list, the drift check and the watched interface.
+        let interfaces = backend.all_interface_details();
+        let (active, drifted) = match &interfaces {
+            Ok(interfaces) => (
+                Ok(interfaces.iter().map(|status| status.name.clone()).collect()),
+                interfaces
+                    .iter()
+                    .filter(|status| profiles.has_drifted(status))
+                    .map(|status| status.name.clone())
+                    .collect(),
+            ),
+            Err(e) => (Err(e.clone()), Vec::new()),
+        };
+        let snapshot = Snapshot {
+            taken_at: Instant::now(),
+            active,
+            drifted,
+            watched: watched.as_ref().map(|interface| {
+                let details = match &interfaces {
+                    Ok(interfaces) => Ok(interfaces.iter().find(|status| status.name == *interface).cloned()),
+                    Err(e) => Err(e.clone()),
+                };
+                (interface.clone(), details)
+            }),
+        };
+        if snapshots.send(snapshot).is_err() {
+            return;
+        }
+
+        match requests.recv_timeout(interval) {
+            Ok(Request::Refresh) | Err(RecvTimeoutError::Timeout) => {}
+            Ok(Request::Watch(interface)) => watched = interface,
+            Ok(Request::Stop) | Err(RecvTimeoutError::Disconnected) => return,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::MockBackend;
+    use crate::testutil::{server_config, TestDirs};
+
+    /// The next snapshot, waiting for the poller to take it.
+    fn next(poller: &Poller) -> Snapshot {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            if let Some(snapshot) = poller.latest() {
+                return snapshot;
+            }
+            assert!(Instant::now() < deadline, "no snapshot was taken");
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn snapshots_report_interfaces_that_differ_from_their_profile() {
+        let dirs = TestDirs::new();
+        let profile = dirs.profile("rgt-poll", &server_config());
+        let mock = Arc::new(MockBackend::new());
+        mock.add_profile(&profile.name, Config::parse(&server_config()));
+        mock.bring_up(&profile.name).expect("up succeeds");
+        let poller = Poller::spawn(mock.clone(), Duration::from_secs(60));
+
+        let snapshot = next(&poller);
+        assert_eq!(snapshot.active, Ok(vec![profile.name.clone()]));
+        assert!(snapshot.drifted.is_empty());
+
+        // Changed on the device, e.g. with `wg set`.
+        let mut changed = Config::parse(&server_config());
+        changed.interface_mut().unwrap().set("ListenPort", "51999");
+        mock.apply_config(&profile.name, &changed).expect("apply succeeds");
+        poller.watch(Some(profile.name.clone()));
+        let snapshot = next(&poller);
+        assert_eq!(snapshot.drifted, vec![profile.name.clone()]);
+        let (watched, details) = snapshot.watched.expect("an interface is watched");
+        assert_eq!(watched, profile.name);
+        assert_eq!(details.unwrap().map(|status| status.listen_port), Some(51999));
+
+        // The edited file is read again, and matches the device.
+        fs::write(profile.path(), changed.to_string()).unwrap();
+        poller.refresh();
+        assert!(next(&poller).drifted.is_empty());
+    }
+
+    #[test]
+    fn profiles_are_parsed_again_only_when_their_file_changes() {
+        let dirs = TestDirs::new();
+        let profile = dirs.profile("rgt-cache", "[Interface]\nListenPort = 1\n");
+        let mut cache = ProfileCache::default();
+        let port = |cache: &mut ProfileCache| cache.get(&profile.name).map(|c| c.interface().unwrap().as_interface());
+        assert_eq!(port(&mut cache).unwrap().listen_port, Some(1));
+
+        // Unchanged files are served from the cache.
+        cache.profiles.get_mut(&profile.name).unwrap().2 = Config::parse("[Interface]\nListenPort = 2\n");
+        assert_eq!(port(&mut cache).unwrap().listen_port, Some(2));
+
+        fs::write(profile.path(), "[Interface]\nListenPort = 30\n").unwrap();
+        assert_eq!(port(&mut cache).unwrap().listen_port, Some(30));
+        fs::remove_file(profile.path()).unwrap();
+        assert!(port(&mut cache).is_none());
+        assert!(cache.profiles.is_empty());
+    }
+}
@@ -0,0 +1,400 @@
+//! Profile discovery and management: one wg-quick config file per profile.
+
+use std::cell::RefCell;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::history;
+use crate::validate::check_interface_name;
+
+thread_local! {
+    static ROOT: RefCell<Option<PathBuf>> = const { RefCell::new(None) };
+}
+
+/// Keep profiles, state and the log under `root` instead of the system
+/// directories, on the current thread only; None goes back to the system
+/// ones. This lets tests run without root and away from real profiles.
+pub fn set_root(root: Option<PathBuf>) {
+    ROOT.with(|current| *current.borrow_mut() = root);
+}
+
+/// The directory set with [`set_root`] on the current thread, if any.
+pub fn root() -> Option<PathBuf> {
+    ROOT.with(|current| current.borrow().clone())
+}
+
+/// `name` under the directory set with [`set_root`], or `system` if there is none.
+pub(crate) fn rooted(system: &str, name: &str) -> String {
+    match root() {
+        Some(root) => format!("{}/{}", root.display(), name),
+        None => system.to_string(),
+    }
+}
+
+/// Returns the configuration directory based on the OS.
+pub fn config_path() -> String {
+    let system = match std::env::consts::OS {
+        "windows" => r"C:\ProgramData\rustguard\wireguard\",
+        "macos" => "/usr/local/etc/wireguard/",
+        _ => "/etc/wireguard/",
+    };
+    rooted(system, "wireguard/")
+}
+
+/// List all VPN profiles (config files) in the configuration directory (without the ".conf" suffix),
+/// sorted by name.
+pub fn list_vpn_profiles() -> Vec<String> {
+    let path = config_path();
+    if let Ok(entries) = fs::read_dir(path) {
+        let mut profiles: Vec<String> = entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().is_file())
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .filter_map(|name| profile_name(&name).map(str::to_string))
+            .collect();
+        profiles.sort();
+        profiles
+    } else {
+        vec![]
+    }
+}
+
+/// The profile a file name in the configuration directory belongs to, if any.
+pub fn profile_name(file_name: &str) -> Option<&str> {
+    file_name.strip_suffix(".conf").filter(|name| !name.is_empty())
+}
+
+/// Directory deleted profiles are moved to.
+pub fn trash_path() -> String {
+    format!("{}.trash", config_path())
+}
+
+/// Directory previous versions of saved profiles are kept in.
+pub fn backup_path() -> String {
+    format!("{}.backups", config_path())
+}
+
+/// How many backups are kept per profile; older ones are removed on save.
+pub const MAX_BACKUPS: usize = 20;
+
+/// Path of the config file of a profile.
+pub fn profile_path(profile: &str) -> String {
+    format!("{}{}.conf", config_path(), profile)
+}
+
+/// Write the config file of a new profile, failing if the profile already exists.
+/// `message` describes the change in the history.
+///
+/// The file is created readable by its owner only, since it holds a private key.
+pub fn create_profile(profile: &str, contents: &str, message: &str) -> Result<String, String> {
+    check_interface_name(profile)?;
+    let path = profile_path(profile);
+    fs::create_dir_all(config_path())
+        .map_err(|e| format!("Failed to create {}: {}", config_path(), e))?;
+    let temp = write_temp(profile, contents)?;
+    // Linking fails if the target exists, unlike renaming.
+    let linked = fs::hard_link(&temp, &path);
+    fs::remove_file(&temp).ok();
+    linked.map_err(|e| match e.kind() {
+        std::io::ErrorKind::AlreadyExists => format!("Profile {} already exists", profile),
+        _ => format!("Failed to create {}: {}", path, e),
+    })?;
+    sync_dir(&config_path());
+    record_history(profile, contents, message)?;
+    Ok(path)
+}
+
+/// What [`save_profile`] did besides writing the file.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SaveOutcome {
+    /// Where the previous version was copied to, if the profile existed.
+    pub backup: Option<String>,
+    /// Problems that did not stop the save, such as a history that could not be updated.
+    pub warnings: Vec<String>,
+}
+
+impl SaveOutcome {
+    /// A status message followed by the warnings, if there are any.
+    pub fn report(&self, message: String) -> String {
+        self.warnings.iter().fold(message, |message, warning| format!("{}\n⚠️ {}", message, warning))
+    }
+}
+
+/// Replace the config file of a profile without ever leaving it half
+/// written: the new contents go to a temporary file that is synced and then
+/// renamed over the old one. The file is made readable by its owner (root
+/// when running as root) only.
+///
+/// The previous version is kept in the backup directory, and the new one is
+/// recorded in the history with `message`. Failing to update the history
+/// does not fail the save; it is reported in the outcome instead.
+pub fn save_profile(profile: &str, contents: &str, message: &str) -> Result<SaveOutcome, String> {
+    check_interface_name(profile)?;
+    let path = profile_path(profile);
+    let mut outcome = SaveOutcome::default();
+    match fs::read(&path) {
+        Ok(previous) => {
+            if history::versions(profile).is_empty() {
+                // Give the first recorded change something to be compared with.
+                let previous = String::from_utf8_lossy(&previous);
+                let recorded = record_history(profile, &previous, "Version found before the first recorded change");
+                outcome.warnings.extend(recorded.err());
+            }
+            outcome.backup = Some(backup_profile(profile, &previous)?);
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(format!("Failed to read {}: {}", path, e)),
+    }
+    let temp = write_temp(profile, contents)?;
+    if let Err(e) = fs::rename(&temp, &path) {
+        fs::remove_file(&temp).ok();
+        return Err(format!("Failed to replace {}: {}", path, e));
+    }
+    sync_dir(&config_path());
+    outcome.warnings.extend(record_history(profile, contents, message).err());
+    Ok(outcome)
+}
+
+fn record_history(profile: &str, contents: &str, message: &str) -> Result<(), String> {
+    history::record(profile, contents, message)
+        .map(|_| ())
+        .map_err(|e| format!("Saved {}, but could not record it in the history: {}", profile, e))
+}
+
+/// Write `contents` to a temporary file next to the profile, synced to disk,
+/// with mode 0600 and, when running as root, owned by root.
+fn write_temp(profile: &str, contents: &str) -> Result<PathBuf, String> {
+    let temp = Path::new(&config_path()).join(format!(".{}.conf.tmp", profile));
+    fs::remove_file(&temp).ok();
+    write_private(&temp, contents.as_bytes())?;
+    Ok(temp)
+}
+
+/// Create a new file readable by its owner only.
+pub(crate) fn write_private(path: &Path, contents: &[u8]) -> Result<(), String> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options
+        .open(path)
+        .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    // The mode only applies to new files and is subject to the umask; set it explicitly.
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(0o600))
+            .map_err(|e| format!("Failed to set the permissions of {}: {}", path.display(), e))?;
+        // SAFETY: geteuid has no preconditions and cannot fail.
+        if unsafe { libc::geteuid() } == 0 {
+            std::os::unix::fs::fchown(&file, Some(0), Some(0))
+                .map_err(|e| format!("Failed to change the owner of {}: {}", path.display(), e))?;
+        }
+    }
+    file.write_all(contents)
+        .and_then(|_| file.sync_all())
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Make a rename or link in `dir` durable. Failures are ignored: the file
+/// itself is already on disk, and some platforms cannot sync directories.
+fn sync_dir(dir: &str) {
+    if let Ok(dir) = fs::File::open(dir) {
+        dir.sync_all().ok();
+    }
+}
+
+/// Create a directory readable by its owner only, if it does not exist.
+pub(crate) fn create_private_dir(dir: &str) -> Result<(), String> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder.create(dir).map_err(|e| format!("Failed to create {}: {}", dir, e))
+}
+
+/// A saved previous version of a profile.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Backup {
+    pub path: String,
+    pub saved_at: SystemTime,
+}
+
+/// Copy the previous contents of a profile into the backup directory and
+/// prune old backups. Returns the path of the copy.
+fn backup_profile(profile: &str, previous: &[u8]) -> Result<String, String> {
+    let dir = backup_path();
+    create_private_dir(&dir)?;
+    let target = timestamped_path(&dir, profile);
+    write_private(&target, previous)?;
+    for old in list_backups(profile).iter().skip(MAX_BACKUPS) {
+        fs::remove_file(&old.path).ok();
+    }
+    Ok(target.display().to_string())
+}
+
+/// A path in `dir` for a copy of a profile taken now, named after the time in
+/// milliseconds, or a later one if another copy already has that name.
+fn timestamped_path(dir: &str, profile: &str) -> PathBuf {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis();
+    (now..)
+        .map(|millis| Path::new(dir).join(format!("{}.conf.{}", profile, millis)))
+        .find(|path| !path.exists())
+        .expect("some millisecond is free")
+}
+
+/// Backups of a profile, newest first.
+pub fn list_backups(profile: &str) -> Vec<Backup> {
+    let Ok(entries) = fs::read_dir(backup_path()) else {
+        return Vec::new();
+    };
+    let prefix = format!("{}.conf.", profile);
+    let mut backups: Vec<Backup> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            let millis: u64 = name.strip_prefix(&prefix)?.parse().ok()?;
+            Some(Backup {
+                path: entry.path().display().to_string(),
+                saved_at: UNIX_EPOCH + Duration::from_millis(millis),
+            })
+        })
+        .collect();
+    backups.sort_by_key(|backup| std::cmp::Reverse(backup.saved_at));
+    backups
+}
+
+/// Put a backup back in place of a profile. The current version is backed up
+/// first, so a restore can itself be undone.
+pub fn restore_backup(profile: &str, backup: &Backup) -> Result<SaveOutcome, String> {
+    let contents = fs::read_to_string(&backup.path)
+        .map_err(|e| format!("Failed to read {}: {}", backup.path, e))?;
+    save_profile(profile, &contents, &format!("Restored the backup {}", backup.path))
+}
+
+/// Copy a profile to a new name.
+pub fn clone_profile(profile: &str, new_name: &str) -> Result<String, String> {
+    let contents = fs::read_to_string(profile_path(profile))
+        .map_err(|e| format!("Failed to read {}: {}", profile_path(profile), e))?;
+    create_profile(new_name, &contents, &format!("Cloned from {}", profile))
+}
+
+/// Give a profile a new name. The caller must make sure it is not active,
+/// since wg-quick needs the old file to bring the interface down.
+pub fn rename_profile(profile: &str, new_name: &str) -> Result<String, String> {
+    check_interface_name(new_name)?;
+    let path = profile_path(new_name);
+    // Unlike rename(2), linking never replaces an existing profile.
+    fs::hard_link(profile_path(profile), &path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::AlreadyExists => format!("Profile {} already exists", new_name),
+        _ => format!("Failed to rename {} to {}: {}", profile, new_name, e),
+    })?;
+    if let Err(e) = fs::remove_file(profile_path(profile)) {
+        fs::remove_file(&path).ok();
+        return Err(format!("Failed to rename {} to {}: {}", profile, new_name, e));
+    }
+    sync_dir(&config_path());
+    history::rename_history(profile, new_name)?;
+    Ok(path)
+}
+
+/// Move a profile into the trash directory. Returns where it was moved to.
+pub fn delete_profile(profile: &str) -> Result<String, String> {
+    let trash = trash_path();
+    create_private_dir(&trash)?;
+    let target = timestamped_path(&trash, profile);
+    fs::rename(profile_path(profile), &target)
+        .map_err(|e| format!("Failed to move {} to the trash: {}", profile, e))?;
+    Ok(target.display().to_string())
+}
+
+/// Copy a config file from anywhere into the config directory, naming the
+/// profile after the file. Returns the new profile name.
+pub fn import_profile(path: &str) -> Result<String, String> {
+    let path = match (path.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => Path::new(&home).join(rest),
+        _ => Path::new(path).to_path_buf(),
+    };
+    let profile = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .ok_or_else(|| format!("Cannot name a profile after {}", path.display()))?
+        .to_string();
+    let contents = fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    create_profile(&profile, &contents, &format!("Imported from {}", path.display()))?;
+    Ok(profile)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::TestDirs;
+
+    #[test]
+    fn save_backs_up_and_records_the_previous_version() {
+        let dirs = TestDirs::new();
+        let profile = dirs.profile("rgt-save", "[Interface]\n# before\n");
+        let outcome = save_profile(&profile.name, "[Interface]\n# after\n", "test").unwrap();
+        assert_eq!(outcome.warnings, Vec::<String>::new());
+        let backup = outcome.backup.expect("the previous version was backed up");
+        assert_eq!(fs::read_to_string(backup).unwrap(), "[Interface]\n# before\n");
+        assert_eq!(fs::read_to_string(profile.path()).unwrap(), "[Interface]\n# after\n");
+        let messages: Vec<String> = history::versions(&profile.name).into_iter().map(|v| v.message).collect();
+        assert_eq!(messages.len(), 2);
+        assert!(messages.contains(&"test".to_string()));
+    }
+
+    #[test]
+    fn save_succeeds_with_a_warning_when_the_history_cannot_be_updated() {
+        let dirs = TestDirs::new();
+        let profile = dirs.profile("rgt-nohist", "[Interface]\n# before\n");
+        // A file where the history directory of the profile should be.
+        create_private_dir(&history::history_path()).unwrap();
+        fs::write(Path::new(&history::history_path()).join(&profile.name), "").unwrap();
+
+        let outcome = save_profile(&profile.name, "[Interface]\n# after\n", "test").unwrap();
+        assert_eq!(fs::read_to_string(profile.path()).unwrap(), "[Interface]\n# after\n");
+        assert!(outcome.backup.is_some());
+        assert_eq!(outcome.warnings.len(), 2, "{:?}", outcome.warnings);
+        let report = outcome.report("Saved".into());
+        let expected = "Saved\n⚠️ Saved rgt-nohist, but could not record it in the history";
+        assert!(report.starts_with(expected), "{}", report);
+    }
+
+    #[test]
+    fn deleting_again_keeps_every_trashed_copy() {
+        let dirs = TestDirs::new();
+        let mut trashed = Vec::new();
+        for version in ["# first", "# second", "# third"] {
+            let profile = dirs.profile("wg0", version);
+            trashed.push(delete_profile(&profile.name).unwrap());
+            assert!(!Path::new(&profile.path()).exists());
+        }
+        let contents: Vec<String> = trashed.iter().map(|path| fs::read_to_string(path).unwrap()).collect();
+        assert_eq!(contents, ["# first", "# second", "# third"]);
+        assert!(delete_profile("wg0").is_err());
+    }
+
+    #[test]
+    fn rename_moves_the_profile() {
+        let dirs = TestDirs::new();
+        let old = dirs.profile("rgt-ren-old", "[Interface]\n");
+        let new = dirs.missing("rgt-ren-new");
+        assert_eq!(rename_profile(&old.name, &new.name), Ok(new.path()));
+        assert!(!Path::new(&old.path()).exists());
+        assert_eq!(fs::read_to_string(new.path()).unwrap(), "[Interface]\n");
+    }
+
+    #[test]
+    fn rename_never_replaces_another_profile() {
+        let dirs = TestDirs::new();
+        let old = dirs.profile("rgt-ren-a", "[Interface]\n# a\n");
+        let other = dirs.profile("rgt-ren-b", "[Interface]\n# b\n");
+        assert_eq!(rename_profile(&old.name, &other.name), Err("Profile rgt-ren-b already exists".into()));
+        assert_eq!(fs::read_to_string(old.path()).unwrap(), "[Interface]\n# a\n");
+        assert_eq!(fs::read_to_string(other.path()).unwrap(), "[Interface]\n# b\n");
+        assert!(rename_profile(&old.name, "bad name").is_err());
+    }
+}
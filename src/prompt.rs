@@ -0,0 +1,127 @@
+//! A small popup asking for a line of text or a yes/no confirmation.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+/// What the manager does with the answer.
+/// Other screens asking questions use their own action types.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PromptAction {
+    Clone(String),
+    Rename(String),
+    Delete(String),
+    Import,
+}
+
+/// State of a prompt popup.
+#[derive(Clone)]
+pub struct PromptState<A = PromptAction> {
+    pub action: A,
+    title: String,
+    /// Question shown above the input.
+    question: String,
+    /// The text typed so far, or None for a yes/no confirmation.
+    input: Option<String>,
+    error: Option<String>,
+}
+
+impl<A> PromptState<A> {
+    /// Ask for a line of text, starting from `initial`.
+    pub fn input(action: A, title: &str, question: &str, initial: &str) -> Self {
+        Self {
+            action,
+            title: title.to_string(),
+            question: question.to_string(),
+            input: Some(initial.to_string()),
+            error: None,
+        }
+    }
+
+    /// Ask a yes/no question.
+    pub fn confirm(action: A, title: &str, question: &str) -> Self {
+        Self { action, title: title.to_string(), question: question.to_string(), input: None, error: None }
+    }
+
+    /// Show why the answer could not be used, keeping the prompt open.
+    pub fn fail(&mut self, error: String) {
+        self.error = Some(error);
+    }
+
+    /// Handle a key press.
+    ///
+    /// Returns Some(Some(answer)) when the prompt was answered ("y" for a
+    /// confirmation), Some(None) when it was cancelled and None otherwise.
+    pub fn handle_event(&mut self, key: KeyEvent) -> Option<Option<String>> {
+        self.error = None;
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match &mut self.input {
+            None => match key.code {
+                KeyCode::Char('y') | KeyCode::Char('Y') => Some(Some("y".into())),
+                KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc | KeyCode::Enter => Some(None),
+                _ => None,
+            },
+            Some(input) => match key.code {
+                KeyCode::Esc => Some(None),
+                KeyCode::Enter if input.trim().is_empty() => None,
+                KeyCode::Enter => Some(Some(input.trim().to_string())),
+                KeyCode::Backspace => {
+                    input.pop();
+                    None
+                }
+                KeyCode::Char('u') if ctrl => {
+                    input.clear();
+                    None
+                }
+                KeyCode::Char(c) if !ctrl => {
+                    input.push(c);
+                    None
+                }
+                _ => None,
+            },
+        }
+    }
+}
+
+/// Draw the prompt as a popup over `area`.
+pub fn draw<A>(f: &mut Frame, area: Rect, state: &PromptState<A>) {
+    let mut lines = vec![Line::from(state.question.clone()), Line::from("")];
+    match &state.input {
+        Some(input) => {
+            lines.push(Line::from(vec![
+                Span::styled("> ", Style::default().fg(Color::Yellow)),
+                Span::raw(input.clone()),
+                Span::styled(" ", Style::default().add_modifier(Modifier::REVERSED)),
+            ]));
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled(
+                "Enter: OK | Esc: Cancel | Ctrl+U: Clear",
+                Style::default().fg(Color::DarkGray),
+            )));
+        }
+        None => lines.push(Line::from(Span::styled("y: Yes | n: No", Style::default().fg(Color::DarkGray)))),
+    }
+    if let Some(error) = &state.error {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(error.clone(), Style::default().fg(Color::Red))));
+    }
+    // Sized to the content, with room for the question and error to wrap once.
+    let width = (area.width * 3 / 5).max(40).min(area.width);
+    let height = (lines.len() as u16 + 4).min(area.height);
+    let popup = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+    let paragraph = Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .block(Block::default().title(format!(" {} ", state.title)).borders(Borders::ALL));
+    f.render_widget(Clear, popup);
+    f.render_widget(paragraph, popup);
+}
@@ -0,0 +1,249 @@
+//! QR codes of profiles, as scanned by the WireGuard mobile apps.
+//!
+//! Codes can be drawn in the terminal with half-block characters or written
+//! to PNG and SVG files.
+
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use qrcode::render::svg;
+use qrcode::{Color, EcLevel, QrCode};
+
+/// Light modules around the code, as required by the QR specification.
+pub const QUIET_ZONE: usize = 4;
+
+/// Pixels per module in PNG files.
+const PNG_SCALE: usize = 8;
+
+/// Encode config text. The lowest error correction level keeps the code small
+/// enough to fit a terminal; it is shown on a screen, not printed.
+pub fn encode(text: &str) -> Result<QrCode, String> {
+    QrCode::with_error_correction_level(text.as_bytes(), EcLevel::L)
+        .map_err(|e| format!("Cannot encode the profile as a QR code: {}", e))
+}
+
+/// Width and height of the code in modules, including the quiet zone.
+pub fn size(code: &QrCode) -> usize {
+    code.width() + 2 * QUIET_ZONE
+}
+
+/// Whether the module at (x, y) is dark, with (0, 0) the corner of the quiet zone.
+fn is_dark(code: &QrCode, x: usize, y: usize) -> bool {
+    let width = code.width();
+    match (x.checked_sub(QUIET_ZONE), y.checked_sub(QUIET_ZONE)) {
+        (Some(x), Some(y)) if x < width && y < width => code[(x, y)] == Color::Dark,
+        _ => false,
+    }
+}
+
+/// The code as lines of half-block characters, two modules per character
+/// cell. Meant to be drawn dark on light.
+pub fn half_blocks(code: &QrCode) -> Vec<String> {
+    let size = size(code);
+    (0..size)
+        .step_by(2)
+        .map(|y| {
+            (0..size)
+                .map(|x| match (is_dark(code, x, y), y + 1 < size && is_dark(code, x, y + 1)) {
+                    (true, true) => '█',
+                    (true, false) => '▀',
+                    (false, true) => '▄',
+                    (false, false) => ' ',
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// The code as an SVG document.
+pub fn to_svg(code: &QrCode) -> String {
+    code.render::<svg::Color>().quiet_zone(true).min_dimensions(256, 256).build()
+}
+
+/// The code as a black and white PNG image.
+pub fn to_png(code: &QrCode) -> Vec<u8> {
+    let pixels = size(code) * PNG_SCALE;
+    let row_len = pixels.div_ceil(8);
+    // One filter byte (none) per row, then one bit per pixel, 1 being white.
+    let mut raw = Vec::with_capacity(pixels * (row_len + 1));
+    for y in 0..pixels {
+        raw.push(0);
+        let mut row = vec![0u8; row_len];
+        for x in 0..pixels {
+            if !is_dark(code, x / PNG_SCALE, y / PNG_SCALE) {
+                row[x / 8] |= 0x80 >> (x % 8);
+            }
+        }
+        raw.extend_from_slice(&row);
+    }
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(pixels as u32).to_be_bytes());
+    header.extend_from_slice(&(pixels as u32).to_be_bytes());
+    // Bit depth 1, greyscale, deflate, no filtering method, no interlacing.
+    header.extend_from_slice(&[1, 0, 0, 0, 0]);
+
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    png_chunk(&mut png, b"IHDR", &header);
+    png_chunk(&mut png, b"IDAT", &zlib_stored(&raw));
+    png_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn png_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// A zlib stream of uncompressed deflate blocks. The images are small, so
+/// compressing them is not worth an extra dependency.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    let mut blocks = data.chunks(u16::MAX as usize).peekable();
+    if blocks.peek().is_none() {
+        out.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        out.push(u8::from(blocks.peek().is_none()));
+        let len = block.len() as u16;
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in data {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in data {
+        a = (a + u32::from(*byte)) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+/// Write the code to `path` as PNG or SVG, chosen by the extension, failing
+/// if the file exists. The file is readable by its owner only, since the
+/// code usually holds a private key.
+pub fn save(code: &QrCode, path: &str) -> Result<(), String> {
+    let contents = match Path::new(path).extension().and_then(|e| e.to_str()) {
+        Some(ext) if ext.eq_ignore_ascii_case("png") => to_png(code),
+        Some(ext) if ext.eq_ignore_ascii_case("svg") => to_svg(code).into_bytes(),
+        _ => return Err(format!("{} must end in .png or .svg", path)),
+    };
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path).map_err(|e| format!("Failed to create {}: {}", path, e))?;
+    file.write_all(&contents)
+        .and_then(|_| file.sync_all())
+        .map_err(|e| format!("Failed to write {}: {}", path, e))
+}
+
+/// Config text without comments and blank lines, which the apps ignore, so
+/// the code stays as small as possible.
+pub fn compact(text: &str) -> String {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| format!("{}\n", line))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checksums_match_known_vectors() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(crc32(b"IEND"), 0xae42_6082);
+        assert_eq!(adler32(b""), 1);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+    }
+
+    /// The data of each chunk, checking its CRC.
+    fn chunks(png: &[u8]) -> Vec<([u8; 4], &[u8])> {
+        let mut chunks = Vec::new();
+        let mut rest = &png[8..];
+        while !rest.is_empty() {
+            let len = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+            let crc = u32::from_be_bytes(rest[8 + len..12 + len].try_into().unwrap());
+            assert_eq!(crc32(&rest[4..8 + len]), crc);
+            chunks.push((rest[4..8].try_into().unwrap(), &rest[8..8 + len]));
+            rest = &rest[12 + len..];
+        }
+        chunks
+    }
+
+    /// The data of a zlib stream of stored blocks, checking its Adler-32.
+    fn inflate_stored(stream: &[u8]) -> Vec<u8> {
+        assert_eq!(&stream[..2], &[0x78, 0x01]);
+        let mut data = Vec::new();
+        let mut rest = &stream[2..];
+        loop {
+            let last = rest[0] == 1;
+            let len = u16::from_le_bytes([rest[1], rest[2]]);
+            assert_eq!(u16::from_le_bytes([rest[3], rest[4]]), !len);
+            data.extend_from_slice(&rest[5..5 + len as usize]);
+            rest = &rest[5 + len as usize..];
+            if last {
+                break;
+            }
+        }
+        assert_eq!(rest, adler32(&data).to_be_bytes());
+        data
+    }
+
+    #[test]
+    fn png_round_trips() {
+        let code = encode("[Interface]\nPrivateKey = x\n").unwrap();
+        let png = to_png(&code);
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+
+        let chunks = chunks(&png);
+        let kinds: Vec<&[u8; 4]> = chunks.iter().map(|(kind, _)| kind).collect();
+        assert_eq!(kinds, [b"IHDR", b"IDAT", b"IEND"]);
+        let pixels = size(&code) * PNG_SCALE;
+        let header = chunks[0].1;
+        assert_eq!(&header[..4], &(pixels as u32).to_be_bytes());
+        assert_eq!(&header[4..8], &(pixels as u32).to_be_bytes());
+        assert_eq!(&header[8..], &[1, 0, 0, 0, 0]);
+
+        let raw = inflate_stored(chunks[1].1);
+        let row_len = pixels.div_ceil(8) + 1;
+        assert_eq!(raw.len(), pixels * row_len);
+        let white = |x: usize, y: usize| raw[y * row_len + 1 + x / 8] & (0x80 >> (x % 8)) != 0;
+        // The quiet zone is white and the finder pattern starts dark right after it.
+        let edge = QUIET_ZONE * PNG_SCALE;
+        assert!(white(0, 0) && white(edge - 1, edge - 1));
+        assert!(!white(edge, edge));
+        assert!((0..pixels).all(|y| raw[y * row_len] == 0));
+    }
+
+    #[test]
+    fn large_data_is_split_into_stored_blocks() {
+        let data: Vec<u8> = (0..200_000u32).map(|i| i as u8).collect();
+        assert_eq!(inflate_stored(&zlib_stored(&data)), data);
+        assert_eq!(inflate_stored(&zlib_stored(&[])), Vec::<u8>::new());
+    }
+}
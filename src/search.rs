@@ -0,0 +1,127 @@
+//! Searching the editor buffer, as `/`, `?`, `n`, `N`, `*` and `#` do.
+//!
+//! Patterns are matched literally and case-sensitively: configs are full of
+//! keys and addresses, where a regex metacharacter or a case-insensitive
+//! match would only get in the way. Columns are in bytes, as in the editor.
+
+use std::ops::Range;
+
+use crate::motions::Position;
+
+/// A search typed after `/` or `?`, or started with `*` or `#`.
+#[derive(Clone, Debug, Default)]
+pub struct Search {
+    pub pattern: String,
+    /// Whether `n` goes towards the end of the buffer: true for `/` and `*`.
+    pub forward: bool,
+    /// Only match whole tokens, as `*` and `#` do.
+    pub whole: bool,
+}
+
+impl Search {
+    /// Byte ranges of the matches in a line.
+    pub fn matches<'a>(&'a self, line: &'a str) -> impl Iterator<Item = Range<usize>> + 'a {
+        line.match_indices(self.pattern.as_str())
+            .filter(|_| !self.pattern.is_empty())
+            .map(|(start, text)| start..start + text.len())
+            .filter(move |range| !self.whole || is_whole(line, range))
+    }
+
+    /// The next match after `from`, searching forward or backward and
+    /// wrapping around the buffer. Also returns whether it wrapped.
+    pub fn find(&self, lines: &[String], from: Position, forward: bool) -> Option<(Position, bool)> {
+        let rows = lines.len();
+        // Row by row, ending on the starting row again for matches before (or after) the cursor.
+        for step in 0..=rows {
+            let row = if forward { (from.0 + step) % rows } else { (from.0 + rows - step % rows) % rows };
+            let mut starts = self.matches(&lines[row]).map(|range| range.start);
+            let col = if forward {
+                starts.find(|col| step > 0 || *col > from.1)
+            } else {
+                starts.filter(|col| step > 0 || *col < from.1).last()
+            };
+            if let Some(col) = col {
+                let wrapped = if forward { (row, col) <= from } else { (row, col) >= from };
+                return Some(((row, col), wrapped));
+            }
+        }
+        None
+    }
+}
+
+/// Whether a byte ends a token for `*`: blanks and the commas between list items.
+fn is_separator(byte: u8) -> bool {
+    matches!(byte, b' ' | b'\t' | b',')
+}
+
+fn is_whole(line: &str, range: &Range<usize>) -> bool {
+    let bytes = line.as_bytes();
+    let before = range.start == 0 || is_separator(bytes[range.start - 1]);
+    let after = bytes.get(range.end).is_none_or(|b| is_separator(*b));
+    before && after
+}
+
+/// The token `*` searches for: the run of non-blank characters at or after
+/// `col`, up to a comma. Unlike a Vim word this keeps keys, addresses and
+/// endpoints whole, e.g. `aPx…F10=` or `10.0.0.2/32`.
+pub fn token_at(line: &str, col: usize) -> Option<Range<usize>> {
+    let bytes = line.as_bytes();
+    let start = (col..bytes.len()).find(|i| !is_separator(bytes[*i]))?;
+    let start = (0..start).rev().find(|i| is_separator(bytes[*i])).map_or(0, |i| i + 1);
+    let end = (start..bytes.len()).find(|i| is_separator(bytes[*i])).unwrap_or(bytes.len());
+    Some(start..end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines() -> Vec<String> {
+        ["[Peer]", "AllowedIPs = 10.0.0.2/32, 10.0.0.20/32", "", "[Peer]"].map(str::to_string).to_vec()
+    }
+
+    fn search(pattern: &str, whole: bool) -> Search {
+        Search { pattern: pattern.into(), forward: true, whole }
+    }
+
+    #[test]
+    fn finds_matches_and_wraps_around() {
+        let lines = lines();
+        let peer = search("[Peer]", false);
+        assert_eq!(peer.find(&lines, (0, 0), true), Some(((3, 0), false)));
+        assert_eq!(peer.find(&lines, (3, 0), true), Some(((0, 0), true)));
+        assert_eq!(peer.find(&lines, (3, 0), false), Some(((0, 0), false)));
+        assert_eq!(peer.find(&lines, (0, 0), false), Some(((3, 0), true)));
+        assert_eq!(peer.find(&lines, (2, 0), false), Some(((0, 0), false)));
+
+        // The only match is found again from itself, after wrapping.
+        let address = search("10.0.0.20", false);
+        assert_eq!(address.find(&lines, (1, 26), true), Some(((1, 26), true)));
+        assert_eq!(address.find(&lines, (1, 26), false), Some(((1, 26), true)));
+        assert_eq!(search("nothing", false).find(&lines, (1, 0), true), None);
+        assert_eq!(search("", false).find(&lines, (1, 0), true), None);
+    }
+
+    #[test]
+    fn whole_searches_match_tokens_only() {
+        let line = &lines()[1];
+        let ranges = |search: Search| search.matches(line).collect::<Vec<_>>();
+        assert_eq!(ranges(search("10.0.0.2", false)), [13..21, 26..34]);
+        assert_eq!(ranges(search("10.0.0.2", true)), []);
+        assert_eq!(ranges(search("10.0.0.2/32", true)), vec![13..24]);
+        assert_eq!(ranges(search("10.0.0.20/32", true)), vec![26..38]);
+        assert_eq!(ranges(search("AllowedIPs", true)), vec![0..10]);
+    }
+
+    #[test]
+    fn tokens_are_keys_and_list_items() {
+        let line = &lines()[1];
+        assert_eq!(token_at(line, 0), Some(0..10));
+        assert_eq!(token_at(line, 4), Some(0..10));
+        assert_eq!(token_at(line, 10), Some(11..12));
+        assert_eq!(token_at(line, 17), Some(13..24));
+        assert_eq!(token_at(line, 24), Some(26..38));
+        assert_eq!(token_at(line, 38), None);
+        assert_eq!(token_at("", 0), None);
+    }
+}
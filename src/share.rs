@@ -0,0 +1,328 @@
+//! The share screen: a profile, or a new client of it, as a QR code for the
+//! WireGuard mobile apps.
+
+use std::fs;
+
+use crossterm::event::{KeyCode, KeyEvent};
+use qrcode::QrCode;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, Paragraph, Wrap},
+    Frame,
+};
+
+use rustguard::client::add_client;
+use rustguard::config::Config;
+use rustguard::profiles::{profile_path, save_profile};
+use rustguard::qr;
+
+use crate::prompt::{self, PromptState};
+
+/// What a question asked by the share screen is for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShareAction {
+    /// Save the code to the answered path.
+    Save,
+    /// Generate a client connecting to the answered endpoint.
+    NewClient,
+}
+
+/// A generated client that has not been added to the profile yet.
+struct Client {
+    name: String,
+    /// The profile with the client added as a peer.
+    server: Config,
+    /// The client's own config, shown as the code.
+    config: String,
+    added: bool,
+}
+
+/// State of the share screen.
+pub struct ShareState {
+    pub profile: String,
+    /// The last generated client, kept until another one is generated.
+    client: Option<Client>,
+    /// Whether the code is the client's config rather than the profile.
+    showing_client: bool,
+    code: Result<QrCode, String>,
+    has_private_key: bool,
+    prompt: Option<PromptState<ShareAction>>,
+    message: Option<(String, Color)>,
+}
+
+impl ShareState {
+    /// Show the QR code of a profile.
+    pub fn new(profile: String) -> Self {
+        let mut state = Self {
+            profile,
+            client: None,
+            showing_client: false,
+            code: Err(String::new()),
+            has_private_key: false,
+            prompt: None,
+            message: None,
+        };
+        state.show_profile();
+        state
+    }
+
+    fn show_profile(&mut self) {
+        let path = profile_path(&self.profile);
+        self.showing_client = false;
+        match fs::read_to_string(&path) {
+            Ok(text) => self.show(&text),
+            Err(e) => {
+                self.code = Err(format!("Failed to read {}: {}", path, e));
+                self.has_private_key = false;
+            }
+        }
+    }
+
+    fn show_client(&mut self) {
+        if let Some(config) = self.client.as_ref().map(|client| client.config.clone()) {
+            self.show(&config);
+            self.showing_client = true;
+        }
+    }
+
+    /// The client whose config is shown, if any.
+    fn shown_client(&self) -> Option<&Client> {
+        self.client.as_ref().filter(|_| self.showing_client)
+    }
+
+    fn show(&mut self, text: &str) {
+        let config = Config::parse(text);
+        self.has_private_key = config.interface().is_some_and(|i| i.get("PrivateKey").is_some());
+        self.code = qr::encode(&qr::compact(text));
+    }
+
+    /// Name the code is saved under by default.
+    fn file_stem(&self) -> String {
+        match self.shown_client() {
+            Some(client) => format!("{}-{}", self.profile, client.name),
+            None => self.profile.clone(),
+        }
+    }
+
+    fn generate_client(&mut self, endpoint: &str) -> Result<(), String> {
+        let mut server = Config::load(profile_path(&self.profile))
+            .map_err(|e| format!("Failed to read {}: {}", profile_path(&self.profile), e))?;
+        let name = format!("client-{}", server.peers().count() + 1);
+        let config = add_client(&mut server, &name, endpoint)?.to_string();
+        self.message = Some((
+            format!(
+                "Generated {}. Its private key exists only in this code: scan or save it before leaving. \
+                 Press A to add it as a peer of {}.",
+                name, self.profile
+            ),
+            Color::Yellow,
+        ));
+        self.client = Some(Client { name, server, config, added: false });
+        self.show_client();
+        Ok(())
+    }
+
+    /// Save the shown code. Returns a status message.
+    fn save(&mut self, path: &str) -> Result<String, String> {
+        let code = self.code.as_ref().map_err(String::clone)?;
+        qr::save(code, path)?;
+        let msg = format!("Saved the QR code of {} to {}", self.file_stem(), path);
+        self.message = Some((msg.clone(), Color::Green));
+        Ok(msg)
+    }
+
+    /// Add the shown client as a peer of the profile. Returns a status message.
+    fn add_client_peer(&mut self) -> Option<String> {
+        let client = self.client.as_mut().filter(|client| !client.added)?;
+        let message = format!("Added {} from the QR code screen", client.name);
+        let (msg, color) = match save_profile(&self.profile, &client.server.to_string(), &message) {
+            Ok(outcome) => {
+                client.added = true;
+                (
+                    outcome.report(format!(
+                        "Added {} as a peer of {}; if {} is up, press A in the manager to apply it live.",
+                        client.name, self.profile, self.profile
+                    )),
+                    Color::Green,
+                )
+            }
+            Err(e) => (e, Color::Red),
+        };
+        self.message = Some((msg.clone(), color));
+        Some(msg)
+    }
+
+    /// Handle a key press.
+    ///
+    /// Returns whether the screen should close, and a message for the status
+    /// log when something was written.
+    pub fn handle_event(&mut self, key: KeyEvent) -> (bool, Option<String>) {
+        if let Some(prompt) = &mut self.prompt {
+            match prompt.handle_event(key) {
+                None => {}
+                Some(None) => self.prompt = None,
+                Some(Some(answer)) => {
+                    let result = match prompt.action {
+                        ShareAction::Save => self.save(&answer).map(Some),
+                        ShareAction::NewClient => self.generate_client(&answer).map(|()| None),
+                    };
+                    match result {
+                        Ok(msg) => {
+                            self.prompt = None;
+                            return (false, msg);
+                        }
+                        Err(e) => {
+                            if let Some(prompt) = &mut self.prompt {
+                                prompt.fail(e);
+                            }
+                        }
+                    }
+                }
+            }
+            return (false, None);
+        }
+
+        self.message = None;
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => return (true, None),
+            KeyCode::Char('p') | KeyCode::Char('s') if self.code.is_ok() => {
+                let extension = if key.code == KeyCode::Char('p') { "png" } else { "svg" };
+                let question = "Save the QR code to (.png or .svg, readable only by you):";
+                let initial = format!("{}.{}", self.file_stem(), extension);
+                self.prompt = Some(PromptState::input(ShareAction::Save, "Save QR Code", question, &initial));
+            }
+            KeyCode::Char('g') => {
+                let question = format!(
+                    "Endpoint the new client reaches {} at (host:port, e.g. vpn.example.com:51820):",
+                    self.profile
+                );
+                self.prompt = Some(PromptState::input(ShareAction::NewClient, "New Client", &question, ""));
+            }
+            KeyCode::Char('a') => return (false, self.add_client_peer()),
+            KeyCode::Char('v') if self.showing_client => self.show_profile(),
+            KeyCode::Char('v') => self.show_client(),
+            _ => {}
+        }
+        (false, None)
+    }
+}
+
+/// Draw the share screen.
+pub fn draw(f: &mut Frame, area: Rect, state: &ShareState) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(3), Constraint::Length(4)].as_ref())
+        .split(area);
+
+    let (title, owner) = match state.shown_client() {
+        Some(client) => (format!(" QR Code: {} of {} ", client.name, state.profile), client.name.as_str()),
+        None => (format!(" QR Code: {} ", state.profile), state.profile.as_str()),
+    };
+    let warning = if state.has_private_key {
+        Paragraph::new(format!(
+            "⚠ Contains the PrivateKey of {}: anyone who sees this code can connect as it.",
+            owner
+        ))
+        .style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))
+    } else {
+        Paragraph::new("No PrivateKey in this profile; the app will have to be given one.")
+            .style(Style::default().fg(Color::DarkGray))
+    };
+    f.render_widget(
+        warning.wrap(Wrap { trim: true }).block(Block::default().title(title).borders(Borders::ALL)),
+        chunks[0],
+    );
+
+    let block = Block::default().borders(Borders::ALL);
+    let inner = block.inner(chunks[1]);
+    f.render_widget(block, chunks[1]);
+    match &state.code {
+        Ok(code) => {
+            let lines = qr::half_blocks(code);
+            let (width, height) = (qr::size(code) as u16, lines.len() as u16);
+            if width > inner.width || height > inner.height {
+                let msg = format!(
+                    "The terminal is too small for the QR code: it needs {}x{} cells inside the border, \
+                     there are {}x{}. Enlarge the window or save the code with P or S.",
+                    width, height, inner.width, inner.height
+                );
+                f.render_widget(Paragraph::new(msg).wrap(Wrap { trim: true }), inner);
+            } else {
+                let rect = Rect {
+                    x: inner.x + (inner.width - width) / 2,
+                    y: inner.y + (inner.height - height) / 2,
+                    width,
+                    height,
+                };
+                let text: Vec<Line> = lines.into_iter().map(Line::from).collect();
+                // Scanners expect dark modules on a light background, whatever the terminal theme.
+                let qr_style = Style::default().fg(Color::Black).bg(Color::White);
+                f.render_widget(Paragraph::new(text).style(qr_style), rect);
+            }
+        }
+        Err(e) => f.render_widget(
+            Paragraph::new(e.clone()).style(Style::default().fg(Color::Red)).wrap(Wrap { trim: true }),
+            inner,
+        ),
+    }
+
+    let footer = match &state.message {
+        Some((message, color)) => Paragraph::new(message.clone()).style(Style::default().fg(*color)),
+        None => {
+            let mut help = String::from("P: Save PNG | S: Save SVG | G: New client config");
+            match &state.client {
+                Some(client) if !client.added => help.push_str(&format!(" | A: Add client to {}", state.profile)),
+                _ => {}
+            }
+            if state.showing_client {
+                help.push_str(" | V: Back to profile");
+            } else if let Some(client) = &state.client {
+                help.push_str(&format!(" | V: Show {}", client.name));
+            }
+            help.push_str(" | Esc: Back");
+            Paragraph::new(help)
+        }
+    };
+    f.render_widget(
+        footer.wrap(Wrap { trim: true }).block(Block::default().borders(Borders::ALL)),
+        chunks[2],
+    );
+
+    if let Some(prompt) = &state.prompt {
+        prompt::draw(f, area, prompt);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+    use rustguard::testutil::{server_config, TestDirs};
+
+    fn press(share: &mut ShareState, c: char) -> (bool, Option<String>) {
+        share.handle_event(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE))
+    }
+
+    #[test]
+    fn going_back_to_the_profile_keeps_the_client() {
+        let dirs = TestDirs::new();
+        let profile = dirs.profile("wg0", &server_config());
+        let mut share = ShareState::new(profile.name.clone());
+        share.generate_client("vpn.example.com:51820").unwrap();
+        assert_eq!(share.file_stem(), "wg0-client-3");
+
+        press(&mut share, 'v');
+        assert_eq!(share.file_stem(), "wg0");
+        press(&mut share, 'v');
+        assert_eq!(share.file_stem(), "wg0-client-3");
+        press(&mut share, 'v');
+
+        let (_, message) = press(&mut share, 'a');
+        assert!(message.unwrap().contains("Added client-3"));
+        let saved = Config::load(profile.path()).unwrap();
+        assert_eq!(saved.peers().count(), 3);
+        assert_eq!(press(&mut share, 'a'), (false, None));
+    }
+}
@@ -0,0 +1,275 @@
+//! Runtime state of WireGuard interfaces.
+//!
+//! State is read from the kernel over generic netlink where possible, with
+//! `wg show all dump` as a fallback for systems where that is unavailable
+//! (non-Linux hosts, userspace implementations, missing permissions).
+
+use std::fmt;
+use std::net::SocketAddr;
+use std::process::Command;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::backend::VpnBackend;
+
+/// Runtime state of a WireGuard interface.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct InterfaceStatus {
+    pub name: String,
+    pub public_key: Option<String>,
+    /// Only available when running with CAP_NET_ADMIN.
+    pub private_key: Option<String>,
+    pub listen_port: u16,
+    /// 0 when no firewall mark is set.
+    pub fwmark: u32,
+    pub peers: Vec<PeerStatus>,
+}
+
+/// Runtime state of a single peer of an interface.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PeerStatus {
+    pub public_key: String,
+    pub preshared_key: Option<String>,
+    pub endpoint: Option<SocketAddr>,
+    pub allowed_ips: Vec<String>,
+    pub last_handshake: Option<SystemTime>,
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+    /// Keepalive interval in seconds; None when disabled.
+    pub persistent_keepalive: Option<u16>,
+}
+
+/// Query every WireGuard interface on the system.
+pub fn query_all() -> Result<Vec<InterfaceStatus>, String> {
+    #[cfg(target_os = "linux")]
+    if let Ok(interfaces) = crate::netlink::list_devices() {
+        return Ok(interfaces);
+    }
+    query_all_wg()
+}
+
+/// Query a single interface. Returns Ok(None) if it does not exist.
+pub fn query(interface: &str) -> Result<Option<InterfaceStatus>, String> {
+    #[cfg(target_os = "linux")]
+    match crate::netlink::get_device(interface) {
+        Ok(status) => return Ok(Some(status)),
+        Err(e) if e.raw_os_error() == Some(libc::ENODEV) => return Ok(None),
+        Err(_) => {}
+    }
+    Ok(query_all_wg()?.into_iter().find(|i| i.name == interface))
+}
+
+/// Get active VPN interfaces from the backend.
+pub fn get_active_vpns(backend: &dyn VpnBackend) -> Result<Vec<String>, String> {
+    backend.list_interfaces()
+}
+
+/// Get full details for a VPN interface, laid out like "wg show <interface>".
+pub fn get_vpn_details(backend: &dyn VpnBackend, interface: &str) -> String {
+    match backend.interface_details(interface) {
+        Ok(Some(status)) => status.to_string(),
+        Ok(None) => format!("{} is not active", interface),
+        Err(e) => e,
+    }
+}
+
+/// Query every interface through the `wg` command line tool.
+pub fn query_all_wg() -> Result<Vec<InterfaceStatus>, String> {
+    let output = Command::new("wg")
+        .args(["show", "all", "dump"])
+        .output()
+        .map_err(|e| format!("Failed to get VPN status: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "Failed to get VPN status: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(parse_dump(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Parse the tab-separated output of `wg show all dump`.
+pub fn parse_dump(text: &str) -> Vec<InterfaceStatus> {
+    let mut interfaces: Vec<InterfaceStatus> = Vec::new();
+    let optional = |s: &str| match s {
+        "(none)" | "off" | "" => None,
+        s => Some(s.to_string()),
+    };
+
+    for line in text.lines() {
+        let fields: Vec<&str> = line.split('\t').collect();
+        match fields.as_slice() {
+            [name, private_key, public_key, listen_port, fwmark] => interfaces.push(InterfaceStatus {
+                name: name.to_string(),
+                public_key: optional(public_key),
+                private_key: optional(private_key),
+                listen_port: listen_port.parse().unwrap_or(0),
+                fwmark: crate::config::parse_fwmark(fwmark).unwrap_or(0),
+                peers: Vec::new(),
+            }),
+            [name, public_key, preshared_key, endpoint, allowed_ips, handshake, rx, tx, keepalive] => {
+                let Some(interface) = interfaces.iter_mut().rev().find(|i| i.name == *name) else {
+                    continue;
+                };
+                let handshake: u64 = handshake.parse().unwrap_or(0);
+                interface.peers.push(PeerStatus {
+                    public_key: public_key.to_string(),
+                    preshared_key: optional(preshared_key),
+                    endpoint: endpoint.parse().ok(),
+                    allowed_ips: optional(allowed_ips)
+                        .map(|ips| ips.split(',').map(str::to_string).collect())
+                        .unwrap_or_default(),
+                    last_handshake: (handshake > 0).then(|| UNIX_EPOCH + Duration::from_secs(handshake)),
+                    rx_bytes: rx.parse().unwrap_or(0),
+                    tx_bytes: tx.parse().unwrap_or(0),
+                    persistent_keepalive: keepalive.parse().ok().filter(|k| *k > 0),
+                });
+            }
+            _ => {}
+        }
+    }
+    interfaces
+}
+
+/// Format a byte count in binary units, as `wg show` does.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.2} {}", value, UNITS[unit])
+    }
+}
+
+/// Format a duration as a compact relative age such as "3m 12s".
+pub fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m {}s", secs / 60, secs % 60),
+        3600..=86399 => format!("{}h {}m", secs / 3600, secs % 3600 / 60),
+        _ => format!("{}d {}h", secs / 86400, secs % 86400 / 3600),
+    }
+}
+
+/// Time elapsed since a handshake, or None if there never was one.
+pub fn handshake_age(handshake: Option<SystemTime>) -> Option<Duration> {
+    handshake.map(|t| SystemTime::now().duration_since(t).unwrap_or_default())
+}
+
+impl fmt::Display for InterfaceStatus {
+    /// Mirrors the layout of `wg show <interface>`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "interface: {}", self.name)?;
+        if let Some(key) = &self.public_key {
+            writeln!(f, "  public key: {}", key)?;
+        }
+        if self.private_key.is_some() {
+            writeln!(f, "  private key: (hidden)")?;
+        }
+        if self.listen_port > 0 {
+            writeln!(f, "  listening port: {}", self.listen_port)?;
+        }
+        if self.fwmark > 0 {
+            writeln!(f, "  fwmark: {:#x}", self.fwmark)?;
+        }
+        for peer in &self.peers {
+            writeln!(f)?;
+            writeln!(f, "peer: {}", peer.public_key)?;
+            if peer.preshared_key.is_some() {
+                writeln!(f, "  preshared key: (hidden)")?;
+            }
+            if let Some(endpoint) = peer.endpoint {
+                writeln!(f, "  endpoint: {}", endpoint)?;
+            }
+            let allowed_ips = if peer.allowed_ips.is_empty() {
+                "(none)".to_string()
+            } else {
+                peer.allowed_ips.join(", ")
+            };
+            writeln!(f, "  allowed ips: {}", allowed_ips)?;
+            if let Some(age) = handshake_age(peer.last_handshake) {
+                writeln!(f, "  latest handshake: {} ago", format_age(age))?;
+            }
+            if peer.rx_bytes > 0 || peer.tx_bytes > 0 {
+                writeln!(
+                    f,
+                    "  transfer: {} received, {} sent",
+                    format_bytes(peer.rx_bytes),
+                    format_bytes(peer.tx_bytes)
+                )?;
+            }
+            if let Some(keepalive) = peer.persistent_keepalive {
+                writeln!(f, "  persistent keepalive: every {} seconds", keepalive)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY_1: &str = "AQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQE=";
+    const KEY_2: &str = "AgICAgICAgICAgICAgICAgICAgICAgICAgICAgICAgI=";
+    const KEY_3: &str = "AwMDAwMDAwMDAwMDAwMDAwMDAwMDAwMDAwMDAwMDAwM=";
+
+    #[test]
+    fn parses_wg_show_all_dump() {
+        let dump = [
+            format!("wg0\t{}\t{}\t51820\toff", KEY_1, KEY_2),
+            format!("wg0\t{}\t{}\t1.2.3.4:51820\t10.0.0.2/32,fd00::2/128\t1700000000\t1234\t99\t25", KEY_2, KEY_3),
+            format!("wg0\t{}\t(none)\t[fd00::1]:443\t(none)\t0\t0\t0\toff", KEY_3),
+            format!("wg1\t(none)\t{}\t0\t0x51", KEY_3),
+            format!("wg1\t{}\t(none)\t(none)\t10.9.0.0/16\t0\t5\t6\toff", KEY_1),
+        ]
+        .join("\n");
+        let interfaces = parse_dump(&dump);
+        assert_eq!(interfaces.len(), 2);
+
+        let wg0 = &interfaces[0];
+        assert_eq!(wg0.name, "wg0");
+        assert_eq!(wg0.private_key.as_deref(), Some(KEY_1));
+        assert_eq!(wg0.public_key.as_deref(), Some(KEY_2));
+        assert_eq!((wg0.listen_port, wg0.fwmark), (51820, 0));
+        let v4 = &wg0.peers[0];
+        assert_eq!(v4.preshared_key.as_deref(), Some(KEY_3));
+        assert_eq!(v4.endpoint, Some("1.2.3.4:51820".parse().unwrap()));
+        assert_eq!(v4.allowed_ips, ["10.0.0.2/32", "fd00::2/128"]);
+        assert_eq!(v4.last_handshake, Some(UNIX_EPOCH + Duration::from_secs(1_700_000_000)));
+        assert_eq!((v4.rx_bytes, v4.tx_bytes, v4.persistent_keepalive), (1234, 99, Some(25)));
+        let v6 = &wg0.peers[1];
+        assert_eq!(v6.preshared_key, None);
+        assert_eq!(v6.endpoint, Some("[fd00::1]:443".parse().unwrap()));
+        assert!(v6.allowed_ips.is_empty());
+        assert_eq!(v6.last_handshake, None);
+        assert_eq!(v6.persistent_keepalive, None);
+
+        let wg1 = &interfaces[1];
+        assert_eq!((wg1.private_key.as_deref(), wg1.fwmark), (None, 0x51));
+        assert_eq!(wg1.peers.len(), 1);
+        assert_eq!(wg1.peers[0].endpoint, None);
+    }
+
+    #[test]
+    fn malformed_dump_lines_are_skipped() {
+        let dump = format!(
+            "\ngarbage\nwg9\t{}\t(none)\t(none)\t(none)\t0\t0\t0\toff\nwg0\t(none)\t{}\tport\tfw\n\
+             wg0\t{}\t(none)\tnot an endpoint\t(none)\tx\t-1\t\tmaybe\nwg0\t{}\t(none)\t(none)\n",
+            KEY_1, KEY_2, KEY_3, KEY_1
+        );
+        let interfaces = parse_dump(&dump);
+        assert_eq!(interfaces.len(), 1);
+        assert_eq!((interfaces[0].listen_port, interfaces[0].fwmark), (0, 0));
+        let peer = &interfaces[0].peers[0];
+        assert_eq!(interfaces[0].peers.len(), 1);
+        assert_eq!((peer.endpoint, peer.last_handshake, peer.rx_bytes), (None, None, 0));
+        assert_eq!(peer.persistent_keepalive, None);
+    }
+}
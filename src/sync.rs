@@ -0,0 +1,569 @@
+//! Applying an edited profile to its running interface without taking it
+//! down, like `wg syncconf` does, and noticing when a running interface was
+//! changed behind RustGuard's back, e.g. with `wg set`.
+//!
+//! `wg syncconf` only knows the WireGuard settings. Changes to the settings
+//! wg-quick applies itself (addresses, DNS, MTU, routing table) need the
+//! interface restarted. To notice them, the config each interface was last
+//! brought up or applied with is kept in the state directory.
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::fs;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::PathBuf;
+
+use crate::backend::VpnBackend;
+use crate::config::{Config, Section, SectionKind};
+use crate::history::state_path;
+use crate::keys;
+use crate::profiles::{create_private_dir, profile_path, write_private};
+use crate::status::{InterfaceStatus, PeerStatus};
+use crate::validate;
+
+/// `[Interface]` keys that can only be changed by restarting the interface.
+pub const RESTART_KEYS: &[&str] = &["Address", "DNS", "MTU", "Table"];
+
+/// Directory of the configs interfaces are running with.
+pub fn applied_path() -> String {
+    format!("{}applied", state_path())
+}
+
+fn applied_file(interface: &str) -> PathBuf {
+    PathBuf::from(applied_path()).join(format!("{}.conf", interface))
+}
+
+/// Remember the config an interface is now running with.
+pub fn record_applied(interface: &str, config: &Config) -> Result<(), String> {
+    create_private_dir(&applied_path())?;
+    let path = applied_file(interface);
+    fs::remove_file(&path).ok();
+    write_private(&path, config.to_string().as_bytes())
+}
+
+/// Forget the config of an interface that was taken down.
+pub fn forget_applied(interface: &str) -> Result<(), String> {
+    let path = applied_file(interface);
+    match fs::remove_file(&path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(format!("Failed to remove {}: {}", path.display(), e))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// The config an interface was last brought up or applied with by RustGuard.
+pub fn applied_config(interface: &str) -> Option<Config> {
+    Config::load(applied_file(interface)).ok()
+}
+
+/// A difference between the peers of a running interface and its profile.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PeerChange {
+    Added(String),
+    Removed(String),
+    /// A peer whose settings differ, and the keys that differ.
+    Changed(String, Vec<&'static str>),
+}
+
+/// What applying a profile to its running interface changes.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SyncPlan {
+    /// `[Interface]` keys `wg syncconf` will change.
+    pub interface: Vec<&'static str>,
+    pub peers: Vec<PeerChange>,
+    /// Changed keys that need a restart.
+    pub restart: Vec<&'static str>,
+    /// False when the config the interface was brought up with is unknown,
+    /// so changes needing a restart could not be detected.
+    pub baseline_known: bool,
+}
+
+impl SyncPlan {
+    /// True when the interface already matches the profile.
+    pub fn is_empty(&self) -> bool {
+        self.interface.is_empty() && self.peers.is_empty() && self.restart.is_empty()
+    }
+}
+
+impl fmt::Display for SyncPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts: Vec<String> = self.interface.iter().map(|key| format!("changed {}", key)).collect();
+        parts.extend(self.peers.iter().map(|change| match change {
+            PeerChange::Added(peer) => format!("added peer {}", peer),
+            PeerChange::Removed(peer) => format!("removed peer {}", peer),
+            PeerChange::Changed(peer, keys) => format!("changed {} of peer {}", keys.join(", "), peer),
+        }));
+        parts.extend(self.restart.iter().map(|key| format!("changed {}", key)));
+        if parts.is_empty() {
+            f.write_str("no changes")
+        } else {
+            f.write_str(&parts.join("; "))
+        }
+    }
+}
+
+/// How a peer is named in reports: its `# Name`, or the start of its key.
+fn peer_label(public_key: &str, sections: &[&Section]) -> String {
+    sections
+        .iter()
+        .find(|section| section.get("PublicKey") == Some(public_key))
+        .and_then(|section| section.friendly_name())
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("{}…", public_key.chars().take(8).collect::<String>()))
+}
+
+/// `AllowedIPs` as the kernel stores them: host bits cleared, and a single
+/// address as a /32 or /128. Entries that do not parse are compared as written.
+fn normalized_ips(ips: &[String]) -> BTreeSet<String> {
+    ips.iter().map(|ip| normalized_cidr(ip)).collect()
+}
+
+fn normalized_cidr(entry: &str) -> String {
+    let entry = entry.trim();
+    let (addr, prefix) = entry.split_once('/').unwrap_or((entry, ""));
+    let Ok(addr) = addr.parse::<IpAddr>() else {
+        return entry.to_string();
+    };
+    let max = if addr.is_ipv4() { 32 } else { 128 };
+    let prefix = if prefix.is_empty() { Some(max) } else { prefix.parse::<u32>().ok().filter(|p| *p <= max) };
+    let Some(prefix) = prefix else {
+        return entry.to_string();
+    };
+    // Shifting a mask by its full width clears it, as a /0 needs.
+    let network = match addr {
+        IpAddr::V4(addr) => {
+            let mask = u32::MAX.checked_shl(max - prefix).unwrap_or(0);
+            IpAddr::V4(Ipv4Addr::from(u32::from(addr) & mask))
+        }
+        IpAddr::V6(addr) => {
+            let mask = u128::MAX.checked_shl(max - prefix).unwrap_or(0);
+            IpAddr::V6(Ipv6Addr::from(u128::from(addr) & mask))
+        }
+    };
+    format!("{}/{}", network, prefix)
+}
+
+/// Keys of a peer that differ between the running interface and the profile.
+/// `privileged` tells whether the running state includes secrets.
+fn changed_peer_keys(running: &PeerStatus, section: &Section, privileged: bool) -> Vec<&'static str> {
+    let peer = section.as_peer();
+    let mut keys = Vec::new();
+    if privileged && peer.preshared_key != running.preshared_key {
+        keys.push("PresharedKey");
+    }
+    // Endpoints given as host names are resolved by the kernel and cannot be compared.
+    if let Some(endpoint) = peer.endpoint.as_deref().and_then(|e| e.parse::<SocketAddr>().ok()) {
+        if running.endpoint != Some(endpoint) {
+            keys.push("Endpoint");
+        }
+    }
+    if normalized_ips(&peer.allowed_ips) != normalized_ips(&running.allowed_ips) {
+        keys.push("AllowedIPs");
+    }
+    if peer.persistent_keepalive.filter(|k| *k > 0) != running.persistent_keepalive {
+        keys.push("PersistentKeepalive");
+    }
+    keys
+}
+
+/// Work out what applying `config` to the `running` interface changes.
+/// `applied` is the config the interface was brought up with, if known.
+pub fn plan(running: &InterfaceStatus, applied: Option<&Config>, config: &Config) -> SyncPlan {
+    let mut plan = SyncPlan { baseline_known: applied.is_some(), ..SyncPlan::default() };
+    let interface = config.interface().map(|s| s.as_interface()).unwrap_or_default();
+    let privileged = running.private_key.is_some();
+
+    // Comparing public keys also works without the privileges to read the private key.
+    if let Some(private_key) = &interface.private_key {
+        if keys::public_key(private_key).ok() != running.public_key {
+            plan.interface.push("PrivateKey");
+        }
+    }
+    if interface.listen_port.is_some_and(|port| port != running.listen_port) {
+        plan.interface.push("ListenPort");
+    }
+    if interface.fw_mark.unwrap_or(0) != running.fwmark {
+        plan.interface.push("FwMark");
+    }
+
+    let sections: Vec<&Section> = config.peers().collect();
+    let applied_sections: Vec<&Section> = applied.map(|a| a.peers().collect()).unwrap_or_default();
+    for section in &sections {
+        let Some(key) = section.get("PublicKey") else { continue };
+        match running.peers.iter().find(|peer| peer.public_key == key) {
+            None => plan.peers.push(PeerChange::Added(peer_label(key, &sections))),
+            Some(peer) => {
+                let keys = changed_peer_keys(peer, section, privileged);
+                if !keys.is_empty() {
+                    plan.peers.push(PeerChange::Changed(peer_label(key, &sections), keys));
+                }
+            }
+        }
+    }
+    for peer in &running.peers {
+        if !sections.iter().any(|section| section.get("PublicKey") == Some(peer.public_key.as_str())) {
+            plan.peers.push(PeerChange::Removed(peer_label(&peer.public_key, &applied_sections)));
+        }
+    }
+
+    if let (Some(before), Some(after)) = (applied.and_then(Config::interface), config.interface()) {
+        plan.restart = RESTART_KEYS
+            .iter()
+            .copied()
+            .filter(|key| before.get_list(key) != after.get_list(key))
+            .collect();
+    }
+    plan
+}
+
+/// How a running interface differs from its profile, one description per
+/// difference. Empty when they match.
+pub fn drift(running: &InterfaceStatus, config: &Config) -> Vec<String> {
+    let plan = plan(running, None, config);
+    let mut report: Vec<String> = plan
+        .interface
+        .iter()
+        .map(|key| format!("{} differs from the file", key))
+        .collect();
+    report.extend(plan.peers.iter().map(|change| match change {
+        PeerChange::Added(peer) => format!("Peer {} is in the file but not on the device", peer),
+        PeerChange::Removed(peer) => format!("Peer {} is on the device but not in the file", peer),
+        PeerChange::Changed(peer, keys) => format!("{} of peer {} differ from the file", keys.join(", "), peer),
+    }));
+    report
+}
+
+fn set_or_remove(section: &mut Section, key: &str, value: Option<String>) {
+    match value {
+        Some(value) => section.set(key, &value),
+        None => {
+            section.remove(key);
+        }
+    }
+}
+
+/// The profile changed to match its running interface, keeping comments,
+/// peer names and the settings only wg-quick knows about.
+pub fn running_config(running: &InterfaceStatus, config: &Config) -> Config {
+    let mut updated = config.clone();
+    let plan = plan(running, None, config);
+    let privileged = running.private_key.is_some();
+
+    if let Some(interface) = updated.interface_mut() {
+        for key in &plan.interface {
+            match *key {
+                "PrivateKey" => set_or_remove(interface, key, running.private_key.clone()),
+                "ListenPort" => interface.set(key, &running.listen_port.to_string()),
+                // FwMark; no mark is written as no key.
+                _ => {
+                    let mark = Some(running.fwmark).filter(|mark| *mark != 0);
+                    set_or_remove(interface, key, mark.map(|mark| format!("{:#x}", mark)));
+                }
+            }
+        }
+    }
+
+    let stale: Vec<usize> = updated
+        .sections()
+        .iter()
+        .enumerate()
+        .filter(|(_, section)| section.kind == SectionKind::Peer)
+        .filter(|(_, section)| !running.peers.iter().any(|peer| is_peer(section, peer)))
+        .map(|(index, _)| index)
+        .collect();
+    for index in stale.into_iter().rev() {
+        updated.remove_section(index);
+    }
+
+    for section in updated.peers_mut() {
+        let Some(peer) = running.peers.iter().find(|peer| is_peer(section, peer)) else {
+            continue;
+        };
+        for key in changed_peer_keys(peer, section, privileged) {
+            set_peer_key(section, key, peer);
+        }
+    }
+    for peer in &running.peers {
+        if config.peers().any(|section| is_peer(section, peer)) {
+            continue;
+        }
+        let section = updated.add_section(SectionKind::Peer);
+        section.set("PublicKey", &peer.public_key);
+        for key in ["PresharedKey", "AllowedIPs", "Endpoint", "PersistentKeepalive"] {
+            set_peer_key(section, key, peer);
+        }
+    }
+    updated
+}
+
+fn is_peer(section: &Section, peer: &PeerStatus) -> bool {
+    section.get("PublicKey") == Some(peer.public_key.as_str())
+}
+
+/// Set a key of a `[Peer]` section to its value on the device.
+fn set_peer_key(section: &mut Section, key: &str, peer: &PeerStatus) {
+    let value = match key {
+        "PresharedKey" => peer.preshared_key.clone(),
+        "AllowedIPs" => Some(peer.allowed_ips.join(", ")).filter(|ips| !ips.is_empty()),
+        "Endpoint" => peer.endpoint.map(|endpoint| endpoint.to_string()),
+        _ => peer.persistent_keepalive.map(|keepalive| keepalive.to_string()),
+    };
+    set_or_remove(section, key, value);
+}
+
+/// Apply a profile to its running interface, restarting it only if a
+/// setting `wg syncconf` cannot change was edited. Returns a report of what
+/// changed.
+pub fn apply_live(backend: &dyn VpnBackend, profile: &str) -> Result<String, String> {
+    let path = profile_path(profile);
+    let config = Config::load(&path).map_err(|e| format!("❌ Failed to read {}: {}", path, e))?;
+    let diagnostics = validate::validate(profile, &config);
+    if validate::has_errors(&diagnostics) {
+        let report: Vec<String> = diagnostics.iter().map(|d| d.to_string()).collect();
+        return Err(format!("❌ Not applying {}, it has problems:\n{}", path, report.join("\n")));
+    }
+    let running = backend
+        .interface_details(profile)?
+        .ok_or_else(|| format!("❌ {} is not up; there is nothing to apply it to.", profile))?;
+    let plan = plan(&running, applied_config(profile).as_ref(), &config);
+
+    let mut msg = if !plan.restart.is_empty() {
+        backend
+            .bring_down(profile)
+            .map_err(|e| format!("❌ Failed to restart {}:\n{}", profile, e))?;
+        if let Err(e) = backend.bring_up(profile) {
+            // It no longer runs with the config that was applied before.
+            forget_applied(profile).ok();
+            return Err(format!("❌ Failed to restart {}; it is now down:\n{}", profile, e));
+        }
+        format!(
+            "🔄 Restarted {}, since {} cannot be changed live: {}",
+            profile,
+            plan.restart.join(", "),
+            plan
+        )
+    } else if plan.is_empty() {
+        format!("✅ {} already runs with its current config", profile)
+    } else {
+        backend
+            .apply_config(profile, &config)
+            .map_err(|e| format!("❌ Failed to apply {}:\n{}", profile, e))?;
+        format!("✅ Applied {} live: {}", profile, plan)
+    };
+    if !plan.baseline_known {
+        msg.push_str(&format!(
+            "\n⚠️ {} was not brought up by RustGuard, so changes to {} could not be detected; \
+             reconnect if you changed them.",
+            profile,
+            RESTART_KEYS.join(", ")
+        ));
+    }
+    if let Err(e) = record_applied(profile, &config) {
+        msg.push_str(&format!("\n⚠️ {}", e));
+    }
+    Ok(msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::MockBackend;
+    use crate::testutil::{server_config, TestDirs, OTHER_PEER_KEY, PEER_KEY, PRIVATE_KEY};
+
+    const NEW_PEER_KEY: &str = "CPxGwq8zERHQ3Q1cOZFdJ+cvJX5Ka4mLN38AyYKYF10=";
+
+    /// The state of an interface brought up with `text`.
+    fn running(text: &str) -> InterfaceStatus {
+        let mock = MockBackend::new();
+        mock.add_profile("wg9", Config::parse(text));
+        mock.bring_up("wg9").expect("up succeeds");
+        mock.interface_details("wg9").unwrap().expect("wg9 is up")
+    }
+
+    #[test]
+    fn nothing_to_do_for_an_unchanged_profile() {
+        let config = Config::parse(&server_config());
+        let plan = plan(&running(&server_config()), Some(&config), &config);
+        assert!(plan.is_empty());
+        assert!(plan.baseline_known);
+        assert_eq!(plan.to_string(), "no changes");
+    }
+
+    #[test]
+    fn peers_added_removed_and_changed() {
+        let before = server_config();
+        let after = before
+            .replace("10.0.0.2/32\nEndpoint = 1.2.3.4:51820", "10.0.0.2/32, 10.0.1.0/24\nEndpoint = 1.2.3.5:51820")
+            .replace(OTHER_PEER_KEY, NEW_PEER_KEY);
+        let plan = plan(&running(&before), Some(&Config::parse(&before)), &Config::parse(&after));
+        assert_eq!(
+            plan.peers,
+            [
+                PeerChange::Changed("laptop".into(), vec!["Endpoint", "AllowedIPs"]),
+                PeerChange::Added("CPxGwq8z…".into()),
+                PeerChange::Removed("BPxGwq8z…".into()),
+            ]
+        );
+        assert!(plan.interface.is_empty() && plan.restart.is_empty());
+        assert_eq!(
+            plan.to_string(),
+            "changed Endpoint, AllowedIPs of peer laptop; added peer CPxGwq8z…; removed peer BPxGwq8z…"
+        );
+    }
+
+    #[test]
+    fn removed_peers_are_named_after_the_applied_config() {
+        let before = server_config();
+        let laptop = format!(
+            "[Peer]\n# Name = laptop\nPublicKey = {}\nAllowedIPs = 10.0.0.2/32\nEndpoint = 1.2.3.4:51820\n\n",
+            PEER_KEY
+        );
+        let after = before.replace(&laptop, "");
+        let plan = plan(&running(&before), Some(&Config::parse(&before)), &Config::parse(&after));
+        assert_eq!(plan.peers, [PeerChange::Removed("laptop".into())]);
+    }
+
+    #[test]
+    fn changed_peer_settings() {
+        let before = server_config();
+        let after = before
+            .replace("PersistentKeepalive = 25", &format!("PersistentKeepalive = 30\nPresharedKey = {}", NEW_PEER_KEY));
+        let plan = plan(&running(&before), None, &Config::parse(&after));
+        assert_eq!(
+            plan.peers,
+            [PeerChange::Changed("BPxGwq8z…".into(), vec!["PresharedKey", "PersistentKeepalive"])]
+        );
+    }
+
+    #[test]
+    fn interface_keys_and_keys_needing_a_restart() {
+        let before = server_config();
+        let after = before
+            .replace(PRIVATE_KEY, NEW_PEER_KEY)
+            .replace("ListenPort = 51820", "ListenPort = 51821\nFwMark = 0x10")
+            .replace("Address = 10.0.0.1/24", "Address = 10.0.0.1/16\nMTU = 1420");
+        let plan = plan(&running(&before), Some(&Config::parse(&before)), &Config::parse(&after));
+        assert_eq!(plan.interface, ["PrivateKey", "ListenPort", "FwMark"]);
+        assert_eq!(plan.restart, ["Address", "MTU"]);
+        assert!(plan.peers.is_empty());
+    }
+
+    #[test]
+    fn restarts_cannot_be_detected_without_a_baseline() {
+        let before = server_config();
+        let after = before.replace("Address = 10.0.0.1/24", "Address = 10.0.0.1/16");
+        let plan = plan(&running(&before), None, &Config::parse(&after));
+        assert!(plan.is_empty());
+        assert!(!plan.baseline_known);
+    }
+
+    #[test]
+    fn allowed_ips_are_compared_as_networks() {
+        for (written, kernel) in [
+            ("10.0.0.2", "10.0.0.2/32"),
+            ("10.0.0.1/24", "10.0.0.0/24"),
+            (" 10.0.0.2/32 ", "10.0.0.2/32"),
+            ("0.0.0.0/0", "0.0.0.0/0"),
+            ("1.2.3.4/0", "0.0.0.0/0"),
+            ("FD00::1", "fd00::1/128"),
+            ("fd00:0000:0000::0001/128", "fd00::1/128"),
+            ("fd00::1/64", "fd00::/64"),
+            ("::/0", "::/0"),
+        ] {
+            assert_eq!(normalized_cidr(written), normalized_cidr(kernel), "{} vs {}", written, kernel);
+        }
+        for (written, kernel) in [
+            ("10.0.0.2/31", "10.0.0.2/32"),
+            ("10.0.0.2", "10.0.0.3/32"),
+            ("fd00::1", "fd00::1/64"),
+        ] {
+            assert_ne!(normalized_cidr(written), normalized_cidr(kernel), "{} vs {}", written, kernel);
+        }
+        // Invalid entries never match a valid one.
+        assert_eq!(normalized_cidr("10.0.0.2/33"), "10.0.0.2/33");
+        assert_eq!(normalized_cidr("nonsense"), "nonsense");
+    }
+
+    #[test]
+    fn profiles_written_differently_from_the_kernel_have_not_drifted() {
+        let written = server_config()
+            .replace("AllowedIPs = 10.0.0.2/32", "AllowedIPs = 10.0.0.2, FD00:0:0::2, 10.0.1.1/24")
+            .replace("AllowedIPs = 10.0.0.3/32", "AllowedIPs = 10.0.0.3");
+        let mut running = running(&server_config());
+        running.peers[0].allowed_ips = vec!["10.0.0.2/32".into(), "fd00::2/128".into(), "10.0.1.0/24".into()];
+        assert_eq!(drift(&running, &Config::parse(&written)), Vec::<String>::new());
+
+        running.peers[0].allowed_ips.pop();
+        assert_eq!(
+            drift(&running, &Config::parse(&written)),
+            ["AllowedIPs of peer laptop differ from the file"]
+        );
+    }
+
+    #[test]
+    fn drift_describes_each_difference() {
+        let before = server_config();
+        let after = before.replace("ListenPort = 51820", "ListenPort = 51821").replace(OTHER_PEER_KEY, NEW_PEER_KEY);
+        assert_eq!(
+            drift(&running(&before), &Config::parse(&after)),
+            [
+                "ListenPort differs from the file",
+                "Peer CPxGwq8z… is in the file but not on the device",
+                "Peer BPxGwq8z… is on the device but not in the file",
+            ]
+        );
+    }
+
+    #[test]
+    fn apply_live_syncs_or_restarts_the_interface() {
+        let dirs = TestDirs::new();
+        let profile = dirs.profile("rgt-apply", &server_config());
+        let mock = MockBackend::new();
+        mock.add_profile(&profile.name, Config::parse(&server_config()));
+        assert!(apply_live(&mock, &profile.name).unwrap_err().contains("is not up"));
+
+        crate::backend::toggle_vpn(&mock, &profile.name, "up").expect("up succeeds");
+        let msg = apply_live(&mock, &profile.name).unwrap();
+        assert_eq!(msg, "✅ rgt-apply already runs with its current config");
+
+        let edited = server_config().replace(OTHER_PEER_KEY, NEW_PEER_KEY);
+        fs::write(profile.path(), &edited).unwrap();
+        let msg = apply_live(&mock, &profile.name).unwrap();
+        assert_eq!(msg, "✅ Applied rgt-apply live: added peer CPxGwq8z…; removed peer BPxGwq8z…");
+        assert_eq!(applied_config(&profile.name).map(|c| c.to_string()), Some(edited.clone()));
+
+        let readdressed = edited.replace("10.0.0.1/24", "10.0.0.1/16");
+        fs::write(profile.path(), &readdressed).unwrap();
+        mock.add_profile(&profile.name, Config::parse(&readdressed));
+        assert!(apply_live(&mock, &profile.name).unwrap().starts_with("🔄 Restarted rgt-apply, since Address"));
+        assert_eq!(mock.calls(), ["up rgt-apply", "apply rgt-apply", "down rgt-apply", "up rgt-apply"]);
+    }
+
+    #[test]
+    fn a_failed_restart_says_the_interface_is_down() {
+        let dirs = TestDirs::new();
+        let profile = dirs.profile("wg0", &server_config());
+        let mock = MockBackend::new();
+        mock.add_profile(&profile.name, Config::parse(&server_config()));
+        crate::backend::toggle_vpn(&mock, &profile.name, "up").expect("up succeeds");
+
+        fs::write(profile.path(), server_config().replace("10.0.0.1/24", "10.0.0.1/16")).unwrap();
+        mock.remove_profile(&profile.name);
+        let msg = apply_live(&mock, &profile.name).unwrap_err();
+        assert!(msg.starts_with("❌ Failed to restart wg0; it is now down:\nwg-quick"), "{}", msg);
+        assert_eq!(mock.list_interfaces(), Ok(vec![]));
+        assert!(applied_config(&profile.name).is_none());
+        assert_eq!(mock.calls(), ["up wg0", "down wg0", "up wg0"]);
+    }
+
+    #[test]
+    fn apply_live_refuses_invalid_profiles() {
+        let invalid = server_config().replace("ListenPort = 51820", "ListenPort = x");
+        let dirs = TestDirs::new();
+        let profile = dirs.profile("rgt-apply-bad", &invalid);
+        let mock = MockBackend::new();
+        assert!(apply_live(&mock, &profile.name).unwrap_err().starts_with("❌ Not applying"));
+        assert!(mock.calls().is_empty());
+    }
+}
@@ -0,0 +1,83 @@
+//! Fixtures for tests that need profiles and state on disk.
+//!
+//! Each test gets a directory of its own under the system temp directory,
+//! used in place of the system directories through [`profiles::set_root`],
+//! so tests need neither root nor WireGuard and never touch real profiles.
+//! Public only so the binary's tests can use it too.
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::profiles::{self, config_path, profile_path};
+
+pub const PRIVATE_KEY: &str = "GHuMwljFfqd2a7cs6BaUOmHflK23zME8VNvC5B37S3k=";
+pub const PEER_KEY: &str = "aPxGwq8zERHQ3Q1cOZFdJ+cvJX5Ka4mLN38AyYKYF10=";
+pub const OTHER_PEER_KEY: &str = "BPxGwq8zERHQ3Q1cOZFdJ+cvJX5Ka4mLN38AyYKYF10=";
+
+/// A valid server profile with two peers.
+pub fn server_config() -> String {
+    format!(
+        "[Interface]\nPrivateKey = {}\nAddress = 10.0.0.1/24\nListenPort = 51820\n\n\
+         [Peer]\n# Name = laptop\nPublicKey = {}\nAllowedIPs = 10.0.0.2/32\nEndpoint = 1.2.3.4:51820\n\n\
+         [Peer]\nPublicKey = {}\nAllowedIPs = 10.0.0.3/32\nPersistentKeepalive = 25\n",
+        PRIVATE_KEY, PEER_KEY, OTHER_PEER_KEY
+    )
+}
+
+/// A temporary directory holding the profiles, state and log of the current
+/// thread while the value lives. Dropping it removes everything in it.
+pub struct TestDirs {
+    pub root: PathBuf,
+}
+
+impl TestDirs {
+    pub fn new() -> Self {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let root = loop {
+            let n = NEXT.fetch_add(1, Ordering::Relaxed);
+            let root = std::env::temp_dir().join(format!("rustguard-test-{}-{}", std::process::id(), n));
+            if fs::create_dir(&root).is_ok() {
+                break root;
+            }
+        };
+        profiles::set_root(Some(root.clone()));
+        fs::create_dir_all(config_path()).expect("the temp directory must be writable");
+        Self { root }
+    }
+
+    /// A profile with the given contents.
+    pub fn profile(&self, name: &str, contents: &str) -> TempProfile {
+        fs::write(profile_path(name), contents).expect("the temp directory must be writable");
+        self.missing(name)
+    }
+
+    /// A profile name, without creating the file.
+    pub fn missing(&self, name: &str) -> TempProfile {
+        TempProfile { name: name.to_string() }
+    }
+}
+
+impl Default for TestDirs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for TestDirs {
+    fn drop(&mut self) {
+        profiles::set_root(None);
+        fs::remove_dir_all(&self.root).ok();
+    }
+}
+
+/// A profile in a [`TestDirs`].
+pub struct TempProfile {
+    pub name: String,
+}
+
+impl TempProfile {
+    pub fn path(&self) -> String {
+        profile_path(&self.name)
+    }
+}
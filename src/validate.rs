@@ -0,0 +1,545 @@
+//! Pre-flight validation of wg-quick configs.
+//!
+//! The checks mirror what `wg-quick` and `wg setconf` would reject, so that
+//! problems can be reported with line numbers before anything is run as root.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::net::IpAddr;
+
+use wgctrl::types::Key;
+
+use crate::config::{Config, LineKind, Section, SectionKind};
+
+/// Longest interface name the kernel accepts (IFNAMSIZ - 1).
+pub const MAX_INTERFACE_NAME_LEN: usize = 15;
+
+/// Keys that may legitimately appear more than once in a section.
+const REPEATABLE_KEYS: &[&str] = &[
+    "Address",
+    "DNS",
+    "AllowedIPs",
+    "PreUp",
+    "PostUp",
+    "PreDown",
+    "PostDown",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A single problem found in a config.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// 1-based line number, or 0 for problems with the file as a whole.
+    pub line: usize,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Diagnostic {
+    fn error(line: usize, message: impl Into<String>) -> Self {
+        Self { line, severity: Severity::Error, message: message.into() }
+    }
+
+    fn warning(line: usize, message: impl Into<String>) -> Self {
+        Self { line, severity: Severity::Warning, message: message.into() }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        if self.line > 0 {
+            write!(f, "line {}: {}: {}", self.line, label, self.message)
+        } else {
+            write!(f, "{}: {}", label, self.message)
+        }
+    }
+}
+
+/// Returns true if any diagnostic is an error.
+pub fn has_errors(diagnostics: &[Diagnostic]) -> bool {
+    diagnostics.iter().any(Diagnostic::is_error)
+}
+
+/// Check the interface name wg-quick derives from the profile file name.
+pub fn check_interface_name(name: &str) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("interface name is empty".into());
+    }
+    if name.len() > MAX_INTERFACE_NAME_LEN {
+        return Err(format!(
+            "interface name \"{}\" is {} characters long; the limit is {}",
+            name,
+            name.len(),
+            MAX_INTERFACE_NAME_LEN
+        ));
+    }
+    if let Some(c) = name.chars().find(|c| !c.is_ascii_alphanumeric() && !"_=+.-".contains(*c)) {
+        return Err(format!("interface name \"{}\" contains invalid character '{}'", name, c));
+    }
+    Ok(())
+}
+
+/// Validate a config for the given profile and return every problem found, in line order.
+pub fn validate(profile: &str, config: &Config) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    if let Err(e) = check_interface_name(profile) {
+        diagnostics.push(Diagnostic::error(0, e));
+    }
+
+    for line in config.preamble() {
+        match line.kind {
+            LineKind::Field { .. } => {
+                diagnostics.push(Diagnostic::error(line.number, "key outside of any section"))
+            }
+            LineKind::Invalid => diagnostics.push(invalid_line(line.number)),
+            _ => {}
+        }
+    }
+
+    let mut interfaces = 0;
+    let mut peer_keys: HashMap<String, usize> = HashMap::new();
+    for section in config.sections() {
+        let header = section.header().number;
+        match section.kind {
+            SectionKind::Interface => {
+                interfaces += 1;
+                if interfaces > 1 {
+                    diagnostics.push(Diagnostic::error(header, "duplicate [Interface] section"));
+                }
+                if section.get("PrivateKey").is_none() {
+                    diagnostics.push(Diagnostic::error(header, "[Interface] has no PrivateKey"));
+                }
+            }
+            SectionKind::Peer => match section.get("PublicKey") {
+                Some(key) => {
+                    let line = key_line(section, "PublicKey");
+                    if let Some(first) = peer_keys.insert(key.to_string(), line) {
+                        diagnostics.push(Diagnostic::error(
+                            line,
+                            format!("duplicate peer; this PublicKey is already used on line {}", first),
+                        ));
+                    }
+                    if section.get_list("AllowedIPs").is_empty() {
+                        diagnostics.push(Diagnostic::warning(
+                            header,
+                            "peer has no AllowedIPs and will not receive any traffic",
+                        ));
+                    }
+                }
+                None => diagnostics.push(Diagnostic::error(header, "[Peer] has no PublicKey")),
+            },
+            SectionKind::Unknown => {
+                diagnostics.push(Diagnostic::error(header, format!("unknown section [{}]", section.name())));
+            }
+        }
+        check_fields(section, &mut diagnostics);
+    }
+
+    if interfaces == 0 {
+        diagnostics.push(Diagnostic::error(0, "missing [Interface] section"));
+    }
+
+    diagnostics.sort_by_key(|d| d.line);
+    diagnostics
+}
+
+fn invalid_line(line: usize) -> Diagnostic {
+    Diagnostic::error(line, "expected a [Section] header or a Key = Value pair")
+}
+
+fn key_line(section: &Section, key: &str) -> usize {
+    section
+        .fields()
+        .filter(|(k, _, _)| k.eq_ignore_ascii_case(key))
+        .last()
+        .map(|(_, _, line)| line.number)
+        .unwrap_or(section.header().number)
+}
+
+fn check_fields(section: &Section, diagnostics: &mut Vec<Diagnostic>) {
+    let known = section.kind.known_keys();
+    let mut seen: HashMap<String, usize> = HashMap::new();
+
+    for line in section.lines() {
+        let (key, value) = match &line.kind {
+            LineKind::Field { key, value } => (key.as_str(), value.as_str()),
+            LineKind::Invalid => {
+                diagnostics.push(invalid_line(line.number));
+                continue;
+            }
+            _ => continue,
+        };
+        let n = line.number;
+
+        let canonical = match known.iter().find(|k| k.eq_ignore_ascii_case(key)) {
+            Some(canonical) => *canonical,
+            None => {
+                if section.kind != SectionKind::Unknown {
+                    let mut message = format!("unknown key \"{}\" in [{}]", key, section.name());
+                    if let Some(suggestion) = suggest_key(key, known) {
+                        message.push_str(&format!("; did you mean {}?", suggestion));
+                    }
+                    diagnostics.push(Diagnostic::error(n, message));
+                }
+                continue;
+            }
+        };
+
+        if !REPEATABLE_KEYS.contains(&canonical) {
+            if let Some(first) = seen.insert(canonical.to_string(), n) {
+                diagnostics.push(Diagnostic::warning(
+                    n,
+                    format!("{} is already set on line {}; the last value wins", canonical, first),
+                ));
+            }
+        }
+
+        if value.is_empty() {
+            diagnostics.push(Diagnostic::error(n, format!("{} has no value", canonical)));
+            continue;
+        }
+
+        let result = match canonical {
+            "PrivateKey" | "PublicKey" | "PresharedKey" => check_key(value),
+            "ListenPort" => check_port(value, false),
+            "FwMark" => check_fwmark(value),
+            "Address" => check_list(value, check_cidr),
+            "DNS" => check_list(value, check_dns),
+            "AllowedIPs" => check_list(value, check_cidr),
+            "MTU" => check_mtu(value),
+            "SaveConfig" => check_bool(value),
+            "Endpoint" => check_endpoint(value),
+            "PersistentKeepalive" => check_keepalive(value),
+            _ => Ok(()),
+        };
+        if let Err(e) = result {
+            diagnostics.push(Diagnostic::error(n, format!("{}: {}", canonical, e)));
+        }
+    }
+}
+
+/// Suggest a known key within a small edit distance of a misspelt one.
+fn suggest_key(key: &str, known: &[&'static str]) -> Option<&'static str> {
+    let key = key.to_ascii_lowercase();
+    known
+        .iter()
+        .map(|k| (edit_distance(&key, &k.to_ascii_lowercase()), *k))
+        .filter(|(d, _)| *d <= 2)
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, k)| k)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != *cb);
+            cur[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        prev = cur;
+    }
+    prev[b.len()]
+}
+
+fn check_list(value: &str, check: fn(&str) -> Result<(), String>) -> Result<(), String> {
+    for item in value.split(',').map(str::trim) {
+        if item.is_empty() {
+            return Err("empty list entry".into());
+        }
+        check(item)?;
+    }
+    Ok(())
+}
+
+/// Check a base64-encoded 32-byte Curve25519 key.
+pub fn check_key(value: &str) -> Result<(), String> {
+    if Key::try_from(value).is_ok() {
+        return Ok(());
+    }
+    if value.len() != 44 {
+        Err(format!("key must be 44 characters of base64, got {}", value.len()))
+    } else {
+        Err("key is not valid base64".into())
+    }
+}
+
+/// Check a port number; `nonzero` rejects port 0.
+pub fn check_port(value: &str, nonzero: bool) -> Result<(), String> {
+    match value.parse::<u32>() {
+        Ok(0) if nonzero => Err("port must be between 1 and 65535".into()),
+        Ok(port) if port <= 65535 => Ok(()),
+        Ok(_) => Err(format!("port {} is out of range (0-65535)", value)),
+        Err(_) => Err(format!("\"{}\" is not a port number", value)),
+    }
+}
+
+/// Check an IP address with an optional `/prefix` length.
+pub fn check_cidr(value: &str) -> Result<(), String> {
+    let (addr, prefix) = match value.split_once('/') {
+        Some((addr, prefix)) => (addr, Some(prefix)),
+        None => (value, None),
+    };
+    let ip: IpAddr = addr
+        .parse()
+        .map_err(|_| format!("\"{}\" is not an IP address", addr))?;
+    let max = if ip.is_ipv4() { 32 } else { 128 };
+    match prefix.map(str::parse::<u8>) {
+        None => Ok(()),
+        Some(Ok(p)) if p <= max => Ok(()),
+        Some(_) => Err(format!("\"{}\" is not a valid prefix length (0-{})", prefix.unwrap_or_default(), max)),
+    }
+}
+
+/// Check a `DNS` entry. As for wg-quick, an entry is either the IP address
+/// of a name server or a search domain.
+pub fn check_dns(value: &str) -> Result<(), String> {
+    if value.parse::<IpAddr>().is_ok() {
+        return Ok(());
+    }
+    let labels: Vec<&str> = value.strip_suffix('.').unwrap_or(value).split('.').collect();
+    // Anything shaped like an address is not meant as a domain.
+    if value.contains(':') || labels.iter().all(|label| label.bytes().all(|b| b.is_ascii_digit())) {
+        return Err(format!("\"{}\" is not an IP address", value));
+    }
+    let valid_label = |label: &&str| {
+        (1..=63).contains(&label.len())
+            && label.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-')
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+    };
+    if value.len() > 253 || !labels.iter().all(valid_label) {
+        return Err(format!("\"{}\" is neither an IP address nor a valid search domain", value));
+    }
+    Ok(())
+}
+
+/// Check an `Endpoint` of the form `host:port` or `[ipv6]:port`.
+pub fn check_endpoint(value: &str) -> Result<(), String> {
+    let (host, port) = if let Some(rest) = value.strip_prefix('[') {
+        let (host, port) = rest
+            .split_once("]:")
+            .ok_or_else(|| "IPv6 endpoints must look like [address]:port".to_string())?;
+        host.parse::<std::net::Ipv6Addr>()
+            .map_err(|_| format!("\"{}\" is not an IPv6 address", host))?;
+        (host, port)
+    } else {
+        let (host, port) = value
+            .rsplit_once(':')
+            .ok_or_else(|| "endpoint must be host:port".to_string())?;
+        if host.contains(':') {
+            return Err("IPv6 endpoints must be written as [address]:port".into());
+        }
+        (host, port)
+    };
+    if host.is_empty() {
+        return Err("endpoint host is empty".into());
+    }
+    check_port(port, true)
+}
+
+fn check_fwmark(value: &str) -> Result<(), String> {
+    crate::config::parse_fwmark(value)
+        .map(|_| ())
+        .ok_or_else(|| format!("\"{}\" is not a number, hex value or \"off\"", value))
+}
+
+fn check_mtu(value: &str) -> Result<(), String> {
+    match value.parse::<u32>() {
+        Ok(mtu) if (68..=65535).contains(&mtu) => Ok(()),
+        _ => Err(format!("\"{}\" is not a valid MTU (68-65535)", value)),
+    }
+}
+
+fn check_keepalive(value: &str) -> Result<(), String> {
+    crate::config::parse_keepalive(value)
+        .map(|_| ())
+        .ok_or_else(|| format!("\"{}\" is not a number of seconds (0-65535) or \"off\"", value))
+}
+
+fn check_bool(value: &str) -> Result<(), String> {
+    if value.eq_ignore_ascii_case("true") || value.eq_ignore_ascii_case("false") {
+        Ok(())
+    } else {
+        Err(format!("\"{}\" must be true or false", value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "GHuMwljFfqd2a7cs6BaUOmHflK23zME8VNvC5B37S3k=";
+    const PEER_KEY: &str = "aPxGwq8zERHQ3Q1cOZFdJ+cvJX5Ka4mLN38AyYKYF10=";
+
+    fn check_table(check: fn(&str) -> Result<(), String>, cases: &[(&str, bool)]) {
+        for (value, valid) in cases {
+            assert_eq!(check(value).is_ok(), *valid, "{:?}: {:?}", value, check(value));
+        }
+    }
+
+    #[test]
+    fn keys() {
+        check_table(
+            check_key,
+            &[
+                (KEY, true),
+                (PEER_KEY, true),
+                ("", false),
+                ("GHuMwljFfqd2a7cs6BaUOmHflK23zME8VNvC5B37S3k", false),
+                ("GHuMwljFfqd2a7cs6BaUOmHflK23zME8VNvC5B37S3k==", false),
+                ("!HuMwljFfqd2a7cs6BaUOmHflK23zME8VNvC5B37S3k=", false),
+            ],
+        );
+    }
+
+    #[test]
+    fn cidrs() {
+        check_table(
+            check_cidr,
+            &[
+                ("10.0.0.1", true),
+                ("10.0.0.0/24", true),
+                ("0.0.0.0/0", true),
+                ("10.0.0.1/32", true),
+                ("10.0.0.1/33", false),
+                ("fd00::1/64", true),
+                ("::/0", true),
+                ("fd00::1/128", true),
+                ("fd00::1/129", false),
+                ("10.0.0.256/24", false),
+                ("10.0.0.1/", false),
+                ("10.0.0.1/x", false),
+                ("example.com/24", false),
+            ],
+        );
+    }
+
+    #[test]
+    fn endpoints() {
+        check_table(
+            check_endpoint,
+            &[
+                ("1.2.3.4:51820", true),
+                ("vpn.example.com:51820", true),
+                ("[fd00::1]:51820", true),
+                ("[::1]:1", true),
+                ("1.2.3.4:65535", true),
+                ("1.2.3.4:0", false),
+                ("1.2.3.4:65536", false),
+                ("1.2.3.4", false),
+                (":51820", false),
+                ("fd00::1:51820", false),
+                ("[fd00::1]51820", false),
+                ("[not-ipv6]:51820", false),
+                ("vpn.example.com:port", false),
+            ],
+        );
+    }
+
+    #[test]
+    fn dns_entries() {
+        check_table(
+            check_dns,
+            &[
+                ("1.1.1.1", true),
+                ("2606:4700:4700::1111", true),
+                ("example.com", true),
+                ("corp.example.com.", true),
+                ("vpn-1.internal", true),
+                ("localdomain", true),
+                ("1.1.1.256", false),
+                ("1.1.1", false),
+                ("fd00::zz", false),
+                ("exa mple.com", false),
+                ("-bad.example.com", false),
+                ("bad-.example.com", false),
+                ("example..com", false),
+                ("under_score.com", false),
+                ("", false),
+            ],
+        );
+        assert_eq!(
+            errors(&format!("[Interface]\nPrivateKey = {}\nDNS = 1.1.1.1, bad domain\n", KEY)),
+            ["line 3: error: DNS: \"bad domain\" is neither an IP address nor a valid search domain"]
+        );
+    }
+
+    #[test]
+    fn mtu_bounds() {
+        check_table(
+            check_mtu,
+            &[("68", true), ("1420", true), ("65535", true), ("67", false), ("65536", false), ("-1", false)],
+        );
+    }
+
+    #[test]
+    fn interface_names() {
+        check_table(
+            check_interface_name,
+            &[("wg0", true), ("wg-home_1.2", true), ("", false), ("wg 0", false), ("a234567890123456", false)],
+        );
+    }
+
+    fn errors(text: &str) -> Vec<String> {
+        validate("wg0", &Config::parse(text))
+            .into_iter()
+            .filter(Diagnostic::is_error)
+            .map(|d| d.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn a_valid_config_has_no_errors() {
+        let text = format!(
+            "[Interface]\nPrivateKey = {}\nAddress = 10.0.0.1/24\nMTU = 1420\n\n\
+             [Peer]\nPublicKey = {}\nAllowedIPs = 10.0.0.2/32\nEndpoint = [fd00::1]:51820\n",
+            KEY, PEER_KEY
+        );
+        assert_eq!(errors(&text), Vec::<String>::new());
+    }
+
+    #[test]
+    fn duplicate_peers_are_reported_on_the_second_key() {
+        let text = format!(
+            "[Interface]\nPrivateKey = {key}\n\n[Peer]\nPublicKey = {peer}\nAllowedIPs = 10.0.0.2/32\n\n\
+             [Peer]\nPublicKey = {peer}\nAllowedIPs = 10.0.0.3/32\n",
+            key = KEY,
+            peer = PEER_KEY
+        );
+        assert_eq!(errors(&text), ["line 9: error: duplicate peer; this PublicKey is already used on line 5"]);
+    }
+
+    #[test]
+    fn structural_problems_are_reported_with_line_numbers() {
+        let text = "ListenPort = 1\n[Interface]\nMTU = 10\nPrivatKey = x\n[Peer]\n[Foo]\n";
+        assert_eq!(
+            errors(text),
+            [
+                "line 1: error: key outside of any section",
+                "line 2: error: [Interface] has no PrivateKey",
+                "line 3: error: MTU: \"10\" is not a valid MTU (68-65535)",
+                "line 4: error: unknown key \"PrivatKey\" in [Interface]; did you mean PrivateKey?",
+                "line 5: error: [Peer] has no PublicKey",
+                "line 6: error: unknown section [Foo]",
+            ]
+        );
+        let text = format!("[Peer]\nPublicKey = {}\n", PEER_KEY);
+        assert_eq!(errors(&text), ["error: missing [Interface] section"]);
+    }
+}
@@ -0,0 +1,237 @@
+//! The history screen: recorded versions of a profile, the changes each one
+//! made, and rollback to any of them.
+
+use std::fs;
+use std::time::SystemTime;
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+    Frame,
+};
+
+use rustguard::diff::{diff_lines, has_changes, unified, DEFAULT_CONTEXT};
+use rustguard::history::{history_path, read_version, versions, Version};
+use rustguard::profiles::{profile_path, save_profile};
+use rustguard::status::format_age;
+
+use crate::highlight;
+use crate::prompt::{self, PromptState};
+
+/// What the selected version is compared with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Compare {
+    /// The version before it, showing what it changed.
+    Previous,
+    /// The profile as it is now, showing what a rollback would change.
+    Current,
+}
+
+/// State of the history screen.
+pub struct HistoryState {
+    pub profile: String,
+    versions: Vec<Version>,
+    selected: usize,
+    compare: Compare,
+    /// The diff shown, as unified diff lines.
+    diff: Result<Vec<String>, String>,
+    scroll: u16,
+    confirm: Option<PromptState<()>>,
+    message: Option<(String, Color)>,
+}
+
+impl HistoryState {
+    pub fn new(profile: String) -> Self {
+        let versions = versions(&profile);
+        let mut state = Self {
+            profile,
+            versions,
+            selected: 0,
+            compare: Compare::Previous,
+            diff: Ok(Vec::new()),
+            scroll: 0,
+            confirm: None,
+            message: None,
+        };
+        state.load_diff();
+        state
+    }
+
+    fn load_diff(&mut self) {
+        self.scroll = 0;
+        self.diff = self.selected_diff();
+    }
+
+    fn selected_diff(&self) -> Result<Vec<String>, String> {
+        let Some(version) = self.versions.get(self.selected) else {
+            return Ok(Vec::new());
+        };
+        let contents = read_version(&self.profile, version)?;
+        let old = match self.compare {
+            Compare::Previous => match self.versions.get(self.selected + 1) {
+                Some(previous) => read_version(&self.profile, previous)?,
+                None => String::new(),
+            },
+            Compare::Current => {
+                let path = profile_path(&self.profile);
+                fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?
+            }
+        };
+        let diff = diff_lines(&old, &contents);
+        Ok(if has_changes(&diff) { unified(&diff, DEFAULT_CONTEXT) } else { Vec::new() })
+    }
+
+    fn select(&mut self, index: usize) {
+        self.selected = index.min(self.versions.len().saturating_sub(1));
+        self.load_diff();
+    }
+
+    /// Make the selected version the current one. Returns a status message.
+    fn rollback(&mut self) -> Option<String> {
+        let version = self.versions.get(self.selected)?.clone();
+        let result = read_version(&self.profile, &version).and_then(|contents| {
+            let message = format!("Rolled back to {} ({})", version.id, version.message);
+            save_profile(&self.profile, &contents, &message)
+        });
+        let (msg, color) = match result {
+            Ok(outcome) => (
+                outcome.report(format!("Rolled {} back to the version of {} ago", self.profile, age(&version))),
+                Color::Green,
+            ),
+            Err(e) => (e, Color::Red),
+        };
+        // The rollback is itself the newest version.
+        self.versions = versions(&self.profile);
+        self.select(0);
+        self.message = Some((msg.clone(), color));
+        Some(msg)
+    }
+
+    /// Handle a key press.
+    ///
+    /// Returns whether the screen should close, and a message for the status
+    /// log when the profile was rolled back.
+    pub fn handle_event(&mut self, key: KeyEvent) -> (bool, Option<String>) {
+        if let Some(confirm) = &mut self.confirm {
+            return match confirm.handle_event(key) {
+                None => (false, None),
+                Some(answer) => {
+                    self.confirm = None;
+                    (false, answer.and_then(|_| self.rollback()))
+                }
+            };
+        }
+
+        self.message = None;
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => return (true, None),
+            KeyCode::Up | KeyCode::Char('k') => self.select(self.selected.saturating_sub(1)),
+            KeyCode::Down | KeyCode::Char('j') => self.select(self.selected + 1),
+            KeyCode::Home | KeyCode::Char('g') => self.select(0),
+            KeyCode::End | KeyCode::Char('G') => self.select(usize::MAX),
+            KeyCode::PageDown => self.scroll = self.scroll.saturating_add(10),
+            KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(10),
+            KeyCode::Char('c') => {
+                self.compare = match self.compare {
+                    Compare::Previous => Compare::Current,
+                    Compare::Current => Compare::Previous,
+                };
+                self.load_diff();
+            }
+            KeyCode::Enter if !self.versions.is_empty() => {
+                let version = &self.versions[self.selected];
+                let question = format!(
+                    "Roll {} back to the version saved {} ago by {}? The rollback is recorded as a new version.",
+                    self.profile,
+                    age(version),
+                    version.author
+                );
+                self.confirm = Some(PromptState::confirm((), "Roll Back", &question));
+            }
+            _ => {}
+        }
+        (false, None)
+    }
+}
+
+fn age(version: &Version) -> String {
+    format_age(SystemTime::now().duration_since(version.saved_at).unwrap_or_default())
+}
+
+/// Draw the history screen.
+pub fn draw(f: &mut Frame, area: Rect, state: &HistoryState) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(3)].as_ref())
+        .split(area);
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(45), Constraint::Percentage(55)].as_ref())
+        .split(chunks[0]);
+
+    let title = format!(" History of {} ({}) ", state.profile, state.versions.len());
+    let list_block = Block::default().title(title).borders(Borders::ALL);
+    if state.versions.is_empty() {
+        let empty = Paragraph::new(format!(
+            "No versions recorded yet. Every save is recorded in {}.",
+            history_path()
+        ))
+        .wrap(Wrap { trim: true })
+        .style(Style::default().fg(Color::DarkGray))
+        .block(list_block);
+        f.render_widget(empty, columns[0]);
+    } else {
+        let items: Vec<ListItem> = state
+            .versions
+            .iter()
+            .map(|version| {
+                ListItem::new(Line::from(vec![
+                    Span::styled(format!("{:>8} ago ", age(version)), Style::default().fg(Color::Yellow)),
+                    Span::styled(format!("{} ", version.author), Style::default().fg(Color::Cyan)),
+                    Span::raw(version.message.clone()),
+                ]))
+            })
+            .collect();
+        let list = List::new(items)
+            .block(list_block)
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD | Modifier::REVERSED));
+        let mut list_state = ListState::default().with_selected(Some(state.selected));
+        f.render_stateful_widget(list, columns[0], &mut list_state);
+    }
+
+    let diff_title = match (state.versions.get(state.selected), state.compare) {
+        (Some(version), Compare::Previous) => format!(" Changes made by {} ", version.id),
+        (Some(version), Compare::Current) => format!(" Current version → {} ", version.id),
+        (None, _) => " Changes ".to_string(),
+    };
+    let diff_block = Block::default().title(diff_title).borders(Borders::ALL);
+    let diff = match &state.diff {
+        Ok(lines) if lines.is_empty() && !state.versions.is_empty() => {
+            Paragraph::new("No differences").style(Style::default().fg(Color::DarkGray))
+        }
+        Ok(lines) => Paragraph::new(
+            lines
+                .iter()
+                .map(|line| Line::from(Span::styled(line.clone(), highlight::diff_line_style(line))))
+                .collect::<Vec<_>>(),
+        ),
+        Err(e) => Paragraph::new(e.clone()).style(Style::default().fg(Color::Red)),
+    };
+    f.render_widget(diff.scroll((state.scroll, 0)).block(diff_block), columns[1]);
+
+    let footer = match &state.message {
+        Some((message, color)) => Paragraph::new(message.clone()).style(Style::default().fg(*color)),
+        None => Paragraph::new(match state.compare {
+            Compare::Previous => "↑/k, ↓/j: Select | PgUp/PgDn: Scroll | C: Compare with current | Enter: Roll back | Esc: Back",
+            Compare::Current => "↑/k, ↓/j: Select | PgUp/PgDn: Scroll | C: Show changes made | Enter: Roll back | Esc: Back",
+        }),
+    };
+    f.render_widget(footer.block(Block::default().borders(Borders::ALL)), chunks[1]);
+
+    if let Some(confirm) = &state.confirm {
+        prompt::draw(f, area, confirm);
+    }
+}
@@ -0,0 +1,405 @@
+//! Watching the configuration directory for profiles that are added, removed,
+//! renamed or rewritten by other tools.
+//!
+//! On Linux the directory is watched with inotify. Elsewhere, or when inotify
+//! is unavailable, the directory listing is polled instead.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime};
+
+use crate::profiles::{config_path, profile_name};
+
+/// How often the directory is listed when inotify cannot be used.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How long the worker waits at a time before checking whether it should stop.
+const STOP_CHECK_INTERVAL: Duration = Duration::from_millis(250);
+
+/// A change to the set of profiles.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProfileChange {
+    Added(String),
+    Removed(String),
+    Renamed { from: String, to: String },
+    /// The file of an existing profile was rewritten.
+    Modified(String),
+}
+
+/// Handle to the watcher thread. Dropping it stops the thread.
+pub struct ProfileWatcher {
+    changes: Receiver<ProfileChange>,
+    stop: Arc<AtomicBool>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl ProfileWatcher {
+    /// Watch the configuration directory.
+    pub fn spawn() -> Self {
+        Self::watch(config_path())
+    }
+
+    /// Watch the profiles in `dir`.
+    pub fn watch(dir: impl Into<PathBuf>) -> Self {
+        let dir = dir.into();
+        let (sender, changes) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let worker_stop = Arc::clone(&stop);
+        let worker = thread::Builder::new()
+            .name("rustguard-watcher".into())
+            .spawn(move || {
+                let mut known = list_profiles(&dir);
+                #[cfg(target_os = "linux")]
+                if let Ok(watch) = inotify::Watch::new(&dir) {
+                    if watch.run(&mut known, &sender, &worker_stop) {
+                        return;
+                    }
+                }
+                poll_loop(&dir, &mut known, &sender, &worker_stop);
+            })
+            .expect("Failed to spawn watcher thread");
+        Self { changes, stop, worker: Some(worker) }
+    }
+
+    /// Changes seen since the last call, oldest first.
+    pub fn changes(&self) -> Vec<ProfileChange> {
+        self.changes.try_iter().collect()
+    }
+}
+
+impl Drop for ProfileWatcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(worker) = self.worker.take() {
+            worker.join().ok();
+        }
+    }
+}
+
+/// Profiles in `dir` and when their files were last modified.
+type Known = BTreeMap<String, Option<SystemTime>>;
+
+fn list_profiles(dir: &Path) -> Known {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Known::new();
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok().filter(|m| m.is_file())?;
+            let name = profile_name(entry.file_name().to_str()?)?.to_string();
+            Some((name, metadata.modified().ok()))
+        })
+        .collect()
+}
+
+/// Compare listings of the directory until told to stop.
+fn poll_loop(dir: &Path, known: &mut Known, sender: &Sender<ProfileChange>, stop: &AtomicBool) {
+    loop {
+        let mut waited = Duration::ZERO;
+        while waited < POLL_INTERVAL {
+            if stop.load(Ordering::Relaxed) {
+                return;
+            }
+            thread::sleep(STOP_CHECK_INTERVAL);
+            waited += STOP_CHECK_INTERVAL;
+        }
+
+        let current = list_profiles(dir);
+        let mut changes = Vec::new();
+        for (name, modified) in &current {
+            match known.get(name) {
+                None => changes.push(ProfileChange::Added(name.clone())),
+                Some(before) if before != modified => changes.push(ProfileChange::Modified(name.clone())),
+                Some(_) => {}
+            }
+        }
+        for name in known.keys().filter(|name| !current.contains_key(*name)) {
+            changes.push(ProfileChange::Removed(name.clone()));
+        }
+        *known = current;
+        for change in changes {
+            if sender.send(change).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod inotify {
+    use std::ffi::CString;
+    use std::io;
+    use std::mem;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::mpsc::Sender;
+
+    use super::{Known, ProfileChange, STOP_CHECK_INTERVAL};
+    use crate::profiles::profile_name;
+
+    const WATCH_MASK: u32 = libc::IN_CREATE
+        | libc::IN_DELETE
+        | libc::IN_MOVED_FROM
+        | libc::IN_MOVED_TO
+        | libc::IN_CLOSE_WRITE
+        | libc::IN_DELETE_SELF
+        | libc::IN_MOVE_SELF;
+
+    /// Events meaning the directory itself is gone and nothing more will be reported.
+    const GONE_MASK: u32 = libc::IN_DELETE_SELF | libc::IN_MOVE_SELF | libc::IN_IGNORED | libc::IN_Q_OVERFLOW;
+
+    struct Event {
+        mask: u32,
+        cookie: u32,
+        name: String,
+    }
+
+    /// An inotify instance watching one directory.
+    pub struct Watch {
+        fd: OwnedFd,
+    }
+
+    impl Watch {
+        pub fn new(dir: &Path) -> io::Result<Self> {
+            // SAFETY: plain syscall; the result is checked before use.
+            let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) };
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            // SAFETY: fd is a freshly created descriptor that nothing else owns.
+            let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+            let path = CString::new(dir.as_os_str().as_bytes())
+                .map_err(|_| io::Error::from(io::ErrorKind::InvalidInput))?;
+            // SAFETY: path is a valid NUL-terminated string for the duration of the call.
+            if unsafe { libc::inotify_add_watch(fd.as_raw_fd(), path.as_ptr(), WATCH_MASK) } < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(Self { fd })
+        }
+
+        /// Report changes until told to stop (returns true), or until the
+        /// directory can no longer be watched (returns false).
+        pub fn run(&self, known: &mut Known, sender: &Sender<ProfileChange>, stop: &AtomicBool) -> bool {
+            while !stop.load(Ordering::Relaxed) {
+                let events = match self.read() {
+                    Ok(events) => events,
+                    Err(_) => return false,
+                };
+                if events.iter().any(|e| e.mask & GONE_MASK != 0) {
+                    return false;
+                }
+                for change in translate(known, events) {
+                    if sender.send(change).is_err() {
+                        return true;
+                    }
+                }
+            }
+            true
+        }
+
+        /// Wait a little for events and read those that are pending.
+        fn read(&self) -> io::Result<Vec<Event>> {
+            let mut pollfd = libc::pollfd { fd: self.fd.as_raw_fd(), events: libc::POLLIN, revents: 0 };
+            // SAFETY: pollfd is a valid array of one element.
+            let ready = unsafe { libc::poll(&mut pollfd, 1, STOP_CHECK_INTERVAL.as_millis() as libc::c_int) };
+            if ready < 0 {
+                let err = io::Error::last_os_error();
+                return if err.kind() == io::ErrorKind::Interrupted { Ok(Vec::new()) } else { Err(err) };
+            }
+            if ready == 0 {
+                return Ok(Vec::new());
+            }
+
+            let mut buf = [0u8; 4096];
+            // SAFETY: buf is valid for writes of buf.len() bytes.
+            let len = unsafe { libc::read(self.fd.as_raw_fd(), buf.as_mut_ptr().cast(), buf.len()) };
+            if len < 0 {
+                let err = io::Error::last_os_error();
+                return if err.kind() == io::ErrorKind::WouldBlock { Ok(Vec::new()) } else { Err(err) };
+            }
+
+            let header = mem::size_of::<libc::inotify_event>();
+            let mut events = Vec::new();
+            let mut offset = 0;
+            while offset + header <= len as usize {
+                // SAFETY: the kernel wrote a whole inotify_event at this offset.
+                let raw: libc::inotify_event =
+                    unsafe { std::ptr::read_unaligned(buf[offset..].as_ptr().cast()) };
+                let name_end = (offset + header + raw.len as usize).min(len as usize);
+                let name = &buf[offset + header..name_end];
+                let name = name.split(|b| *b == 0).next().unwrap_or_default();
+                events.push(Event {
+                    mask: raw.mask,
+                    cookie: raw.cookie,
+                    name: String::from_utf8_lossy(name).into_owned(),
+                });
+                offset = name_end;
+            }
+            Ok(events)
+        }
+    }
+
+    /// A profile file was written or moved into the directory.
+    fn appeared(known: &mut Known, changes: &mut Vec<ProfileChange>, name: &str) {
+        if let Some(profile) = profile_name(name) {
+            let change = if known.contains_key(profile) {
+                ProfileChange::Modified(profile.to_string())
+            } else {
+                ProfileChange::Added(profile.to_string())
+            };
+            known.insert(profile.to_string(), None);
+            changes.push(change);
+        }
+    }
+
+    /// A profile file was deleted or moved out of the directory.
+    fn disappeared(known: &mut Known, changes: &mut Vec<ProfileChange>, name: &str) {
+        if let Some(profile) = profile_name(name) {
+            if known.remove(profile).is_some() {
+                changes.push(ProfileChange::Removed(profile.to_string()));
+            }
+        }
+    }
+
+    /// Turn raw events into profile changes, pairing the two halves of a
+    /// rename by their cookie.
+    fn translate(known: &mut Known, events: Vec<Event>) -> Vec<ProfileChange> {
+        let mut changes = Vec::new();
+        let mut moved_from: Vec<(u32, String)> = Vec::new();
+        for event in events.into_iter().filter(|e| e.mask & libc::IN_ISDIR == 0) {
+            if event.mask & libc::IN_MOVED_FROM != 0 {
+                moved_from.push((event.cookie, event.name));
+            } else if event.mask & libc::IN_MOVED_TO != 0 {
+                let from = moved_from
+                    .iter()
+                    .position(|(cookie, _)| *cookie == event.cookie)
+                    .map(|i| moved_from.remove(i).1);
+                let from_profile = from.as_deref().and_then(profile_name);
+                match (from_profile, profile_name(&event.name)) {
+                    (Some(from), Some(to)) if known.contains_key(from) && !known.contains_key(to) => {
+                        known.remove(from);
+                        known.insert(to.to_string(), None);
+                        changes.push(ProfileChange::Renamed { from: from.to_string(), to: to.to_string() });
+                    }
+                    _ => {
+                        if let Some(from) = &from {
+                            disappeared(known, &mut changes, from);
+                        }
+                        appeared(known, &mut changes, &event.name);
+                    }
+                }
+            } else if event.mask & libc::IN_DELETE != 0 {
+                disappeared(known, &mut changes, &event.name);
+            } else if event.mask & libc::IN_CREATE != 0 {
+                if let Some(profile) = profile_name(&event.name) {
+                    if !known.contains_key(profile) {
+                        appeared(known, &mut changes, &event.name);
+                    }
+                }
+            } else if event.mask & libc::IN_CLOSE_WRITE != 0 {
+                appeared(known, &mut changes, &event.name);
+            }
+        }
+        // The other half of these renames is outside the directory.
+        for (_, name) in moved_from {
+            disappeared(known, &mut changes, &name);
+        }
+        changes
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn event(mask: u32, cookie: u32, name: &str) -> Event {
+            Event { mask, cookie, name: name.to_string() }
+        }
+
+        fn known(names: &[&str]) -> Known {
+            names.iter().map(|name| (name.to_string(), None)).collect()
+        }
+
+        fn added(name: &str) -> ProfileChange {
+            ProfileChange::Added(name.to_string())
+        }
+
+        fn removed(name: &str) -> ProfileChange {
+            ProfileChange::Removed(name.to_string())
+        }
+
+        #[test]
+        fn creating_and_writing_profiles() {
+            let mut profiles = known(&["wg0"]);
+            let events = vec![
+                event(libc::IN_CREATE, 0, "wg1.conf"),
+                event(libc::IN_CLOSE_WRITE, 0, "wg1.conf"),
+                // Creating an existing profile, as an editor saving in place does, is not an addition.
+                event(libc::IN_CREATE, 0, "wg0.conf"),
+                event(libc::IN_CLOSE_WRITE, 0, "wg0.conf"),
+            ];
+            let changes = translate(&mut profiles, events);
+            assert_eq!(
+                changes,
+                [added("wg1"), ProfileChange::Modified("wg1".into()), ProfileChange::Modified("wg0".into())]
+            );
+            assert_eq!(profiles, known(&["wg0", "wg1"]));
+        }
+
+        #[test]
+        fn deleting_profiles() {
+            let mut profiles = known(&["wg0", "wg1"]);
+            let changes = translate(&mut profiles, vec![event(libc::IN_DELETE, 0, "wg0.conf")]);
+            assert_eq!(changes, [removed("wg0")]);
+            // Deleting something that was never a known profile reports nothing.
+            assert!(translate(&mut profiles, vec![event(libc::IN_DELETE, 0, "wg9.conf")]).is_empty());
+            assert_eq!(profiles, known(&["wg1"]));
+        }
+
+        #[test]
+        fn moves_within_into_and_out_of_the_directory() {
+            let mut profiles = known(&["wg0", "wg1"]);
+            let events = vec![
+                event(libc::IN_MOVED_FROM, 1, "wg0.conf"),
+                event(libc::IN_MOVED_TO, 1, "home.conf"),
+                event(libc::IN_MOVED_TO, 2, "work.conf"),
+                event(libc::IN_MOVED_FROM, 3, "wg1.conf"),
+            ];
+            let changes = translate(&mut profiles, events);
+            assert_eq!(
+                changes,
+                [
+                    ProfileChange::Renamed { from: "wg0".into(), to: "home".into() },
+                    added("work"),
+                    removed("wg1"),
+                ]
+            );
+            assert_eq!(profiles, known(&["home", "work"]));
+
+            // Moving a temporary file over a profile, as atomic saves do, rewrites it.
+            let events = vec![event(libc::IN_MOVED_FROM, 4, "home.conf.tmp"), event(libc::IN_MOVED_TO, 4, "home.conf")];
+            assert_eq!(translate(&mut profiles, events), [ProfileChange::Modified("home".into())]);
+        }
+
+        #[test]
+        fn other_files_and_directories_are_ignored() {
+            let mut profiles = known(&["wg0"]);
+            let events = vec![
+                event(libc::IN_CREATE, 0, "notes.txt"),
+                event(libc::IN_CLOSE_WRITE, 0, "wg0.conf.swp"),
+                event(libc::IN_CREATE | libc::IN_ISDIR, 0, "backup.conf"),
+                event(libc::IN_CLOSE_WRITE, 0, ".conf"),
+                event(libc::IN_MOVED_FROM, 5, "wg0.conf~"),
+            ];
+            assert!(translate(&mut profiles, events).is_empty());
+            assert_eq!(profiles, known(&["wg0"]));
+        }
+    }
+}
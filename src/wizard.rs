@@ -0,0 +1,593 @@
+//! The new-profile wizard: an interface form, one form per peer and a review
+//! step that writes the new config file.
+
+use std::path::Path;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, Paragraph},
+    Frame,
+};
+
+use rustguard::config::{parse_keepalive, Config, SectionKind};
+use rustguard::keys::{self, KeyPair};
+use rustguard::profiles::{create_profile, profile_path};
+use rustguard::validate::{
+    self, check_cidr, check_dns, check_endpoint, check_interface_name, check_key, check_port,
+};
+
+use crate::highlight;
+
+/// Width of the label column of the forms.
+const LABEL_WIDTH: usize = 22;
+
+/// Stand-in for the private key in the review, so it is never shown on screen.
+const HIDDEN_KEY: &str = "(generated, hidden)";
+
+/// What a form field holds, which decides how it is checked.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FieldKind {
+    InterfaceName,
+    Addresses,
+    Dns,
+    ListenPort,
+    PeerName,
+    PublicKey,
+    PresharedKey,
+    Endpoint,
+    AllowedIps,
+    Keepalive,
+}
+
+/// A single line of a form.
+#[derive(Clone, Debug)]
+struct Field {
+    kind: FieldKind,
+    label: &'static str,
+    hint: &'static str,
+    value: String,
+    error: Option<String>,
+}
+
+impl Field {
+    fn new(kind: FieldKind, label: &'static str, hint: &'static str) -> Self {
+        Self { kind, label, hint, value: String::new(), error: None }
+    }
+
+    fn with_value(mut self, value: &str) -> Self {
+        self.value = value.to_string();
+        self
+    }
+
+    /// Check the value, storing and returning the error if there is one.
+    fn check(&mut self) -> bool {
+        let value = self.value.trim();
+        let result = match self.kind {
+            FieldKind::InterfaceName => check_interface_name(value).and_then(|_| {
+                if Path::new(&profile_path(value)).exists() {
+                    Err(format!("profile {} already exists", value))
+                } else {
+                    Ok(())
+                }
+            }),
+            FieldKind::Addresses | FieldKind::AllowedIps if value.is_empty() => Err("required".into()),
+            FieldKind::Addresses | FieldKind::AllowedIps => check_each(value, check_cidr),
+            FieldKind::Dns => check_each(value, check_dns),
+            FieldKind::PublicKey if value.is_empty() => Err("required".into()),
+            FieldKind::PublicKey | FieldKind::PresharedKey => check_key(value),
+            FieldKind::ListenPort => check_port(value, false),
+            FieldKind::Endpoint => check_endpoint(value),
+            FieldKind::Keepalive => parse_keepalive(value)
+                .map(|_| ())
+                .ok_or_else(|| "must be a number of seconds (0-65535) or \"off\"".into()),
+            FieldKind::PeerName => Ok(()),
+        };
+        // Optional fields may be left empty.
+        self.error = match result {
+            Err(_) if value.is_empty() && !self.required() => None,
+            Err(e) => Some(e),
+            Ok(()) => None,
+        };
+        self.error.is_none()
+    }
+
+    fn required(&self) -> bool {
+        matches!(
+            self.kind,
+            FieldKind::InterfaceName | FieldKind::Addresses | FieldKind::PublicKey | FieldKind::AllowedIps
+        )
+    }
+}
+
+fn check_each(value: &str, check: fn(&str) -> Result<(), String>) -> Result<(), String> {
+    for item in value.split(',').map(str::trim) {
+        if item.is_empty() {
+            return Err("empty list entry".into());
+        }
+        check(item)?;
+    }
+    Ok(())
+}
+
+/// The trimmed value of the field of the given kind, if it is not empty.
+fn field_value(fields: &[Field], kind: FieldKind) -> Option<&str> {
+    fields
+        .iter()
+        .find(|f| f.kind == kind)
+        .map(|f| f.value.trim())
+        .filter(|v| !v.is_empty())
+}
+
+fn interface_fields() -> Vec<Field> {
+    vec![
+        Field::new(FieldKind::InterfaceName, "Profile name", "e.g. wg0; also the interface name"),
+        Field::new(FieldKind::Addresses, "Address", "e.g. 10.0.0.2/24, fd00::2/64"),
+        Field::new(FieldKind::Dns, "DNS", "optional, e.g. 1.1.1.1"),
+        Field::new(FieldKind::ListenPort, "ListenPort", "optional, e.g. 51820"),
+    ]
+}
+
+fn peer_fields() -> Vec<Field> {
+    vec![
+        Field::new(FieldKind::PeerName, "Name", "optional label, e.g. server"),
+        Field::new(FieldKind::PublicKey, "PublicKey", "leave empty to finish without this peer"),
+        Field::new(FieldKind::PresharedKey, "PresharedKey", "optional; Ctrl+G generates one"),
+        Field::new(FieldKind::Endpoint, "Endpoint", "optional, e.g. vpn.example.com:51820"),
+        Field::new(FieldKind::AllowedIps, "AllowedIPs", "e.g. 0.0.0.0/0, ::/0").with_value("0.0.0.0/0, ::/0"),
+        Field::new(FieldKind::Keepalive, "PersistentKeepalive", "optional, e.g. 25"),
+    ]
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Step {
+    Interface,
+    Peer(usize),
+    Review,
+}
+
+/// State of the new-profile wizard.
+pub struct WizardState {
+    step: Step,
+    interface: Vec<Field>,
+    peers: Vec<Vec<Field>>,
+    /// Index of the focused field of the current form.
+    focus: usize,
+    keys: Result<KeyPair, String>,
+    /// Set once the profile has been written.
+    pub created: Option<String>,
+    message: Option<String>,
+}
+
+impl WizardState {
+    pub fn new() -> Self {
+        Self {
+            step: Step::Interface,
+            interface: interface_fields(),
+            peers: Vec::new(),
+            focus: 0,
+            keys: keys::generate_key_pair(),
+            created: None,
+            message: None,
+        }
+    }
+
+    /// Name of the profile being created.
+    pub fn profile(&self) -> &str {
+        self.interface[0].value.trim()
+    }
+
+    /// Public key of the new profile, to hand out to its peers.
+    pub fn public_key(&self) -> Option<&str> {
+        self.keys.as_ref().ok().map(|pair| pair.public_key.as_str())
+    }
+
+    fn fields_mut(&mut self) -> &mut [Field] {
+        match self.step {
+            Step::Interface => &mut self.interface,
+            Step::Peer(i) => &mut self.peers[i],
+            Step::Review => &mut [],
+        }
+    }
+
+    /// The config the wizard will write, with `private_key` as the PrivateKey.
+    fn build_config(&self, private_key: &str) -> Config {
+        let mut config = Config::new();
+        let interface = config.add_section(SectionKind::Interface);
+        interface.set("PrivateKey", private_key);
+        for (kind, key) in [
+            (FieldKind::Addresses, "Address"),
+            (FieldKind::Dns, "DNS"),
+            (FieldKind::ListenPort, "ListenPort"),
+        ] {
+            if let Some(v) = field_value(&self.interface, kind) {
+                interface.set(key, v);
+            }
+        }
+        for fields in &self.peers {
+            let peer = config.add_section(SectionKind::Peer);
+            for (kind, key) in [
+                (FieldKind::PublicKey, "PublicKey"),
+                (FieldKind::PresharedKey, "PresharedKey"),
+                (FieldKind::Endpoint, "Endpoint"),
+                (FieldKind::AllowedIps, "AllowedIPs"),
+                (FieldKind::Keepalive, "PersistentKeepalive"),
+            ] {
+                if let Some(v) = field_value(fields, kind) {
+                    peer.set(key, v);
+                }
+            }
+            if let Some(name) = field_value(fields, FieldKind::PeerName) {
+                peer.set_friendly_name(name);
+            }
+        }
+        config
+    }
+
+    /// Enter on a form: check it and move on to the next step.
+    fn advance(&mut self) {
+        match self.step {
+            Step::Interface => {
+                if self.check_form() {
+                    if self.peers.is_empty() {
+                        self.peers.push(peer_fields());
+                    }
+                    self.goto(Step::Peer(0));
+                }
+            }
+            Step::Peer(i) => {
+                if field_value(&self.peers[i], FieldKind::PublicKey).is_none() {
+                    self.peers.remove(i);
+                    self.goto(Step::Review);
+                } else if self.check_form() {
+                    if i + 1 < self.peers.len() {
+                        self.goto(Step::Peer(i + 1));
+                    } else {
+                        self.goto(Step::Review);
+                    }
+                }
+            }
+            Step::Review => {}
+        }
+    }
+
+    /// Check every field of the current form, focusing the first invalid one.
+    fn check_form(&mut self) -> bool {
+        let mut first_error = None;
+        for (i, field) in self.fields_mut().iter_mut().enumerate() {
+            if !field.check() && first_error.is_none() {
+                first_error = Some(i);
+            }
+        }
+        if let Some(i) = first_error {
+            self.focus = i;
+        }
+        first_error.is_none()
+    }
+
+    fn goto(&mut self, step: Step) {
+        self.step = step;
+        self.focus = 0;
+    }
+
+    fn back(&mut self) -> bool {
+        match self.step {
+            Step::Interface => return true,
+            Step::Peer(0) => self.goto(Step::Interface),
+            Step::Peer(i) => self.goto(Step::Peer(i - 1)),
+            Step::Review if self.peers.is_empty() => self.goto(Step::Interface),
+            Step::Review => self.goto(Step::Peer(self.peers.len() - 1)),
+        }
+        false
+    }
+
+    /// Validate the whole config and write it.
+    fn create(&mut self) -> bool {
+        let pair = match &self.keys {
+            Ok(pair) => pair,
+            Err(e) => {
+                self.message = Some(e.clone());
+                return false;
+            }
+        };
+        let config = self.build_config(&pair.private_key);
+        let profile = self.profile().to_string();
+        let diagnostics = validate::validate(&profile, &config);
+        if validate::has_errors(&diagnostics) {
+            self.message = Some("Fix the errors listed below before creating the profile.".into());
+            return false;
+        }
+        match create_profile(&profile, &config.to_string(), "Created with the new-profile wizard") {
+            Ok(_) => {
+                self.created = Some(profile);
+                true
+            }
+            Err(e) => {
+                self.message = Some(e);
+                false
+            }
+        }
+    }
+
+    /// Handle a key press. Returns true when the wizard is finished, either
+    /// because the profile was created (see [`WizardState::created`]) or cancelled.
+    pub fn handle_event(&mut self, key: KeyEvent) -> bool {
+        self.message = None;
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        if self.step == Step::Review {
+            return match key.code {
+                KeyCode::Esc => self.back(),
+                KeyCode::Char('a') => {
+                    self.peers.push(peer_fields());
+                    self.goto(Step::Peer(self.peers.len() - 1));
+                    false
+                }
+                KeyCode::Enter => self.create(),
+                KeyCode::Char('s') if ctrl => self.create(),
+                _ => false,
+            };
+        }
+
+        let focus = self.focus;
+        let count = self.fields_mut().len();
+        match key.code {
+            KeyCode::Esc => return self.back(),
+            KeyCode::Enter => self.advance(),
+            KeyCode::Tab | KeyCode::Down => self.focus = (focus + 1) % count,
+            KeyCode::BackTab | KeyCode::Up => self.focus = (focus + count - 1) % count,
+            KeyCode::Char('g') if ctrl => match self.step {
+                Step::Interface => self.keys = keys::generate_key_pair(),
+                Step::Peer(i) => match keys::generate_preshared_key() {
+                    Ok(psk) => {
+                        if let Some(field) = self.peers[i].iter_mut().find(|f| f.kind == FieldKind::PresharedKey) {
+                            field.value = psk;
+                        }
+                    }
+                    Err(e) => self.message = Some(e),
+                },
+                Step::Review => {}
+            },
+            KeyCode::Char('u') if ctrl => self.fields_mut()[focus].value.clear(),
+            KeyCode::Char(c) if !ctrl => self.fields_mut()[focus].value.push(c),
+            KeyCode::Backspace => {
+                self.fields_mut()[focus].value.pop();
+            }
+            _ => {}
+        }
+        false
+    }
+}
+
+fn form_lines(fields: &[Field], focus: usize) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    for (i, field) in fields.iter().enumerate() {
+        let focused = i == focus;
+        let label_style = if focused {
+            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::Cyan)
+        };
+        let mut spans = vec![
+            Span::styled(if focused { "> " } else { "  " }, label_style),
+            Span::styled(format!("{:<width$}", field.label, width = LABEL_WIDTH), label_style),
+            Span::raw(field.value.clone()),
+        ];
+        if focused {
+            spans.push(Span::styled(" ", Style::default().add_modifier(Modifier::REVERSED)));
+        }
+        if field.value.is_empty() {
+            spans.push(Span::styled(format!(" {}", field.hint), Style::default().fg(Color::DarkGray)));
+        }
+        lines.push(Line::from(spans));
+        if let Some(error) = &field.error {
+            lines.push(Line::from(Span::styled(
+                format!("  {:<width$}└ {}", "", error, width = LABEL_WIDTH),
+                Style::default().fg(Color::Red),
+            )));
+        }
+    }
+    lines
+}
+
+/// Draw the wizard.
+pub fn draw(f: &mut Frame, area: Rect, state: &WizardState) {
+    let (title, help) = match state.step {
+        Step::Interface => (
+            " New Profile: Interface (1/3) ".to_string(),
+            "Tab/↑/↓: Field | Enter: Next | Ctrl+G: New key pair | Ctrl+U: Clear | Esc: Cancel",
+        ),
+        Step::Peer(i) => (
+            format!(" New Profile: Peer {} of {} (2/3) ", i + 1, state.peers.len()),
+            "Tab/↑/↓: Field | Enter: Next | Ctrl+G: Generate PresharedKey | Ctrl+U: Clear | Esc: Back",
+        ),
+        Step::Review => (
+            " New Profile: Review (3/3) ".to_string(),
+            "Enter: Create profile | A: Add another peer | Esc: Back",
+        ),
+    };
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(5), Constraint::Length(3)].as_ref())
+        .split(area);
+
+    let key_line = match &state.keys {
+        Ok(pair) => Line::from(vec![
+            Span::styled("Public key: ", Style::default().fg(Color::Cyan)),
+            Span::raw(pair.public_key.clone()),
+            Span::styled("  (give this to your peers)", Style::default().fg(Color::DarkGray)),
+        ]),
+        Err(e) => Line::from(Span::styled(e.clone(), Style::default().fg(Color::Red))),
+    };
+    f.render_widget(
+        Paragraph::new(key_line).block(Block::default().title(title).borders(Borders::ALL)),
+        chunks[0],
+    );
+
+    match state.step {
+        Step::Interface | Step::Peer(_) => {
+            let fields = match state.step {
+                Step::Peer(i) => &state.peers[i],
+                _ => &state.interface,
+            };
+            let form = Paragraph::new(form_lines(fields, state.focus)).block(Block::default().borders(Borders::ALL));
+            f.render_widget(form, chunks[1]);
+        }
+        Step::Review => {
+            let preview = state.build_config(HIDDEN_KEY).to_string();
+            let lines: Vec<String> = preview.lines().map(str::to_string).collect();
+            let private_key = state.keys.as_ref().map(|pair| pair.private_key.as_str()).unwrap_or("");
+            let diagnostics = validate::validate(state.profile(), &state.build_config(private_key));
+            let halves = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(3), Constraint::Length(diagnostics.len().clamp(1, 5) as u16 + 2)].as_ref())
+                .split(chunks[1]);
+            let text: Vec<Line> = highlight::highlight(&lines).into_iter().map(Line::from).collect();
+            f.render_widget(
+                Paragraph::new(text).block(
+                    Block::default()
+                        .title(format!(" {} ", profile_path(state.profile())))
+                        .borders(Borders::ALL),
+                ),
+                halves[0],
+            );
+            let items: Vec<ListItem> = if diagnostics.is_empty() {
+                vec![ListItem::new("No problems found").style(Style::default().fg(Color::Green))]
+            } else {
+                diagnostics
+                    .iter()
+                    .map(|d| {
+                        let color = if d.is_error() { Color::Red } else { Color::Yellow };
+                        ListItem::new(d.to_string()).style(Style::default().fg(color))
+                    })
+                    .collect()
+            };
+            f.render_widget(
+                List::new(items).block(Block::default().title(" Diagnostics ").borders(Borders::ALL)),
+                halves[1],
+            );
+        }
+    }
+
+    let footer = match &state.message {
+        Some(message) => Paragraph::new(message.clone()).style(Style::default().fg(Color::Red)),
+        None => Paragraph::new(help),
+    };
+    f.render_widget(footer.block(Block::default().borders(Borders::ALL)), chunks[2]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustguard::testutil::{TestDirs, OTHER_PEER_KEY, PEER_KEY};
+
+    const THIRD_PEER_KEY: &str = "CPxGwq8zERHQ3Q1cOZFdJ+cvJX5Ka4mLN38AyYKYF10=";
+
+    fn press(wizard: &mut WizardState, code: KeyCode) -> bool {
+        wizard.handle_event(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    fn ctrl(wizard: &mut WizardState, c: char) {
+        wizard.handle_event(KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL));
+    }
+
+    fn type_text(wizard: &mut WizardState, text: &str) {
+        for c in text.chars() {
+            press(wizard, KeyCode::Char(c));
+        }
+    }
+
+    /// Focus the field of the given kind of the current form.
+    fn focus(wizard: &mut WizardState, kind: FieldKind) {
+        let index = wizard.fields_mut().iter().position(|field| field.kind == kind).unwrap();
+        while wizard.focus != index {
+            press(wizard, KeyCode::Down);
+        }
+    }
+
+    /// A wizard on the review step, with a peer per key.
+    fn wizard(peer_keys: &[&str]) -> WizardState {
+        let mut wizard = WizardState::new();
+        type_text(&mut wizard, "wg0");
+        focus(&mut wizard, FieldKind::Addresses);
+        type_text(&mut wizard, "10.0.0.1/24");
+        press(&mut wizard, KeyCode::Enter);
+        for (i, key) in peer_keys.iter().enumerate() {
+            if i > 0 {
+                press(&mut wizard, KeyCode::Char('a'));
+            }
+            assert_eq!(wizard.step, Step::Peer(i));
+            focus(&mut wizard, FieldKind::PublicKey);
+            type_text(&mut wizard, key);
+            focus(&mut wizard, FieldKind::AllowedIps);
+            ctrl(&mut wizard, 'u');
+            type_text(&mut wizard, &format!("10.0.0.{}/32", i + 2));
+            press(&mut wizard, KeyCode::Enter);
+        }
+        wizard
+    }
+
+    fn peer_keys(wizard: &WizardState) -> Vec<&str> {
+        wizard.peers.iter().filter_map(|fields| field_value(fields, FieldKind::PublicKey)).collect()
+    }
+
+    #[test]
+    fn an_empty_public_key_finishes_without_the_peer() {
+        let _dirs = TestDirs::new();
+        let mut wizard = wizard(&[]);
+        assert_eq!(wizard.step, Step::Peer(0));
+        press(&mut wizard, KeyCode::Enter);
+        assert_eq!(wizard.step, Step::Review);
+        assert!(wizard.peers.is_empty());
+        press(&mut wizard, KeyCode::Esc);
+        assert_eq!(wizard.step, Step::Interface);
+    }
+
+    #[test]
+    fn clearing_a_middle_peers_key_removes_only_that_peer() {
+        let _dirs = TestDirs::new();
+        let mut wizard = wizard(&[PEER_KEY, OTHER_PEER_KEY, THIRD_PEER_KEY]);
+        assert_eq!(wizard.step, Step::Review);
+        press(&mut wizard, KeyCode::Esc);
+        assert_eq!(wizard.step, Step::Peer(2));
+        press(&mut wizard, KeyCode::Esc);
+        assert_eq!(wizard.step, Step::Peer(1));
+
+        focus(&mut wizard, FieldKind::PublicKey);
+        ctrl(&mut wizard, 'u');
+        press(&mut wizard, KeyCode::Enter);
+        assert_eq!(wizard.step, Step::Review);
+        assert_eq!(peer_keys(&wizard), [PEER_KEY, THIRD_PEER_KEY]);
+        press(&mut wizard, KeyCode::Esc);
+        assert_eq!(wizard.step, Step::Peer(1));
+    }
+
+    #[test]
+    fn invalid_fields_keep_the_form_open() {
+        let _dirs = TestDirs::new();
+        let mut wizard = wizard(&[]);
+        focus(&mut wizard, FieldKind::PublicKey);
+        type_text(&mut wizard, "not a key");
+        press(&mut wizard, KeyCode::Enter);
+        assert_eq!(wizard.step, Step::Peer(0));
+        assert_eq!(wizard.focus, 1);
+        assert!(wizard.peers[0][1].error.is_some());
+    }
+
+    #[test]
+    fn the_built_config_is_valid_and_created() {
+        let _dirs = TestDirs::new();
+        let mut wizard = wizard(&[PEER_KEY, OTHER_PEER_KEY]);
+        let private_key = wizard.keys.clone().unwrap().private_key;
+        let config = wizard.build_config(&private_key);
+        assert_eq!(validate::validate("wg0", &config), []);
+        assert_eq!(config.peers().count(), 2);
+
+        assert!(press(&mut wizard, KeyCode::Enter));
+        assert_eq!(wizard.created.as_deref(), Some("wg0"));
+        let saved = Config::load(profile_path("wg0")).unwrap();
+        assert_eq!(saved.to_string(), config.to_string());
+    }
+}